@@ -4,6 +4,8 @@ use {
     solana_program_error::ProgramError,
     solana_program_memory::sol_memset,
     solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    solana_sdk_ids::system_program,
     std::{
         cell::{Ref, RefCell, RefMut},
         fmt,
@@ -196,6 +198,115 @@ impl<'a> AccountInfo<'a> {
         }
     }
 
+    /// Move `lamports` from this account directly to `to`, without a
+    /// cross-program invocation.
+    ///
+    /// The runtime only allows a program to debit accounts that it owns, and
+    /// both accounts must be writable. Transferring to the same account is a
+    /// no-op once the balance has been checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProgramError::Immutable`] if either account is not writable,
+    /// [`ProgramError::InsufficientFunds`] if this account holds fewer than
+    /// `lamports`, [`ProgramError::ArithmeticOverflow`] if `to` would overflow,
+    /// and [`ProgramError::AccountBorrowFailed`] if either balance is already
+    /// borrowed. No balance is modified on error.
+    pub fn checked_transfer_lamports(
+        &self,
+        to: &AccountInfo<'a>,
+        lamports: u64,
+    ) -> Result<(), ProgramError> {
+        if !self.is_writable || !to.is_writable {
+            return Err(ProgramError::Immutable);
+        }
+        if Rc::ptr_eq(&self.lamports, &to.lamports) || self.key == to.key {
+            return if self.try_lamports()? >= lamports {
+                Ok(())
+            } else {
+                Err(ProgramError::InsufficientFunds)
+            };
+        }
+
+        let mut from_lamports = self.try_borrow_mut_lamports()?;
+        let mut to_lamports = to.try_borrow_mut_lamports()?;
+        let new_from_lamports = from_lamports
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        let new_to_lamports = to_lamports
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **from_lamports = new_from_lamports;
+        **to_lamports = new_to_lamports;
+        Ok(())
+    }
+
+    /// Move `lamports` from this account to `to`, leaving this account rent
+    /// exempt under `rent`.
+    ///
+    /// This is [`checked_transfer_lamports`] with the additional requirement
+    /// that the remaining balance covers [`Rent::minimum_balance`] for the
+    /// account's current data length. The minimum is enforced even when the
+    /// data is empty, so this never drains an account; use
+    /// [`close`](AccountInfo::close) to move all of its lamports instead.
+    ///
+    /// [`checked_transfer_lamports`]: AccountInfo::checked_transfer_lamports
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProgramError::InsufficientFunds`] if the withdrawal would
+    /// leave this account below the rent-exempt minimum, in addition to the
+    /// errors returned by [`checked_transfer_lamports`].
+    pub fn checked_withdraw_excess_lamports(
+        &self,
+        to: &AccountInfo<'a>,
+        lamports: u64,
+        rent: &Rent,
+    ) -> Result<(), ProgramError> {
+        let minimum_balance = rent.minimum_balance(self.try_data_len()?);
+        let excess_lamports = self.try_lamports()?.saturating_sub(minimum_balance);
+        if lamports > excess_lamports {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        self.checked_transfer_lamports(to, lamports)
+    }
+
+    /// Close this account into `destination`.
+    ///
+    /// All lamports are moved to `destination`, the account data is zeroed
+    /// and truncated, and ownership is returned to the System program. Zeroing
+    /// the data before truncating it ensures that a closed account cannot be
+    /// revived within the same transaction with its old contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProgramError::InvalidArgument`] if `destination` is this
+    /// account, in addition to the errors returned by
+    /// [`checked_transfer_lamports`] and [`realloc`].
+    ///
+    /// [`checked_transfer_lamports`]: AccountInfo::checked_transfer_lamports
+    /// [`realloc`]: AccountInfo::realloc
+    ///
+    /// # Safety
+    ///
+    /// As with [`realloc`], this method should only be called for instances
+    /// of `AccountInfo` that were created by the runtime and received in the
+    /// `process_instruction` entrypoint of a program.
+    pub fn close(&self, destination: &AccountInfo<'a>) -> Result<(), ProgramError> {
+        if Rc::ptr_eq(&self.lamports, &destination.lamports) || self.key == destination.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.checked_transfer_lamports(destination, self.try_lamports()?)?;
+        {
+            let mut data = self.try_borrow_mut_data()?;
+            let data_len = data.len();
+            sol_memset(&mut data, 0, data_len);
+        }
+        self.realloc(0, false)?;
+        self.assign(&system_program::id());
+        Ok(())
+    }
+
     pub fn new(
         key: &'a Pubkey,
         is_signer: bool,
@@ -439,6 +550,169 @@ mod tests {
         assert_eq!(k5, *info5.key);
     }
 
+    #[test]
+    fn test_checked_transfer_lamports() {
+        let (from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut from_lamports, mut to_lamports) = (100, u64::MAX - 10);
+        let (from_data, to_data) = (&mut [0u8], &mut [0u8]);
+        let from = AccountInfo::new(
+            &from_key,
+            false,
+            true,
+            &mut from_lamports,
+            from_data,
+            &from_key,
+            false,
+            0,
+        );
+        let to = AccountInfo::new(
+            &to_key,
+            false,
+            true,
+            &mut to_lamports,
+            to_data,
+            &to_key,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            from.checked_transfer_lamports(&to, 101),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(
+            from.checked_transfer_lamports(&to, 11),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+        assert_eq!(from.lamports(), 100);
+        assert_eq!(to.lamports(), u64::MAX - 10);
+
+        assert_eq!(from.checked_transfer_lamports(&to, 10), Ok(()));
+        assert_eq!(from.lamports(), 90);
+        assert_eq!(to.lamports(), u64::MAX);
+
+        assert_eq!(from.checked_transfer_lamports(&from.clone(), 90), Ok(()));
+        assert_eq!(
+            from.checked_transfer_lamports(&from, 91),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(from.lamports(), 90);
+
+        let read_only = AccountInfo {
+            is_writable: false,
+            ..to.clone()
+        };
+        assert_eq!(
+            from.checked_transfer_lamports(&read_only, 1),
+            Err(ProgramError::Immutable)
+        );
+
+        let _borrowed = to.try_borrow_lamports().unwrap();
+        assert_eq!(
+            from.checked_transfer_lamports(&to, 1),
+            Err(ProgramError::AccountBorrowFailed)
+        );
+    }
+
+    #[test]
+    fn test_checked_withdraw_excess_lamports() {
+        let rent = Rent::default();
+        let (from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let from_data = &mut [0u8; 10];
+        let minimum_balance = rent.minimum_balance(from_data.len());
+        let (mut from_lamports, mut to_lamports) = (minimum_balance + 5, 0);
+        let from = AccountInfo::new(
+            &from_key,
+            false,
+            true,
+            &mut from_lamports,
+            from_data,
+            &from_key,
+            false,
+            0,
+        );
+        let to = AccountInfo::new(
+            &to_key,
+            false,
+            true,
+            &mut to_lamports,
+            &mut [],
+            &to_key,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            from.checked_withdraw_excess_lamports(&to, 6, &rent),
+            Err(ProgramError::InsufficientFunds)
+        );
+        assert_eq!(from.checked_withdraw_excess_lamports(&to, 5, &rent), Ok(()));
+        assert_eq!(from.lamports(), minimum_balance);
+        assert_eq!(to.lamports(), 5);
+    }
+
+    #[test]
+    fn test_close() {
+        // Mimic the serialized input layout that `realloc` relies on: the
+        // original data length precedes the key and the current data length
+        // precedes the data.
+        #[repr(C)]
+        struct SerializedKey {
+            original_data_len: u32,
+            key: Pubkey,
+        }
+        #[repr(C)]
+        struct SerializedData {
+            data_len: u64,
+            data: [u8; 8],
+        }
+
+        let key = SerializedKey {
+            original_data_len: 8,
+            key: Pubkey::new_unique(),
+        };
+        let mut data = SerializedData {
+            data_len: 8,
+            data: [7; 8],
+        };
+        let owner = Pubkey::new_unique();
+        let destination_key = Pubkey::new_unique();
+        let (mut lamports, mut destination_lamports) = (42, 1);
+        let account = AccountInfo::new(
+            &key.key,
+            false,
+            true,
+            &mut lamports,
+            &mut data.data,
+            &owner,
+            false,
+            0,
+        );
+        let destination = AccountInfo::new(
+            &destination_key,
+            false,
+            true,
+            &mut destination_lamports,
+            &mut [],
+            &destination_key,
+            false,
+            0,
+        );
+
+        assert_eq!(account.close(&account), Err(ProgramError::InvalidArgument));
+        assert_eq!(account.close(&destination), Ok(()));
+        assert_eq!(account.lamports(), 0);
+        assert_eq!(destination.lamports(), 43);
+        assert!(account.data_is_empty());
+        assert_eq!(
+            unsafe { std::ptr::read_volatile(account.owner) },
+            system_program::id()
+        );
+        drop(account);
+        assert_eq!(data.data_len, 0);
+        assert_eq!(data.data, [0; 8]);
+    }
+
     #[test]
     fn test_account_info_as_ref() {
         let k = Pubkey::new_unique();
@@ -4,6 +4,9 @@
 use solana_frozen_abi_macro::{AbiEnumVisitor, AbiExample};
 use {solana_hash::Hash, std::str::FromStr};
 
+#[cfg(feature = "registry")]
+pub mod registry;
+
 // The order can't align with release lifecycle only to remain ABI-compatible...
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample, AbiEnumVisitor))]
 #[cfg_attr(
@@ -0,0 +1,393 @@
+//! A registry of clusters, keyed by genesis hash.
+//!
+//! [`ClusterType`] stays the representation stored in genesis configs and
+//! snapshots. The registry adds what a client needs to talk to a cluster that
+//! `ClusterType` can not name, such as the clusters of a fork, and can be
+//! loaded from a TOML file:
+//!
+//! ```toml
+//! [[clusters]]
+//! name = "x1-testnet"
+//! genesis_hash = "C7ucgdDEhxLTpXHhWSZxavSVmaNTUJWwT5iTdeaviDho"
+//! cluster_type = "Testnet"
+//! rpc_url = "https://rpc.testnet.x1.xyz"
+//! ticker = "XNT"
+//! hard_forks = [1_000, 2_000]
+//! ```
+
+use {
+    crate::ClusterType,
+    serde_derive::Deserialize,
+    solana_hard_forks::HardForks,
+    solana_hash::Hash,
+    std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr},
+};
+
+/// The ticker of the native token of the Solana clusters.
+pub const DEFAULT_TICKER: &str = "SOL";
+
+/// Reasons a registry can not be loaded.
+#[derive(Debug)]
+pub enum ClusterRegistryError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    /// Two clusters of the registry have the same name.
+    DuplicateName(String),
+    /// Two clusters of the registry have the same genesis hash.
+    DuplicateGenesisHash(Hash),
+}
+
+impl std::error::Error for ClusterRegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Toml(error) => Some(error),
+            Self::DuplicateName(_) | Self::DuplicateGenesisHash(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ClusterRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Toml(error) => write!(f, "invalid cluster registry: {error}"),
+            Self::DuplicateName(name) => write!(f, "cluster {name} is defined twice"),
+            Self::DuplicateGenesisHash(hash) => {
+                write!(f, "genesis hash {hash} is used by two clusters")
+            }
+        }
+    }
+}
+
+/// A cluster known to the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterInfo {
+    pub name: String,
+    pub genesis_hash: Hash,
+    pub cluster_type: ClusterType,
+    pub rpc_url: Option<String>,
+    /// Derived from the RPC URL when missing, see [`websocket_url`].
+    ///
+    /// [`websocket_url`]: ClusterInfo::websocket_url
+    pub websocket_url: Option<String>,
+    /// The ticker of the native token.
+    pub ticker: String,
+    pub hard_forks: HardForks,
+}
+
+impl ClusterInfo {
+    pub fn new(name: impl Into<String>, genesis_hash: Hash, cluster_type: ClusterType) -> Self {
+        Self {
+            name: name.into(),
+            genesis_hash,
+            cluster_type,
+            rpc_url: None,
+            websocket_url: None,
+            ticker: DEFAULT_TICKER.to_string(),
+            hard_forks: HardForks::default(),
+        }
+    }
+
+    /// The shred version of the cluster after its hard forks.
+    pub fn shred_version(&self) -> u16 {
+        solana_shred_version::compute_shred_version(&self.genesis_hash, Some(&self.hard_forks))
+    }
+
+    /// The websocket URL of the cluster. Unless set, it is derived from the
+    /// RPC URL the way the CLI does: `http` becomes `ws`, `https` becomes
+    /// `wss`, and an explicit port is incremented.
+    pub fn websocket_url(&self) -> Option<String> {
+        self.websocket_url
+            .clone()
+            .or_else(|| self.rpc_url.as_deref().and_then(websocket_url_from_rpc_url))
+    }
+}
+
+fn websocket_url_from_rpc_url(rpc_url: &str) -> Option<String> {
+    let (scheme, rest) = rpc_url.split_once("://")?;
+    let scheme = match scheme {
+        "http" => "ws",
+        "https" => "wss",
+        _ => return None,
+    };
+    let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    // An IPv6 host is enclosed in brackets and holds colons
+    let port_start = authority
+        .rfind(':')
+        .filter(|i| !authority[*i..].contains(']'));
+    let authority = match port_start {
+        Some(i) => {
+            let port = authority[i.saturating_add(1)..].parse::<u16>().ok()?;
+            format!("{}:{}", &authority[..i], port.checked_add(1)?)
+        }
+        None => authority.to_string(),
+    };
+    Some(format!("{scheme}://{authority}{path}"))
+}
+
+/// Deserializes a hash from its base58 string.
+fn deserialize_hash<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+    let hash = <String as serde::Deserialize>::deserialize(deserializer)?;
+    Hash::from_str(&hash)
+        .map_err(|error| serde::de::Error::custom(format_args!("invalid hash {hash}: {error}")))
+}
+
+/// A cluster as written in a registry file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClusterSpec {
+    name: String,
+    #[serde(deserialize_with = "deserialize_hash")]
+    genesis_hash: Hash,
+    #[serde(default = "default_cluster_type")]
+    cluster_type: ClusterType,
+    #[serde(default)]
+    rpc_url: Option<String>,
+    #[serde(default)]
+    websocket_url: Option<String>,
+    #[serde(default)]
+    ticker: Option<String>,
+    /// The slots of the hard forks; a slot forked more than once is listed
+    /// once per fork.
+    #[serde(default)]
+    hard_forks: Vec<u64>,
+}
+
+fn default_cluster_type() -> ClusterType {
+    ClusterType::Development
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegistrySpec {
+    #[serde(default)]
+    clusters: Vec<ClusterSpec>,
+}
+
+impl From<ClusterSpec> for ClusterInfo {
+    fn from(spec: ClusterSpec) -> Self {
+        let mut hard_forks = HardForks::default();
+        for slot in spec.hard_forks {
+            hard_forks.register(slot);
+        }
+        Self {
+            name: spec.name,
+            genesis_hash: spec.genesis_hash,
+            cluster_type: spec.cluster_type,
+            rpc_url: spec.rpc_url,
+            websocket_url: spec.websocket_url,
+            ticker: spec.ticker.unwrap_or_else(|| DEFAULT_TICKER.to_string()),
+            hard_forks,
+        }
+    }
+}
+
+/// Clusters keyed by genesis hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterRegistry {
+    clusters: BTreeMap<Hash, ClusterInfo>,
+}
+
+impl ClusterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the Solana clusters with a known genesis hash.
+    pub fn with_known_clusters() -> Self {
+        let mut registry = Self::new();
+        for (cluster_type, name, rpc_url) in [
+            (
+                ClusterType::MainnetBeta,
+                "mainnet-beta",
+                "https://api.mainnet-beta.solana.com",
+            ),
+            (
+                ClusterType::Testnet,
+                "testnet",
+                "https://api.testnet.solana.com",
+            ),
+            (
+                ClusterType::Devnet,
+                "devnet",
+                "https://api.devnet.solana.com",
+            ),
+        ] {
+            let genesis_hash = cluster_type.get_genesis_hash().unwrap();
+            let mut cluster = ClusterInfo::new(name, genesis_hash, cluster_type);
+            cluster.rpc_url = Some(rpc_url.to_string());
+            registry.register(cluster);
+        }
+        registry
+    }
+
+    pub fn from_toml_str(registry: &str) -> Result<Self, ClusterRegistryError> {
+        let spec: RegistrySpec = toml::from_str(registry).map_err(ClusterRegistryError::Toml)?;
+        let mut registry = Self::new();
+        registry.extend(spec.clusters.into_iter().map(ClusterInfo::from))?;
+        Ok(registry)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ClusterRegistryError> {
+        let registry = fs::read_to_string(path).map_err(ClusterRegistryError::Io)?;
+        Self::from_toml_str(&registry)
+    }
+
+    /// Adds clusters, failing if any of them reuses the name or the genesis
+    /// hash of another cluster.
+    pub fn extend(
+        &mut self,
+        clusters: impl IntoIterator<Item = ClusterInfo>,
+    ) -> Result<(), ClusterRegistryError> {
+        for cluster in clusters {
+            if self.clusters.contains_key(&cluster.genesis_hash) {
+                return Err(ClusterRegistryError::DuplicateGenesisHash(
+                    cluster.genesis_hash,
+                ));
+            }
+            if self.get_by_name(&cluster.name).is_some() {
+                return Err(ClusterRegistryError::DuplicateName(cluster.name));
+            }
+            self.register(cluster);
+        }
+        Ok(())
+    }
+
+    /// Adds a cluster, returning the cluster it replaces.
+    pub fn register(&mut self, cluster: ClusterInfo) -> Option<ClusterInfo> {
+        self.clusters.insert(cluster.genesis_hash, cluster)
+    }
+
+    pub fn get(&self, genesis_hash: &Hash) -> Option<&ClusterInfo> {
+        self.clusters.get(genesis_hash)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&ClusterInfo> {
+        self.clusters.values().find(|cluster| cluster.name == name)
+    }
+
+    /// The cluster type of the cluster with this genesis hash, which is
+    /// `Development` for unknown clusters.
+    pub fn cluster_type(&self, genesis_hash: &Hash) -> ClusterType {
+        self.get(genesis_hash)
+            .map_or(ClusterType::Development, |cluster| cluster.cluster_type)
+    }
+
+    /// The clusters, ordered by genesis hash.
+    pub fn iter(&self) -> impl Iterator<Item = &ClusterInfo> {
+        self.clusters.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clusters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clusters.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url() {
+        let mut cluster = ClusterInfo::new("local", Hash::new_unique(), ClusterType::Development);
+        assert_eq!(cluster.websocket_url(), None);
+        for (rpc_url, websocket_url) in [
+            ("http://127.0.0.1:8899", Some("ws://127.0.0.1:8900")),
+            ("https://rpc.x1.xyz/api", Some("wss://rpc.x1.xyz/api")),
+            ("http://[::1]:8899/", Some("ws://[::1]:8900/")),
+            ("http://[::1]", Some("ws://[::1]")),
+            ("http://localhost:65535", None),
+            ("ftp://localhost", None),
+        ] {
+            cluster.rpc_url = Some(rpc_url.to_string());
+            assert_eq!(
+                cluster.websocket_url().as_deref(),
+                websocket_url,
+                "{rpc_url}"
+            );
+        }
+        cluster.websocket_url = Some("ws://localhost:9000".to_string());
+        assert_eq!(
+            cluster.websocket_url().as_deref(),
+            Some("ws://localhost:9000")
+        );
+    }
+
+    #[test]
+    fn test_known_clusters() {
+        let registry = ClusterRegistry::with_known_clusters();
+        assert_eq!(registry.len(), 3);
+        let mainnet = registry.get_by_name("mainnet-beta").unwrap();
+        assert_eq!(mainnet.cluster_type, ClusterType::MainnetBeta);
+        assert_eq!(
+            Some(mainnet.genesis_hash),
+            ClusterType::MainnetBeta.get_genesis_hash()
+        );
+        assert_eq!(mainnet.ticker, DEFAULT_TICKER);
+        assert_eq!(
+            mainnet.websocket_url().as_deref(),
+            Some("wss://api.mainnet-beta.solana.com")
+        );
+        assert_eq!(
+            registry.cluster_type(&Hash::new_unique()),
+            ClusterType::Development
+        );
+    }
+
+    #[test]
+    fn test_from_toml_str() {
+        let genesis_hash = Hash::new_unique();
+        let registry = ClusterRegistry::from_toml_str(&format!(
+            r#"
+[[clusters]]
+name = "x1-testnet"
+genesis_hash = "{genesis_hash}"
+cluster_type = "Testnet"
+rpc_url = "https://rpc.testnet.x1.xyz"
+ticker = "XNT"
+hard_forks = [2_000, 1_000, 2_000]
+"#
+        ))
+        .unwrap();
+        let cluster = registry.get(&genesis_hash).unwrap();
+        assert_eq!(cluster.name, "x1-testnet");
+        assert_eq!(cluster.ticker, "XNT");
+        assert_eq!(registry.cluster_type(&genesis_hash), ClusterType::Testnet);
+        assert_eq!(
+            cluster.hard_forks.iter().copied().collect::<Vec<_>>(),
+            vec![(1_000, 1), (2_000, 2)]
+        );
+        assert_eq!(
+            cluster.shred_version(),
+            solana_shred_version::compute_shred_version(&genesis_hash, Some(&cluster.hard_forks))
+        );
+        assert_ne!(
+            cluster.shred_version(),
+            solana_shred_version::compute_shred_version(&genesis_hash, None)
+        );
+
+        let mut registry = ClusterRegistry::with_known_clusters();
+        let devnet = registry.get_by_name("devnet").unwrap().clone();
+        assert!(matches!(
+            registry.extend([ClusterInfo::new(
+                "devnet",
+                Hash::new_unique(),
+                ClusterType::Devnet
+            )]),
+            Err(ClusterRegistryError::DuplicateName(name)) if name == "devnet"
+        ));
+        assert!(matches!(
+            registry.extend([ClusterInfo::new("other", devnet.genesis_hash, ClusterType::Devnet)]),
+            Err(ClusterRegistryError::DuplicateGenesisHash(hash)) if hash == devnet.genesis_hash
+        ));
+        assert!(matches!(
+            ClusterRegistry::from_toml_str("[[clusters]]\nname = \"x\"\ngenesis_hash = \"x\""),
+            Err(ClusterRegistryError::Toml(_))
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+//! Typed cross-program invocations of the address lookup table program.
+
+use {
+    solana_account_info::AccountInfo,
+    solana_address_lookup_table_interface::{
+        instruction::ProgramInstruction,
+        program::{check_id, ID},
+    },
+    solana_clock::Slot,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+};
+
+/// Create an address lookup table.
+///
+/// The table address must be derived with
+/// [`derive_lookup_table_address`] from the authority and `recent_slot`.
+///
+/// [`derive_lookup_table_address`]: solana_address_lookup_table_interface::instruction::derive_lookup_table_address
+pub struct CreateLookupTable<'a, 'info> {
+    /// Uninitialized lookup table account.
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Authority of the lookup table.
+    pub authority: &'a AccountInfo<'info>,
+    /// Funding account, must sign.
+    pub payer: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// The address lookup table program.
+    pub address_lookup_table_program: &'a AccountInfo<'info>,
+    /// Recent slot used to derive the table address.
+    pub recent_slot: Slot,
+    /// Bump seed of the table address.
+    pub bump_seed: u8,
+}
+
+impl<'info> CreateLookupTable<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &ProgramInstruction::CreateLookupTable {
+                recent_slot: self.recent_slot,
+                bump_seed: self.bump_seed,
+            },
+            vec![
+                AccountMeta::new(*self.lookup_table.key, false),
+                AccountMeta::new_readonly(*self.authority.key, false),
+                AccountMeta::new(*self.payer.key, true),
+                AccountMeta::new_readonly(*self.system_program.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.payer.clone(),
+            self.system_program.clone(),
+            self.address_lookup_table_program.clone(),
+        ]
+    }
+}
+
+/// Freeze a lookup table, making it immutable.
+pub struct FreezeLookupTable<'a, 'info> {
+    /// Lookup table account.
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The address lookup table program.
+    pub address_lookup_table_program: &'a AccountInfo<'info>,
+}
+
+impl<'info> FreezeLookupTable<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &ProgramInstruction::FreezeLookupTable,
+            vec![
+                AccountMeta::new(*self.lookup_table.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.address_lookup_table_program.clone(),
+        ]
+    }
+}
+
+/// Extend a lookup table with new addresses, funded by `payer`.
+pub struct ExtendLookupTable<'a, 'info> {
+    /// Lookup table account.
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Funding account, must sign.
+    pub payer: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// The address lookup table program.
+    pub address_lookup_table_program: &'a AccountInfo<'info>,
+    /// Addresses to append to the table.
+    pub new_addresses: &'a [Pubkey],
+}
+
+impl<'info> ExtendLookupTable<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &ProgramInstruction::ExtendLookupTable {
+                new_addresses: self.new_addresses.to_vec(),
+            },
+            vec![
+                AccountMeta::new(*self.lookup_table.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new(*self.payer.key, true),
+                AccountMeta::new_readonly(*self.system_program.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.payer.clone(),
+            self.system_program.clone(),
+            self.address_lookup_table_program.clone(),
+        ]
+    }
+}
+
+/// Deactivate a lookup table so that it can be closed after cooldown.
+pub struct DeactivateLookupTable<'a, 'info> {
+    /// Lookup table account.
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The address lookup table program.
+    pub address_lookup_table_program: &'a AccountInfo<'info>,
+}
+
+impl<'info> DeactivateLookupTable<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &ProgramInstruction::DeactivateLookupTable,
+            vec![
+                AccountMeta::new(*self.lookup_table.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.address_lookup_table_program.clone(),
+        ]
+    }
+}
+
+/// Close a deactivated lookup table and withdraw its lamports.
+pub struct CloseLookupTable<'a, 'info> {
+    /// Lookup table account.
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Recipient of the closed account's lamports.
+    pub recipient: &'a AccountInfo<'info>,
+    /// The address lookup table program.
+    pub address_lookup_table_program: &'a AccountInfo<'info>,
+}
+
+impl<'info> CloseLookupTable<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &ProgramInstruction::CloseLookupTable,
+            vec![
+                AccountMeta::new(*self.lookup_table.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new(*self.recipient.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.recipient.clone(),
+            self.address_lookup_table_program.clone(),
+        ]
+    }
+}
+
+impl_invoke!(
+    address_lookup_table_program, check_id;
+    CreateLookupTable,
+    FreezeLookupTable,
+    ExtendLookupTable,
+    DeactivateLookupTable,
+    CloseLookupTable,
+);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_address_lookup_table_interface::instruction::{
+            close_lookup_table, create_lookup_table, extend_lookup_table,
+        },
+        solana_sdk_ids::system_program,
+    };
+
+    #[test]
+    fn test_instructions_match_interface() {
+        let authority_key = Pubkey::new_unique();
+        let payer_key = Pubkey::new_unique();
+        let system_program_key = system_program::id();
+        let (expected_create, lookup_table_key) = create_lookup_table(authority_key, payer_key, 42);
+        let keys = [
+            &lookup_table_key,
+            &authority_key,
+            &payer_key,
+            &system_program_key,
+            &ID,
+        ];
+        let mut lamports = [0; 5];
+        let mut data: [[u8; 0]; 5] = Default::default();
+        let infos = keys
+            .into_iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, true, lamports, data, &ID, false, 0)
+            })
+            .collect::<Vec<_>>();
+        let [lookup_table, authority, payer, system_program, address_lookup_table_program] =
+            &infos[..]
+        else {
+            unreachable!()
+        };
+
+        let (_, bump_seed) =
+            Pubkey::find_program_address(&[authority_key.as_ref(), &42u64.to_le_bytes()], &ID);
+        let create = CreateLookupTable {
+            lookup_table,
+            authority,
+            payer,
+            system_program,
+            address_lookup_table_program,
+            recent_slot: 42,
+            bump_seed,
+        };
+        assert_eq!(create.instruction(), expected_create);
+        assert_eq!(create.invoke(), Ok(()));
+
+        let new_addresses = [Pubkey::new_unique()];
+        let extend = ExtendLookupTable {
+            lookup_table,
+            authority,
+            payer,
+            system_program,
+            address_lookup_table_program,
+            new_addresses: &new_addresses,
+        };
+        assert_eq!(
+            extend.instruction(),
+            extend_lookup_table(
+                lookup_table_key,
+                authority_key,
+                Some(payer_key),
+                new_addresses.to_vec()
+            )
+        );
+
+        let close = CloseLookupTable {
+            lookup_table,
+            authority,
+            recipient: payer,
+            address_lookup_table_program,
+        };
+        assert_eq!(
+            close.instruction(),
+            close_lookup_table(lookup_table_key, authority_key, payer_key)
+        );
+    }
+}
@@ -8,10 +8,18 @@
 //! If you want to overwrite syscall stubs, use the wrapper functions in
 //! [`solana_program::program`].
 //!
+//! Typed invocations of the builtin programs are available behind the
+//! `system`, `loader-v3`, `loader-v4`, `vote` and `address-lookup-table`
+//! features. Each instruction is a struct holding the [`AccountInfo`]s the
+//! callee expects; the [`AccountMeta`]s are derived from those accounts, so
+//! the instruction and the accounts passed to the runtime cannot disagree.
+//!
 //! [`invoke`]: invoke
 //! [`invoke_signed`]: invoke_signed
 //! [cpi]: https://solana.com/docs/core/cpi
 //! [`solana_program::program`]: https://docs.rs/solana-program/latest/solana_program/program/
+//! [`AccountMeta`]: solana_instruction::AccountMeta
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
 use {
     solana_account_info::AccountInfo, solana_instruction::Instruction,
@@ -20,6 +28,63 @@ use {
 #[cfg(target_os = "solana")]
 pub mod syscalls;
 
+/// Implements `invoke` and `invoke_signed` for typed instruction structs.
+///
+/// Each struct must provide `instruction()` and `account_infos()`, and hold
+/// the callee program account in the named field, which is checked against
+/// the given `check_id` function before invoking.
+#[cfg(any(
+    feature = "address-lookup-table",
+    feature = "loader-v3",
+    feature = "loader-v4",
+    feature = "system",
+    feature = "vote",
+))]
+macro_rules! impl_invoke {
+    ($program:ident, $check_id:path; $($name:ident),+ $(,)?) => {
+        $(
+            impl<'info> $name<'_, 'info> {
+                /// Invoke the instruction.
+                pub fn invoke(&self) -> solana_program_error::ProgramResult {
+                    self.invoke_signed(&[])
+                }
+
+                /// Invoke the instruction, signing for program derived
+                /// addresses with `signers_seeds`.
+                ///
+                /// # Errors
+                ///
+                /// Returns [`ProgramError::IncorrectProgramId`] if the program
+                /// account is not the expected builtin program, in addition
+                /// to the errors returned by [`invoke_signed`].
+                ///
+                /// [`ProgramError::IncorrectProgramId`]: solana_program_error::ProgramError::IncorrectProgramId
+                /// [`invoke_signed`]: crate::invoke_signed
+                pub fn invoke_signed(
+                    &self,
+                    signers_seeds: &[&[&[u8]]],
+                ) -> solana_program_error::ProgramResult {
+                    if !$check_id(self.$program.key) {
+                        return Err(solana_program_error::ProgramError::IncorrectProgramId);
+                    }
+                    $crate::invoke_signed(&self.instruction(), &self.account_infos(), signers_seeds)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "address-lookup-table")]
+pub mod address_lookup_table;
+#[cfg(feature = "loader-v3")]
+pub mod loader_v3;
+#[cfg(feature = "loader-v4")]
+pub mod loader_v4;
+#[cfg(feature = "system")]
+pub mod system;
+#[cfg(feature = "vote")]
+pub mod vote;
+
 /// Invoke a cross-program instruction.
 ///
 /// Invoking one program from another program requires an [`Instruction`]
@@ -0,0 +1,487 @@
+//! Typed cross-program invocations of the upgradeable BPF loader.
+
+use {
+    solana_account_info::AccountInfo,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_loader_v3_interface::instruction::UpgradeableLoaderInstruction,
+    solana_sdk_ids::bpf_loader_upgradeable::{check_id, ID},
+};
+
+/// Write program data into a buffer account.
+pub struct Write<'a, 'info> {
+    /// Buffer account to write program data to.
+    pub buffer: &'a AccountInfo<'info>,
+    /// Buffer authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+    /// Offset at which to write the given bytes.
+    pub offset: u32,
+    /// Serialized program data.
+    pub bytes: &'a [u8],
+}
+
+impl<'info> Write<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::Write {
+                offset: self.offset,
+                bytes: self.bytes.to_vec(),
+            },
+            vec![
+                AccountMeta::new(*self.buffer.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.buffer.clone(),
+            self.authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Upgrade a program from the contents of a buffer account.
+pub struct Upgrade<'a, 'info> {
+    /// ProgramData account of the program.
+    pub programdata: &'a AccountInfo<'info>,
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Buffer account holding the new program data.
+    pub buffer: &'a AccountInfo<'info>,
+    /// Recipient of the buffer account's lamports.
+    pub spill: &'a AccountInfo<'info>,
+    /// Rent sysvar.
+    pub rent_sysvar: &'a AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock_sysvar: &'a AccountInfo<'info>,
+    /// Upgrade authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> Upgrade<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::Upgrade,
+            vec![
+                AccountMeta::new(*self.programdata.key, false),
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new(*self.buffer.key, false),
+                AccountMeta::new(*self.spill.key, false),
+                AccountMeta::new_readonly(*self.rent_sysvar.key, false),
+                AccountMeta::new_readonly(*self.clock_sysvar.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.programdata.clone(),
+            self.program.clone(),
+            self.buffer.clone(),
+            self.spill.clone(),
+            self.rent_sysvar.clone(),
+            self.clock_sysvar.clone(),
+            self.authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Set a new authority on a buffer account.
+pub struct SetBufferAuthority<'a, 'info> {
+    /// Buffer account.
+    pub buffer: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// New authority.
+    pub new_authority: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> SetBufferAuthority<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::SetAuthority,
+            vec![
+                AccountMeta::new(*self.buffer.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.new_authority.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.buffer.clone(),
+            self.authority.clone(),
+            self.new_authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Set a new authority on a buffer account, requiring the new authority to
+/// sign.
+pub struct SetBufferAuthorityChecked<'a, 'info> {
+    /// Buffer account.
+    pub buffer: &'a AccountInfo<'info>,
+    /// Current authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// New authority, must sign.
+    pub new_authority: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> SetBufferAuthorityChecked<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::SetAuthorityChecked,
+            vec![
+                AccountMeta::new(*self.buffer.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.new_authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.buffer.clone(),
+            self.authority.clone(),
+            self.new_authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Set a new upgrade authority on a program, or make it immutable.
+pub struct SetUpgradeAuthority<'a, 'info> {
+    /// ProgramData account of the program.
+    pub programdata: &'a AccountInfo<'info>,
+    /// Current upgrade authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// New upgrade authority. `None` makes the program immutable.
+    pub new_authority: Option<&'a AccountInfo<'info>>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> SetUpgradeAuthority<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        let mut account_metas = vec![
+            AccountMeta::new(*self.programdata.key, false),
+            AccountMeta::new_readonly(*self.authority.key, true),
+        ];
+        if let Some(new_authority) = self.new_authority {
+            account_metas.push(AccountMeta::new_readonly(*new_authority.key, false));
+        }
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::SetAuthority,
+            account_metas,
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut account_infos = vec![self.programdata.clone(), self.authority.clone()];
+        account_infos.extend(self.new_authority.cloned());
+        account_infos.push(self.loader.clone());
+        account_infos
+    }
+}
+
+/// Set a new upgrade authority on a program, requiring the new authority to
+/// sign.
+pub struct SetUpgradeAuthorityChecked<'a, 'info> {
+    /// ProgramData account of the program.
+    pub programdata: &'a AccountInfo<'info>,
+    /// Current upgrade authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// New upgrade authority, must sign.
+    pub new_authority: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> SetUpgradeAuthorityChecked<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::SetAuthorityChecked,
+            vec![
+                AccountMeta::new(*self.programdata.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.new_authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.programdata.clone(),
+            self.authority.clone(),
+            self.new_authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Close a buffer, program data or uninitialized account and withdraw its
+/// lamports.
+pub struct Close<'a, 'info> {
+    /// Account to close.
+    pub account: &'a AccountInfo<'info>,
+    /// Recipient of the closed account's lamports.
+    pub recipient: &'a AccountInfo<'info>,
+    /// Authority of the closed account, must sign. Not required for
+    /// uninitialized accounts.
+    pub authority: Option<&'a AccountInfo<'info>>,
+    /// Program account, required when closing a program data account.
+    pub program: Option<&'a AccountInfo<'info>>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> Close<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        let mut account_metas = vec![
+            AccountMeta::new(*self.account.key, false),
+            AccountMeta::new(*self.recipient.key, false),
+        ];
+        if let Some(authority) = self.authority {
+            account_metas.push(AccountMeta::new_readonly(*authority.key, true));
+        }
+        if let Some(program) = self.program {
+            account_metas.push(AccountMeta::new(*program.key, false));
+        }
+        Instruction::new_with_bincode(ID, &UpgradeableLoaderInstruction::Close, account_metas)
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut account_infos = vec![self.account.clone(), self.recipient.clone()];
+        account_infos.extend(self.authority.cloned());
+        account_infos.extend(self.program.cloned());
+        account_infos.push(self.loader.clone());
+        account_infos
+    }
+}
+
+/// Extend a program's data account, funded by `payer`.
+pub struct ExtendProgramChecked<'a, 'info> {
+    /// ProgramData account of the program.
+    pub programdata: &'a AccountInfo<'info>,
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Upgrade authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The System program, used to fund the extension.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Funding account, must sign.
+    pub payer: &'a AccountInfo<'info>,
+    /// The upgradeable BPF loader.
+    pub loader: &'a AccountInfo<'info>,
+    /// Number of bytes to extend the program data by.
+    pub additional_bytes: u32,
+}
+
+impl<'info> ExtendProgramChecked<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &UpgradeableLoaderInstruction::ExtendProgramChecked {
+                additional_bytes: self.additional_bytes,
+            },
+            vec![
+                AccountMeta::new(*self.programdata.key, false),
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.system_program.key, false),
+                AccountMeta::new(*self.payer.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.programdata.clone(),
+            self.program.clone(),
+            self.authority.clone(),
+            self.system_program.clone(),
+            self.payer.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+impl_invoke!(
+    loader, check_id;
+    Write,
+    Upgrade,
+    SetBufferAuthority,
+    SetBufferAuthorityChecked,
+    SetUpgradeAuthority,
+    SetUpgradeAuthorityChecked,
+    Close,
+    ExtendProgramChecked,
+);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_loader_v3_interface::{get_program_data_address, instruction as loader_instruction},
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::{system_program, sysvar},
+    };
+
+    #[test]
+    fn test_instructions_match_interface() {
+        let program_key = Pubkey::new_unique();
+        let programdata_key = get_program_data_address(&program_key);
+        let buffer_key = Pubkey::new_unique();
+        let authority_key = Pubkey::new_unique();
+        let new_authority_key = Pubkey::new_unique();
+        let payer_key = Pubkey::new_unique();
+        let rent_key = sysvar::rent::id();
+        let clock_key = sysvar::clock::id();
+        let system_program_key = system_program::id();
+        let keys = [
+            &program_key,
+            &programdata_key,
+            &buffer_key,
+            &authority_key,
+            &new_authority_key,
+            &payer_key,
+            &rent_key,
+            &clock_key,
+            &system_program_key,
+            &ID,
+        ];
+        let mut lamports = [0; 10];
+        let mut data: [[u8; 0]; 10] = Default::default();
+        let infos = keys
+            .into_iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, true, lamports, data, &ID, false, 0)
+            })
+            .collect::<Vec<_>>();
+        let [program, programdata, buffer, authority, new_authority, payer, rent_sysvar, clock_sysvar, system_program, loader] =
+            &infos[..]
+        else {
+            unreachable!()
+        };
+
+        let write = Write {
+            buffer,
+            authority,
+            loader,
+            offset: 3,
+            bytes: &[1, 2, 3],
+        };
+        assert_eq!(
+            write.instruction(),
+            loader_instruction::write(&buffer_key, &authority_key, 3, vec![1, 2, 3])
+        );
+        assert_eq!(write.invoke(), Ok(()));
+
+        let upgrade = Upgrade {
+            programdata,
+            program,
+            buffer,
+            spill: payer,
+            rent_sysvar,
+            clock_sysvar,
+            authority,
+            loader,
+        };
+        assert_eq!(
+            upgrade.instruction(),
+            loader_instruction::upgrade(&program_key, &buffer_key, &authority_key, &payer_key)
+        );
+
+        let set_upgrade_authority = SetUpgradeAuthority {
+            programdata,
+            authority,
+            new_authority: None,
+            loader,
+        };
+        assert_eq!(
+            set_upgrade_authority.instruction(),
+            loader_instruction::set_upgrade_authority(&program_key, &authority_key, None)
+        );
+
+        let close = Close {
+            account: programdata,
+            recipient: payer,
+            authority: Some(authority),
+            program: Some(program),
+            loader,
+        };
+        assert_eq!(
+            close.instruction(),
+            loader_instruction::close_any(
+                &programdata_key,
+                &payer_key,
+                Some(&authority_key),
+                Some(&program_key)
+            )
+        );
+
+        let extend = ExtendProgramChecked {
+            programdata,
+            program,
+            authority,
+            system_program,
+            payer,
+            loader,
+            additional_bytes: 1024,
+        };
+        assert_eq!(
+            extend.instruction(),
+            loader_instruction::extend_program_checked(
+                &program_key,
+                &authority_key,
+                Some(&payer_key),
+                1024
+            )
+        );
+
+        let set_buffer_authority = SetBufferAuthorityChecked {
+            buffer,
+            authority,
+            new_authority,
+            loader,
+        };
+        assert_eq!(
+            set_buffer_authority.instruction(),
+            loader_instruction::set_buffer_authority_checked(
+                &buffer_key,
+                &authority_key,
+                &new_authority_key
+            )
+        );
+    }
+}
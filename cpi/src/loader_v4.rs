@@ -0,0 +1,369 @@
+//! Typed cross-program invocations of the v4 loader.
+
+use {
+    solana_account_info::AccountInfo,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_loader_v4_interface::instruction::LoaderV4Instruction,
+    solana_sdk_ids::loader_v4::{check_id, ID},
+};
+
+/// Set the length of a program account, funding or refunding rent through
+/// `recipient`.
+pub struct SetProgramLength<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Recipient of excess lamports when shrinking the account.
+    pub recipient: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+    /// New length of the program data.
+    pub new_size: u32,
+}
+
+impl<'info> SetProgramLength<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::SetProgramLength {
+                new_size: self.new_size,
+            },
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new(*self.recipient.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.recipient.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Write program data into a retracted program account.
+pub struct Write<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+    /// Offset at which to write the given bytes.
+    pub offset: u32,
+    /// Serialized program data.
+    pub bytes: &'a [u8],
+}
+
+impl<'info> Write<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::Write {
+                offset: self.offset,
+                bytes: self.bytes.to_vec(),
+            },
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Copy program data from another program or buffer account.
+pub struct Copy<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Account to copy from.
+    pub source: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+    /// Offset at which to write.
+    pub destination_offset: u32,
+    /// Offset at which to read.
+    pub source_offset: u32,
+    /// Amount of bytes to copy.
+    pub length: u32,
+}
+
+impl<'info> Copy<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::Copy {
+                destination_offset: self.destination_offset,
+                source_offset: self.source_offset,
+                length: self.length,
+            },
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.source.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.source.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Deploy a program, optionally from the contents of a source buffer.
+pub struct Deploy<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Retracted program account to take the program data and lamports from.
+    pub source: Option<&'a AccountInfo<'info>>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> Deploy<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        let mut account_metas = vec![
+            AccountMeta::new(*self.program.key, false),
+            AccountMeta::new_readonly(*self.authority.key, true),
+        ];
+        if let Some(source) = self.source {
+            account_metas.push(AccountMeta::new(*source.key, false));
+        }
+        Instruction::new_with_bincode(ID, &LoaderV4Instruction::Deploy, account_metas)
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut account_infos = vec![self.program.clone(), self.authority.clone()];
+        account_infos.extend(self.source.cloned());
+        account_infos.push(self.loader.clone());
+        account_infos
+    }
+}
+
+/// Undo the deployment of a program account.
+pub struct Retract<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> Retract<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::Retract,
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Transfer the authority of a program.
+pub struct TransferAuthority<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Current program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// New program authority, must sign.
+    pub new_authority: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> TransferAuthority<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::TransferAuthority,
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.new_authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.new_authority.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+/// Finalize a program, making it immutable.
+pub struct Finalize<'a, 'info> {
+    /// Program account.
+    pub program: &'a AccountInfo<'info>,
+    /// Program authority, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Program account of the next version.
+    pub next_version: &'a AccountInfo<'info>,
+    /// The v4 loader.
+    pub loader: &'a AccountInfo<'info>,
+}
+
+impl<'info> Finalize<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &LoaderV4Instruction::Finalize,
+            vec![
+                AccountMeta::new(*self.program.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.next_version.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.program.clone(),
+            self.authority.clone(),
+            self.next_version.clone(),
+            self.loader.clone(),
+        ]
+    }
+}
+
+impl_invoke!(
+    loader, check_id;
+    SetProgramLength,
+    Write,
+    Copy,
+    Deploy,
+    Retract,
+    TransferAuthority,
+    Finalize,
+);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_loader_v4_interface::instruction as loader_instruction,
+        solana_pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_instructions_match_interface() {
+        let program_key = Pubkey::new_unique();
+        let authority_key = Pubkey::new_unique();
+        let source_key = Pubkey::new_unique();
+        let keys = [&program_key, &authority_key, &source_key, &ID];
+        let mut lamports = [0; 4];
+        let mut data: [[u8; 0]; 4] = Default::default();
+        let infos = keys
+            .into_iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, true, lamports, data, &ID, false, 0)
+            })
+            .collect::<Vec<_>>();
+        let [program, authority, source, loader] = &infos[..] else {
+            unreachable!()
+        };
+
+        let set_program_length = SetProgramLength {
+            program,
+            authority,
+            recipient: source,
+            loader,
+            new_size: 42,
+        };
+        assert_eq!(
+            set_program_length.instruction(),
+            loader_instruction::set_program_length(&program_key, &authority_key, 42, &source_key)
+        );
+        assert_eq!(set_program_length.invoke(), Ok(()));
+
+        let deploy = Deploy {
+            program,
+            authority,
+            source: None,
+            loader,
+        };
+        assert_eq!(
+            deploy.instruction(),
+            loader_instruction::deploy(&program_key, &authority_key)
+        );
+
+        let deploy = Deploy {
+            source: Some(source),
+            ..deploy
+        };
+        assert_eq!(
+            deploy.instruction(),
+            loader_instruction::deploy_from_source(&program_key, &authority_key, &source_key)
+        );
+
+        let copy = Copy {
+            program,
+            authority,
+            source,
+            loader,
+            destination_offset: 1,
+            source_offset: 2,
+            length: 3,
+        };
+        assert_eq!(
+            copy.instruction(),
+            loader_instruction::copy(&program_key, &authority_key, &source_key, 1, 2, 3)
+        );
+
+        let finalize = Finalize {
+            program,
+            authority,
+            next_version: program,
+            loader,
+        };
+        assert_eq!(
+            finalize.instruction(),
+            loader_instruction::finalize(&program_key, &authority_key, &program_key)
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Typed cross-program invocations of the System program.
+//!
+//! # Examples
+//!
+//! Funding a program derived address from a signing payer:
+//!
+//! ```
+//! use solana_account_info::{next_account_info, AccountInfo};
+//! use solana_cpi::system::Transfer;
+//! use solana_program_error::ProgramResult;
+//! use solana_pubkey::Pubkey;
+//!
+//! fn process_instruction(
+//!     program_id: &Pubkey,
+//!     accounts: &[AccountInfo],
+//!     instruction_data: &[u8],
+//! ) -> ProgramResult {
+//!     let account_info_iter = &mut accounts.iter();
+//!     let payer = next_account_info(account_info_iter)?;
+//!     let vault = next_account_info(account_info_iter)?;
+//!     let system_program = next_account_info(account_info_iter)?;
+//!
+//!     Transfer {
+//!         from: payer,
+//!         to: vault,
+//!         system_program,
+//!         lamports: 1_000_000,
+//!     }
+//!     .invoke()
+//! }
+//! ```
+
+use {
+    solana_account_info::AccountInfo,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+    solana_system_interface::{
+        instruction::SystemInstruction,
+        program::{check_id, ID},
+    },
+};
+
+/// Create a new account owned by `owner`.
+///
+/// Both `from` and `to` must sign; a program derived `to` address signs via
+/// [`CreateAccount::invoke_signed`].
+pub struct CreateAccount<'a, 'info> {
+    /// Funding account.
+    pub from: &'a AccountInfo<'info>,
+    /// New account.
+    pub to: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Lamports to transfer to the new account.
+    pub lamports: u64,
+    /// Number of bytes of memory to allocate.
+    pub space: u64,
+    /// Address of the program to assign as the owner of the new account.
+    pub owner: &'a Pubkey,
+}
+
+impl<'info> CreateAccount<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::CreateAccount {
+                lamports: self.lamports,
+                space: self.space,
+                owner: *self.owner,
+            },
+            vec![
+                AccountMeta::new(*self.from.key, true),
+                AccountMeta::new(*self.to.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.from.clone(),
+            self.to.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+/// Create a new account at an address derived from `base` and `seed`.
+pub struct CreateAccountWithSeed<'a, 'info> {
+    /// Funding account.
+    pub from: &'a AccountInfo<'info>,
+    /// New account, at `Pubkey::create_with_seed(base, seed, owner)`.
+    pub to: &'a AccountInfo<'info>,
+    /// Base account.
+    pub base: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Seed used to derive the new account address.
+    pub seed: &'a str,
+    /// Lamports to transfer to the new account.
+    pub lamports: u64,
+    /// Number of bytes of memory to allocate.
+    pub space: u64,
+    /// Address of the program to assign as the owner of the new account.
+    pub owner: &'a Pubkey,
+}
+
+impl<'info> CreateAccountWithSeed<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::CreateAccountWithSeed {
+                base: *self.base.key,
+                seed: self.seed.to_string(),
+                lamports: self.lamports,
+                space: self.space,
+                owner: *self.owner,
+            },
+            vec![
+                AccountMeta::new(*self.from.key, true),
+                AccountMeta::new(*self.to.key, false),
+                AccountMeta::new_readonly(*self.base.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.from.clone(),
+            self.to.clone(),
+            self.base.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+/// Transfer lamports from a System program owned account.
+pub struct Transfer<'a, 'info> {
+    /// Funding account, must sign.
+    pub from: &'a AccountInfo<'info>,
+    /// Recipient account.
+    pub to: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Lamports to transfer.
+    pub lamports: u64,
+}
+
+impl<'info> Transfer<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::Transfer {
+                lamports: self.lamports,
+            },
+            vec![
+                AccountMeta::new(*self.from.key, true),
+                AccountMeta::new(*self.to.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.from.clone(),
+            self.to.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+/// Transfer lamports from an account derived from `base` and a seed.
+pub struct TransferWithSeed<'a, 'info> {
+    /// Funding account, at `Pubkey::create_with_seed(base, from_seed, from_owner)`.
+    pub from: &'a AccountInfo<'info>,
+    /// Base account, must sign.
+    pub base: &'a AccountInfo<'info>,
+    /// Recipient account.
+    pub to: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Seed used to derive the funding account address.
+    pub from_seed: &'a str,
+    /// Owner used to derive the funding account address.
+    pub from_owner: &'a Pubkey,
+    /// Lamports to transfer.
+    pub lamports: u64,
+}
+
+impl<'info> TransferWithSeed<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::TransferWithSeed {
+                lamports: self.lamports,
+                from_seed: self.from_seed.to_string(),
+                from_owner: *self.from_owner,
+            },
+            vec![
+                AccountMeta::new(*self.from.key, false),
+                AccountMeta::new_readonly(*self.base.key, true),
+                AccountMeta::new(*self.to.key, false),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.from.clone(),
+            self.base.clone(),
+            self.to.clone(),
+            self.system_program.clone(),
+        ]
+    }
+}
+
+/// Assign an account to a program.
+pub struct Assign<'a, 'info> {
+    /// Account to assign, must sign.
+    pub account: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Address of the new owner.
+    pub owner: &'a Pubkey,
+}
+
+impl<'info> Assign<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::Assign { owner: *self.owner },
+            vec![AccountMeta::new(*self.account.key, true)],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.account.clone(), self.system_program.clone()]
+    }
+}
+
+/// Allocate space for an account without funding it.
+pub struct Allocate<'a, 'info> {
+    /// Account to allocate, must sign.
+    pub account: &'a AccountInfo<'info>,
+    /// The System program.
+    pub system_program: &'a AccountInfo<'info>,
+    /// Number of bytes of memory to allocate.
+    pub space: u64,
+}
+
+impl<'info> Allocate<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &SystemInstruction::Allocate { space: self.space },
+            vec![AccountMeta::new(*self.account.key, true)],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.account.clone(), self.system_program.clone()]
+    }
+}
+
+impl_invoke!(
+    system_program, check_id;
+    CreateAccount,
+    CreateAccountWithSeed,
+    Transfer,
+    TransferWithSeed,
+    Assign,
+    Allocate,
+);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_program_error::ProgramError,
+        solana_system_interface::instruction as system_instruction,
+    };
+
+    #[test]
+    fn test_instructions_match_interface() {
+        let from_key = Pubkey::new_unique();
+        let to_key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (mut from_lamports, mut to_lamports, mut program_lamports) = (10, 0, 1);
+        let (mut from_data, mut to_data, mut program_data) = ([], [], []);
+        let from = AccountInfo::new(
+            &from_key,
+            true,
+            true,
+            &mut from_lamports,
+            &mut from_data,
+            &ID,
+            false,
+            0,
+        );
+        let to = AccountInfo::new(
+            &to_key,
+            true,
+            true,
+            &mut to_lamports,
+            &mut to_data,
+            &ID,
+            false,
+            0,
+        );
+        let system_program = AccountInfo::new(
+            &ID,
+            false,
+            false,
+            &mut program_lamports,
+            &mut program_data,
+            &ID,
+            true,
+            0,
+        );
+
+        let create_account = CreateAccount {
+            from: &from,
+            to: &to,
+            system_program: &system_program,
+            lamports: 5,
+            space: 42,
+            owner: &owner,
+        };
+        assert_eq!(
+            create_account.instruction(),
+            system_instruction::create_account(&from_key, &to_key, 5, 42, &owner)
+        );
+        assert_eq!(create_account.invoke(), Ok(()));
+
+        let transfer = Transfer {
+            from: &from,
+            to: &to,
+            system_program: &system_program,
+            lamports: 5,
+        };
+        assert_eq!(
+            transfer.instruction(),
+            system_instruction::transfer(&from_key, &to_key, 5)
+        );
+
+        let assign = Assign {
+            account: &to,
+            system_program: &system_program,
+            owner: &owner,
+        };
+        assert_eq!(
+            assign.instruction(),
+            system_instruction::assign(&to_key, &owner)
+        );
+
+        let allocate = Allocate {
+            account: &to,
+            system_program: &system_program,
+            space: 42,
+        };
+        assert_eq!(
+            allocate.instruction(),
+            system_instruction::allocate(&to_key, 42)
+        );
+    }
+
+    #[test]
+    fn test_invoke_checks() {
+        let from_key = Pubkey::new_unique();
+        let to_key = Pubkey::new_unique();
+        let not_system_key = Pubkey::new_unique();
+        let (mut from_lamports, mut to_lamports, mut program_lamports) = (10, 0, 1);
+        let (mut from_data, mut to_data, mut program_data) = ([], [], []);
+        let from = AccountInfo::new(
+            &from_key,
+            true,
+            true,
+            &mut from_lamports,
+            &mut from_data,
+            &ID,
+            false,
+            0,
+        );
+        let to = AccountInfo::new(
+            &to_key,
+            false,
+            true,
+            &mut to_lamports,
+            &mut to_data,
+            &ID,
+            false,
+            0,
+        );
+        let not_system = AccountInfo::new(
+            &not_system_key,
+            false,
+            false,
+            &mut program_lamports,
+            &mut program_data,
+            &ID,
+            true,
+            0,
+        );
+
+        let transfer = Transfer {
+            from: &from,
+            to: &to,
+            system_program: &not_system,
+            lamports: 5,
+        };
+        assert_eq!(transfer.invoke(), Err(ProgramError::IncorrectProgramId));
+
+        // A writable account that is already borrowed cannot be passed along
+        let system_program = AccountInfo {
+            key: &ID,
+            ..not_system.clone()
+        };
+        let transfer = Transfer {
+            system_program: &system_program,
+            ..transfer
+        };
+        let _borrowed = to.try_borrow_data().unwrap();
+        assert_eq!(transfer.invoke(), Err(ProgramError::AccountBorrowFailed));
+    }
+}
@@ -0,0 +1,304 @@
+//! Typed cross-program invocations of the vote program.
+
+use {
+    solana_account_info::AccountInfo,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+    solana_vote_interface::{
+        instruction::VoteInstruction,
+        program::{check_id, ID},
+        state::VoteAuthorize,
+    },
+};
+
+/// Authorize a key to send votes or issue a withdrawal.
+pub struct Authorize<'a, 'info> {
+    /// Vote account.
+    pub vote_account: &'a AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock_sysvar: &'a AccountInfo<'info>,
+    /// Currently authorized voter or withdrawer, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// The vote program.
+    pub vote_program: &'a AccountInfo<'info>,
+    /// Newly authorized key.
+    pub new_authority: &'a Pubkey,
+    /// Whether to authorize a voter or a withdrawer.
+    pub vote_authorize: VoteAuthorize,
+}
+
+impl<'info> Authorize<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &VoteInstruction::Authorize(*self.new_authority, self.vote_authorize),
+            vec![
+                AccountMeta::new(*self.vote_account.key, false),
+                AccountMeta::new_readonly(*self.clock_sysvar.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vote_account.clone(),
+            self.clock_sysvar.clone(),
+            self.authority.clone(),
+            self.vote_program.clone(),
+        ]
+    }
+}
+
+/// Authorize a key to send votes or issue a withdrawal, requiring the new
+/// authority to sign.
+pub struct AuthorizeChecked<'a, 'info> {
+    /// Vote account.
+    pub vote_account: &'a AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock_sysvar: &'a AccountInfo<'info>,
+    /// Currently authorized voter or withdrawer, must sign.
+    pub authority: &'a AccountInfo<'info>,
+    /// Newly authorized voter or withdrawer, must sign.
+    pub new_authority: &'a AccountInfo<'info>,
+    /// The vote program.
+    pub vote_program: &'a AccountInfo<'info>,
+    /// Whether to authorize a voter or a withdrawer.
+    pub vote_authorize: VoteAuthorize,
+}
+
+impl<'info> AuthorizeChecked<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &VoteInstruction::AuthorizeChecked(self.vote_authorize),
+            vec![
+                AccountMeta::new(*self.vote_account.key, false),
+                AccountMeta::new_readonly(*self.clock_sysvar.key, false),
+                AccountMeta::new_readonly(*self.authority.key, true),
+                AccountMeta::new_readonly(*self.new_authority.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vote_account.clone(),
+            self.clock_sysvar.clone(),
+            self.authority.clone(),
+            self.new_authority.clone(),
+            self.vote_program.clone(),
+        ]
+    }
+}
+
+/// Update the validator identity of a vote account.
+pub struct UpdateValidatorIdentity<'a, 'info> {
+    /// Vote account.
+    pub vote_account: &'a AccountInfo<'info>,
+    /// New validator identity, must sign.
+    pub node: &'a AccountInfo<'info>,
+    /// Authorized withdrawer, must sign.
+    pub authorized_withdrawer: &'a AccountInfo<'info>,
+    /// The vote program.
+    pub vote_program: &'a AccountInfo<'info>,
+}
+
+impl<'info> UpdateValidatorIdentity<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &VoteInstruction::UpdateValidatorIdentity,
+            vec![
+                AccountMeta::new(*self.vote_account.key, false),
+                AccountMeta::new_readonly(*self.node.key, true),
+                AccountMeta::new_readonly(*self.authorized_withdrawer.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vote_account.clone(),
+            self.node.clone(),
+            self.authorized_withdrawer.clone(),
+            self.vote_program.clone(),
+        ]
+    }
+}
+
+/// Update the commission of a vote account.
+pub struct UpdateCommission<'a, 'info> {
+    /// Vote account.
+    pub vote_account: &'a AccountInfo<'info>,
+    /// Authorized withdrawer, must sign.
+    pub authorized_withdrawer: &'a AccountInfo<'info>,
+    /// The vote program.
+    pub vote_program: &'a AccountInfo<'info>,
+    /// New commission, as a percentage.
+    pub commission: u8,
+}
+
+impl<'info> UpdateCommission<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &VoteInstruction::UpdateCommission(self.commission),
+            vec![
+                AccountMeta::new(*self.vote_account.key, false),
+                AccountMeta::new_readonly(*self.authorized_withdrawer.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vote_account.clone(),
+            self.authorized_withdrawer.clone(),
+            self.vote_program.clone(),
+        ]
+    }
+}
+
+/// Withdraw lamports from a vote account.
+pub struct Withdraw<'a, 'info> {
+    /// Vote account.
+    pub vote_account: &'a AccountInfo<'info>,
+    /// Recipient account.
+    pub recipient: &'a AccountInfo<'info>,
+    /// Authorized withdrawer, must sign.
+    pub authorized_withdrawer: &'a AccountInfo<'info>,
+    /// The vote program.
+    pub vote_program: &'a AccountInfo<'info>,
+    /// Lamports to withdraw.
+    pub lamports: u64,
+}
+
+impl<'info> Withdraw<'_, 'info> {
+    /// Returns the instruction, with account metas derived from the accounts.
+    pub fn instruction(&self) -> Instruction {
+        Instruction::new_with_bincode(
+            ID,
+            &VoteInstruction::Withdraw(self.lamports),
+            vec![
+                AccountMeta::new(*self.vote_account.key, false),
+                AccountMeta::new(*self.recipient.key, false),
+                AccountMeta::new_readonly(*self.authorized_withdrawer.key, true),
+            ],
+        )
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vote_account.clone(),
+            self.recipient.clone(),
+            self.authorized_withdrawer.clone(),
+            self.vote_program.clone(),
+        ]
+    }
+}
+
+impl_invoke!(
+    vote_program, check_id;
+    Authorize,
+    AuthorizeChecked,
+    UpdateValidatorIdentity,
+    UpdateCommission,
+    Withdraw,
+);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_program_error::ProgramError, solana_sdk_ids::sysvar,
+        solana_vote_interface::instruction as vote_instruction,
+    };
+
+    #[test]
+    fn test_instructions_match_interface() {
+        let vote_key = Pubkey::new_unique();
+        let authority_key = Pubkey::new_unique();
+        let new_authority_key = Pubkey::new_unique();
+        let clock_key = sysvar::clock::id();
+        let keys = [
+            &vote_key,
+            &authority_key,
+            &new_authority_key,
+            &clock_key,
+            &ID,
+        ];
+        let mut lamports = [0; 5];
+        let mut data: [[u8; 0]; 5] = Default::default();
+        let infos = keys
+            .into_iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, true, lamports, data, &ID, false, 0)
+            })
+            .collect::<Vec<_>>();
+        let [vote_account, authority, new_authority, clock_sysvar, vote_program] = &infos[..]
+        else {
+            unreachable!()
+        };
+
+        let authorize = Authorize {
+            vote_account,
+            clock_sysvar,
+            authority,
+            vote_program,
+            new_authority: &new_authority_key,
+            vote_authorize: VoteAuthorize::Withdrawer,
+        };
+        assert_eq!(
+            authorize.instruction(),
+            vote_instruction::authorize(
+                &vote_key,
+                &authority_key,
+                &new_authority_key,
+                VoteAuthorize::Withdrawer
+            )
+        );
+        assert_eq!(authorize.invoke(), Ok(()));
+
+        let authorize_checked = AuthorizeChecked {
+            vote_account,
+            clock_sysvar,
+            authority,
+            new_authority,
+            vote_program,
+            vote_authorize: VoteAuthorize::Voter,
+        };
+        assert_eq!(
+            authorize_checked.instruction(),
+            vote_instruction::authorize_checked(
+                &vote_key,
+                &authority_key,
+                &new_authority_key,
+                VoteAuthorize::Voter
+            )
+        );
+
+        let withdraw = Withdraw {
+            vote_account,
+            recipient: new_authority,
+            authorized_withdrawer: authority,
+            vote_program,
+            lamports: 42,
+        };
+        assert_eq!(
+            withdraw.instruction(),
+            vote_instruction::withdraw(&vote_key, &authority_key, 42, &new_authority_key)
+        );
+
+        let withdraw = Withdraw {
+            vote_program: clock_sysvar,
+            ..withdraw
+        };
+        assert_eq!(withdraw.invoke(), Err(ProgramError::IncorrectProgramId));
+    }
+}
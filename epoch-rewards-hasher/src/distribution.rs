@@ -0,0 +1,394 @@
+//! Planning and verification of a partitioned epoch rewards distribution.
+//!
+//! Stake rewards are paid out over `num_partitions` consecutive blocks,
+//! starting at `distribution_starting_block_height`. The partition of a stake
+//! account is picked by an [`EpochRewardsHasher`] seeded with the parent
+//! blockhash of the first block of the epoch, and the block at
+//! `distribution_starting_block_height + partition` pays that partition.
+//! [`DistributionPlan`] computes the whole distribution up front, so that
+//! payouts and the [`EpochRewards`] sysvar can be reconciled block by block.
+
+use {
+    crate::EpochRewardsHasher,
+    solana_epoch_rewards::EpochRewards,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    std::{fmt, num::NonZeroUsize},
+};
+
+/// The rewards paid out by one partition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewardPartition {
+    /// Stake addresses and the lamports they are paid, in the order they were
+    /// given to the plan.
+    pub rewards: Vec<(Pubkey, u64)>,
+    pub total: u64,
+}
+
+/// The rewards paid out in one block of the distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockDistribution {
+    pub block_height: u64,
+    pub partition_index: usize,
+    pub lamports: u64,
+    /// Rewards paid out by the end of the block.
+    pub cumulative: u64,
+}
+
+/// How far the distribution has progressed at a block height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistributionProgress {
+    pub partitions_distributed: usize,
+    pub distributed_rewards: u64,
+    pub remaining_rewards: u64,
+}
+
+/// A difference between a plan and an [`EpochRewards`] sysvar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DistributionMismatch {
+    NumPartitions {
+        planned: u64,
+        sysvar: u64,
+    },
+    ParentBlockhash {
+        planned: Hash,
+        sysvar: Hash,
+    },
+    /// The sysvar has fewer total rewards than the plan pays out.
+    TotalRewards {
+        planned: u64,
+        sysvar: u64,
+    },
+    DistributedRewards {
+        expected: u64,
+        sysvar: u64,
+    },
+    Active {
+        expected: bool,
+        sysvar: bool,
+    },
+}
+
+impl std::error::Error for DistributionMismatch {}
+
+impl fmt::Display for DistributionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NumPartitions { planned, sysvar } => write!(
+                f,
+                "planned {planned} partitions, but the sysvar has {sysvar}"
+            ),
+            Self::ParentBlockhash { planned, sysvar } => write!(
+                f,
+                "planned with parent blockhash {planned}, but the sysvar has {sysvar}"
+            ),
+            Self::TotalRewards { planned, sysvar } => write!(
+                f,
+                "planned to distribute {planned} lamports, but the sysvar only has {sysvar}"
+            ),
+            Self::DistributedRewards { expected, sysvar } => write!(
+                f,
+                "expected {expected} lamports to be distributed, but the sysvar has {sysvar}"
+            ),
+            Self::Active { expected, sysvar } => write!(
+                f,
+                "expected the rewards period to be {}, but the sysvar has it {}",
+                if *expected { "active" } else { "inactive" },
+                if *sysvar { "active" } else { "inactive" },
+            ),
+        }
+    }
+}
+
+/// The partitions of an epoch rewards distribution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistributionPlan {
+    parent_blockhash: Hash,
+    partitions: Vec<RewardPartition>,
+    total_rewards: u64,
+}
+
+impl DistributionPlan {
+    /// Splits `rewards` into `num_partitions` partitions seeded with
+    /// `parent_blockhash`.
+    pub fn new(
+        rewards: impl IntoIterator<Item = (Pubkey, u64)>,
+        parent_blockhash: Hash,
+        num_partitions: NonZeroUsize,
+    ) -> Self {
+        let num_partitions = num_partitions.get();
+        let hasher = EpochRewardsHasher::new(num_partitions, &parent_blockhash);
+        let mut partitions = vec![RewardPartition::default(); num_partitions];
+        let mut total_rewards = 0u64;
+        for (address, lamports) in rewards {
+            let partition = &mut partitions[hasher.clone().hash_address_to_partition(&address)];
+            partition.rewards.push((address, lamports));
+            partition.total = partition.total.saturating_add(lamports);
+            total_rewards = total_rewards.saturating_add(lamports);
+        }
+        Self {
+            parent_blockhash,
+            partitions,
+            total_rewards,
+        }
+    }
+
+    pub fn parent_blockhash(&self) -> &Hash {
+        &self.parent_blockhash
+    }
+
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    pub fn partitions(&self) -> &[RewardPartition] {
+        &self.partitions
+    }
+
+    pub fn total_rewards(&self) -> u64 {
+        self.total_rewards
+    }
+
+    /// The partition `address` is paid in.
+    pub fn partition_of(&self, address: &Pubkey) -> usize {
+        EpochRewardsHasher::new(self.num_partitions(), &self.parent_blockhash)
+            .hash_address_to_partition(address)
+    }
+
+    /// The rewards paid out in each block of a distribution starting at
+    /// `distribution_starting_block_height`.
+    pub fn block_distributions(
+        &self,
+        distribution_starting_block_height: u64,
+    ) -> Vec<BlockDistribution> {
+        let mut cumulative = 0u64;
+        self.partitions
+            .iter()
+            .enumerate()
+            .map(|(partition_index, partition)| {
+                cumulative = cumulative.saturating_add(partition.total);
+                BlockDistribution {
+                    block_height: distribution_starting_block_height
+                        .saturating_add(partition_index as u64),
+                    partition_index,
+                    lamports: partition.total,
+                    cumulative,
+                }
+            })
+            .collect()
+    }
+
+    /// The progress of a distribution starting at
+    /// `distribution_starting_block_height`, by the end of the block at
+    /// `block_height`.
+    pub fn progress(
+        &self,
+        distribution_starting_block_height: u64,
+        block_height: u64,
+    ) -> DistributionProgress {
+        let partitions_distributed = block_height
+            .checked_sub(distribution_starting_block_height)
+            .map_or(0, |blocks| {
+                usize::try_from(blocks.saturating_add(1))
+                    .unwrap_or(usize::MAX)
+                    .min(self.num_partitions())
+            });
+        let distributed_rewards = self.partitions[..partitions_distributed]
+            .iter()
+            .fold(0u64, |total, partition| {
+                total.saturating_add(partition.total)
+            });
+        DistributionProgress {
+            partitions_distributed,
+            distributed_rewards,
+            remaining_rewards: self.total_rewards.saturating_sub(distributed_rewards),
+        }
+    }
+
+    /// Checks the state of the [`EpochRewards`] sysvar by the end of the
+    /// block at `block_height` against the plan.
+    pub fn verify(
+        &self,
+        epoch_rewards: &EpochRewards,
+        block_height: u64,
+    ) -> Result<DistributionProgress, DistributionMismatch> {
+        let num_partitions = self.num_partitions() as u64;
+        if epoch_rewards.num_partitions != num_partitions {
+            return Err(DistributionMismatch::NumPartitions {
+                planned: num_partitions,
+                sysvar: epoch_rewards.num_partitions,
+            });
+        }
+        if epoch_rewards.parent_blockhash != self.parent_blockhash {
+            return Err(DistributionMismatch::ParentBlockhash {
+                planned: self.parent_blockhash,
+                sysvar: epoch_rewards.parent_blockhash,
+            });
+        }
+        if epoch_rewards.total_rewards < self.total_rewards {
+            return Err(DistributionMismatch::TotalRewards {
+                planned: self.total_rewards,
+                sysvar: epoch_rewards.total_rewards,
+            });
+        }
+        let progress = self.progress(
+            epoch_rewards.distribution_starting_block_height,
+            block_height,
+        );
+        if epoch_rewards.distributed_rewards != progress.distributed_rewards {
+            return Err(DistributionMismatch::DistributedRewards {
+                expected: progress.distributed_rewards,
+                sysvar: epoch_rewards.distributed_rewards,
+            });
+        }
+        // The sysvar is deactivated in the block paying the last partition
+        let active = progress.partitions_distributed < self.num_partitions();
+        if epoch_rewards.active != active {
+            return Err(DistributionMismatch::Active {
+                expected: active,
+                sysvar: epoch_rewards.active,
+            });
+        }
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewards(count: u64) -> Vec<(Pubkey, u64)> {
+        (1..=count).map(|i| (Pubkey::new_unique(), i)).collect()
+    }
+
+    #[test]
+    fn test_distribution_plan() {
+        let rewards = rewards(100);
+        let parent_blockhash = Hash::new_unique();
+        let plan = DistributionPlan::new(
+            rewards.clone(),
+            parent_blockhash,
+            NonZeroUsize::new(4).unwrap(),
+        );
+        assert_eq!(plan.num_partitions(), 4);
+        assert_eq!(plan.total_rewards(), 5050);
+        assert_eq!(
+            plan.partitions()
+                .iter()
+                .map(|partition| partition.rewards.len())
+                .sum::<usize>(),
+            100
+        );
+        for (index, partition) in plan.partitions().iter().enumerate() {
+            assert_eq!(
+                partition.total,
+                partition.rewards.iter().map(|(_, lamports)| lamports).sum()
+            );
+            for (address, _) in &partition.rewards {
+                assert_eq!(plan.partition_of(address), index);
+                assert_eq!(
+                    EpochRewardsHasher::new(4, &parent_blockhash)
+                        .hash_address_to_partition(address),
+                    index
+                );
+            }
+        }
+
+        let blocks = plan.block_distributions(10);
+        assert_eq!(
+            blocks
+                .iter()
+                .map(|block| block.block_height)
+                .collect::<Vec<_>>(),
+            vec![10, 11, 12, 13]
+        );
+        assert_eq!(blocks[0].cumulative, plan.partitions()[0].total);
+        assert_eq!(blocks[3].cumulative, 5050);
+
+        assert_eq!(
+            plan.progress(10, 9),
+            DistributionProgress {
+                partitions_distributed: 0,
+                distributed_rewards: 0,
+                remaining_rewards: 5050,
+            }
+        );
+        assert_eq!(
+            plan.progress(10, 11).distributed_rewards,
+            blocks[1].cumulative
+        );
+        assert_eq!(plan.progress(10, 13).remaining_rewards, 0);
+        assert_eq!(plan.progress(10, 100).partitions_distributed, 4);
+    }
+
+    #[test]
+    fn test_distribution_plan_single_partition() {
+        let rewards = rewards(10);
+        let plan = DistributionPlan::new(rewards.clone(), Hash::new_unique(), NonZeroUsize::MIN);
+        assert_eq!(plan.num_partitions(), 1);
+        assert_eq!(plan.partitions()[0].rewards, rewards);
+        assert_eq!(plan.partitions()[0].total, 55);
+    }
+
+    #[test]
+    fn test_verify() {
+        let parent_blockhash = Hash::new_unique();
+        let plan =
+            DistributionPlan::new(rewards(20), parent_blockhash, NonZeroUsize::new(3).unwrap());
+        let mut epoch_rewards = EpochRewards {
+            distribution_starting_block_height: 100,
+            num_partitions: 3,
+            parent_blockhash,
+            total_points: 1_000,
+            total_rewards: 211,
+            distributed_rewards: 0,
+            active: true,
+        };
+        assert_eq!(
+            plan.verify(&epoch_rewards, 99).unwrap().distributed_rewards,
+            0
+        );
+
+        for block in plan.block_distributions(100) {
+            epoch_rewards.distribute(block.lamports);
+            if block.partition_index == 2 {
+                epoch_rewards.active = false;
+            }
+            let progress = plan.verify(&epoch_rewards, block.block_height).unwrap();
+            assert_eq!(progress.partitions_distributed, block.partition_index + 1);
+            assert_eq!(progress.distributed_rewards, block.cumulative);
+        }
+        assert_eq!(
+            plan.verify(&epoch_rewards, 101),
+            Err(DistributionMismatch::DistributedRewards {
+                expected: plan.block_distributions(100)[1].cumulative,
+                sysvar: 210,
+            })
+        );
+
+        epoch_rewards.active = true;
+        assert_eq!(
+            plan.verify(&epoch_rewards, 102),
+            Err(DistributionMismatch::Active {
+                expected: false,
+                sysvar: true,
+            })
+        );
+        epoch_rewards.total_rewards = 200;
+        assert_eq!(
+            plan.verify(&epoch_rewards, 102),
+            Err(DistributionMismatch::TotalRewards {
+                planned: 210,
+                sysvar: 200,
+            })
+        );
+        epoch_rewards.num_partitions = 4;
+        assert_eq!(
+            plan.verify(&epoch_rewards, 102),
+            Err(DistributionMismatch::NumPartitions {
+                planned: 3,
+                sysvar: 4,
+            })
+        );
+    }
+}
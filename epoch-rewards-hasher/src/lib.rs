@@ -1,3 +1,8 @@
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+
+#[cfg(feature = "distribution")]
+pub mod distribution;
+
 use {siphasher::sip::SipHasher13, solana_hash::Hash, solana_pubkey::Pubkey, std::hash::Hasher};
 
 #[derive(Debug, Clone)]
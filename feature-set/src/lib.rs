@@ -13,11 +13,13 @@
 //!      person will generate the keypair, provide pubkey for PR, and ultimately enable the feature.
 //! 2. Add a public module for the feature, specifying keypair pubkey as the id with
 //!    `solana_pubkey::declare_id!()` within the module.
-//!    Additionally, add an entry to `FEATURE_NAMES` map.
+//!    Additionally, add an entry to `FEATURE_NAMES` map, and to the tables of the
+//!    [`metadata`] module.
 //! 3. Add desired logic to check for and switch on feature availability.
 //!
 //! For more information on how features are picked up, see comments for `Feature`.
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![deprecated(since = "2.2.5", note = "Use agave-feature-set instead")]
 
 use {
@@ -29,6 +31,11 @@ use {
     solana_sha256_hasher::Hasher,
 };
 
+pub mod metadata;
+pub mod snapshot;
+#[cfg(feature = "dev-context-only-utils")]
+pub mod test_matrix;
+
 pub mod deprecate_rewards_sysvar {
     solana_pubkey::declare_id!("GaBtBJvmS4Arjj5W1NmFcyvPjsHN38UGYDq2MDwbs9Qu");
 }
@@ -0,0 +1,665 @@
+//! Structured metadata for runtime features.
+//!
+//! [`FEATURE_NAMES`] only holds a free-form description for each feature.
+//! [`FEATURE_METADATA`] adds the SIMD and GitHub references found in those
+//! descriptions, a category, and the dependencies between features, so that
+//! tools can reason about activation order.
+//!
+//! When adding a feature, list it in [`FEATURE_CATEGORIES`], and in
+//! [`FEATURE_DEPENDENCIES`] if its behavior relies on another feature. A
+//! feature reverting another one depends on it, since it can only take
+//! effect once the reverted feature is active. New features should also
+//! record the date they were added in [`FEATURE_DATES_ADDED`].
+
+use {crate::*, ahash::AHashMap, lazy_static::lazy_static, solana_pubkey::Pubkey};
+
+/// The area of the runtime or cluster a feature changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FeatureCategory {
+    /// Account storage, hashing, snapshots and rent.
+    Accounts,
+    /// Voting, fork choice and proof of history.
+    Consensus,
+    /// Inflation, rewards and commission.
+    Economics,
+    /// Transaction fees, compute budget and cost model.
+    Fees,
+    /// Program loaders, SBPF versions and cross-program invocation.
+    Loader,
+    /// Turbine, shreds, gossip and duplicate proofs.
+    Networking,
+    /// Signature verification precompiles.
+    Precompile,
+    /// Builtin and core BPF programs.
+    Program,
+    /// Transaction processing and sysvars.
+    Runtime,
+    /// Syscalls available to programs.
+    Syscall,
+    /// Not categorized yet.
+    Other,
+}
+
+/// Structured metadata of a runtime feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeatureMetadata {
+    pub id: Pubkey,
+    /// User-visible description, as in [`FEATURE_NAMES`].
+    pub description: &'static str,
+    /// Number of the SIMD proposing the feature.
+    pub simd: Option<u16>,
+    /// Number of the GitHub pull request or issue implementing the feature.
+    pub github_ref: Option<u32>,
+    pub category: FeatureCategory,
+    /// Features that must be active before this feature is activated.
+    pub dependencies: Vec<Pubkey>,
+    /// Date the feature was added, formatted as `YYYY-MM-DD`.
+    pub date_added: Option<&'static str>,
+}
+
+/// Category of each feature.
+pub const FEATURE_CATEGORIES: &[(FeatureCategory, &[Pubkey])] = &[
+    (
+        FeatureCategory::Accounts,
+        &[
+            no_overflow_rent_distribution::id(),
+            rent_for_sysvars::id(),
+            require_rent_exempt_accounts::id(),
+            include_account_index_in_rent_error::id(),
+            preserve_rent_epoch_for_rent_exempt_accounts::id(),
+            skip_rent_rewrites::id(),
+            disable_rehash_for_rent_epoch::id(),
+            account_hash_ignore_slot::id(),
+            set_exempt_rent_epoch_max::id(),
+            on_load_preserve_rent_epoch_for_rent_exempt_accounts::id(),
+            prevent_crediting_accounts_that_end_rent_paying::id(),
+            incremental_snapshot_only_incremental_hash_calculation::id(),
+            epoch_accounts_hash::id(),
+            prevent_rent_paying_rent_recipients::id(),
+            disable_rent_fees_collection::id(),
+            accounts_lt_hash::id(),
+            snapshots_lt_hash::id(),
+            remove_accounts_delta_hash::id(),
+            disable_partitioned_rent_collection::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Consensus,
+        &[
+            allow_votes_to_directly_update_vote_state::id(),
+            filter_votes_outside_slot_hashes::id(),
+            vote_state_update_credit_per_dequeue::id(),
+            compact_vote_state_updates::id(),
+            vote_state_update_root_fix::id(),
+            update_hashes_per_tick::id(),
+            vote_state_add_vote_latency::id(),
+            timely_vote_credits::id(),
+            update_hashes_per_tick2::id(),
+            update_hashes_per_tick3::id(),
+            update_hashes_per_tick4::id(),
+            update_hashes_per_tick5::id(),
+            update_hashes_per_tick6::id(),
+            deprecate_unused_legacy_vote_plumbing::id(),
+            enable_tower_sync_ix::id(),
+            deprecate_legacy_vote_ixs::id(),
+            raise_block_limits_to_50m::id(),
+            enable_vote_address_leader_schedule::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Economics,
+        &[
+            pico_inflation::id(),
+            full_inflation::devnet_and_testnet::id(),
+            full_inflation::mainnet::certusone::enable::id(),
+            full_inflation::mainnet::certusone::vote::id(),
+            warp_timestamp_again::id(),
+            update_rewards_from_cached_accounts::id(),
+            enable_partitioned_epoch_reward::id(),
+            stake_minimum_delegation_for_rewards::id(),
+            warp_timestamp_with_a_vengeance::id(),
+            commission_updates_only_allowed_in_first_half_of_epoch::id(),
+            allow_commission_decrease_at_any_time::id(),
+            partitioned_epoch_rewards_superfeature::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Fees,
+        &[
+            tx_wide_compute_cap::id(),
+            disable_fees_sysvar::id(),
+            disable_fee_calculator::id(),
+            add_compute_budget_program::id(),
+            default_units_per_instruction::id(),
+            add_set_compute_unit_price_ix::id(),
+            use_default_units_in_fee_calculation::id(),
+            remove_congestion_multiplier_from_fee_calculation::id(),
+            apply_cost_tracker_during_replay::id(),
+            add_set_tx_loaded_accounts_data_size_instruction::id(),
+            include_loaded_accounts_data_size_in_fee_calculation::id(),
+            native_programs_consume_cu::id(),
+            checked_arithmetic_in_fee_validation::id(),
+            validate_fee_collector_account::id(),
+            cost_model_requested_write_lock_cost::id(),
+            remove_rounding_in_fee_calculation::id(),
+            reward_full_priority_fee::id(),
+            enable_transaction_loading_failure_fees::id(),
+            deplete_cu_meter_on_vm_failure::id(),
+            reserve_minimal_cus_for_builtin_instructions::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Loader,
+        &[
+            reduce_required_deploy_balance::id(),
+            remove_native_loader::id(),
+            requestable_heap_size::id(),
+            disable_bpf_deprecated_load_instructions::id(),
+            disable_bpf_unresolved_symbols_at_runtime::id(),
+            disable_deprecated_loader::id(),
+            reject_callx_r10::id(),
+            executables_incur_cpi_data_cost::id(),
+            enable_bpf_loader_extend_program_ix::id(),
+            cap_bpf_program_instruction_accounts::id(),
+            loosen_cpi_size_restriction::id(),
+            disable_cpi_setting_executable_and_rent_epoch::id(),
+            enable_bpf_loader_set_authority_checked_ix::id(),
+            enable_program_redeployment_cooldown::id(),
+            move_serialized_len_ptr_in_cpi::id(),
+            disable_builtin_loader_ownership_chains::id(),
+            enable_request_heap_frame_ix::id(),
+            delay_visibility_of_program_deployment::id(),
+            switch_to_new_elf_parser::id(),
+            round_up_heap_size::id(),
+            remove_bpf_loader_incorrect_program_id::id(),
+            bpf_account_data_direct_mapping::id(),
+            enable_loader_v4::id(),
+            disable_new_loader_v3_deployments::id(),
+            disable_bpf_loader_instructions::id(),
+            disable_sbpf_v0_execution::id(),
+            reenable_sbpf_v0_execution::id(),
+            enable_sbpf_v1_deployment_and_execution::id(),
+            enable_sbpf_v2_deployment_and_execution::id(),
+            enable_sbpf_v3_deployment_and_execution::id(),
+            remove_accounts_executable_flag_checks::id(),
+            lift_cpi_caller_restriction::id(),
+            disable_account_loader_special_case::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Networking,
+        &[
+            send_to_tpu_vote_port::id(),
+            drop_redundant_turbine_path::id(),
+            add_shred_type_to_shred_seed::id(),
+            enable_turbine_fanout_experiments::id(),
+            disable_turbine_fanout_experiments::id(),
+            revise_turbine_epoch_stakes::id(),
+            drop_legacy_shreds::id(),
+            consume_blockstore_duplicate_proofs::id(),
+            index_erasure_conflict_duplicate_proofs::id(),
+            merkle_conflict_duplicate_proofs::id(),
+            enable_gossip_duplicate_proof_ingestion::id(),
+            enable_chained_merkle_shreds::id(),
+            chained_merkle_conflict_duplicate_proofs::id(),
+            vote_only_full_fec_sets::id(),
+            verify_retransmitter_signature::id(),
+            vote_only_retransmitter_signed_fec_sets::id(),
+            enable_turbine_extended_fanout_experiments::id(),
+            drop_unchained_merkle_shreds::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Precompile,
+        &[
+            secp256k1_program_enabled::id(),
+            libsecp256k1_0_5_upgrade_enabled::id(),
+            libsecp256k1_fail_on_bad_count::id(),
+            libsecp256k1_fail_on_bad_count2::id(),
+            ed25519_program_enabled::id(),
+            prevent_calling_precompiles_as_programs::id(),
+            ed25519_precompile_verify_strict::id(),
+            move_precompile_verification_to_svm::id(),
+            enable_secp256r1_precompile::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Program,
+        &[
+            spl_token_v2_multisig_fix::id(),
+            filter_stake_delegation_accounts::id(),
+            require_custodian_for_locked_stake_authorize::id(),
+            spl_token_v2_self_transfer_fix::id(),
+            check_init_vote_data::id(),
+            system_transfer_zero_check::id(),
+            dedupe_config_program_signers::id(),
+            vote_stake_checked_instructions::id(),
+            spl_token_v2_set_authority_fix::id(),
+            merge_nonce_error_into_system_error::id(),
+            stake_merge_with_unmatched_credits_observed::id(),
+            zk_token_sdk_enabled::id(),
+            stake_program_advance_activating_credits_observed::id(),
+            credits_auto_rewind::id(),
+            stakes_remove_delegation_if_inactive::id(),
+            nonce_must_be_writable::id(),
+            spl_token_v3_3_0_release::id(),
+            leave_nonce_on_success::id(),
+            reject_non_rent_exempt_vote_withdraws::id(),
+            stake_deactivate_delinquent_instruction::id(),
+            vote_withdraw_authority_may_change_authorized_voter::id(),
+            spl_associated_token_account_v1_0_4::id(),
+            reject_vote_account_close_unless_zero_credit_epoch::id(),
+            stake_split_uses_rent_sysvar::id(),
+            add_get_minimum_delegation_instruction_to_stake_program::id(),
+            spl_token_v3_4_0::id(),
+            spl_associated_token_account_v1_1_0::id(),
+            stake_allow_zero_undelegated_amount::id(),
+            stake_raise_minimum_delegation_to_1_sol::id(),
+            separate_nonce_from_blockhash::id(),
+            enable_durable_nonce::id(),
+            nonce_must_be_authorized::id(),
+            nonce_must_be_advanceable::id(),
+            vote_authorize_with_seed::id(),
+            relax_authority_signer_check_for_lookup_table_creation::id(),
+            clean_up_delegation_errors::id(),
+            reduce_stake_warmup_cooldown::id(),
+            require_rent_exempt_split_destination::id(),
+            enable_zk_transfer_with_fee::id(),
+            enable_zk_proof_from_account::id(),
+            migrate_feature_gate_program_to_core_bpf::id(),
+            migrate_config_program_to_core_bpf::id(),
+            migrate_address_lookup_table_program_to_core_bpf::id(),
+            zk_elgamal_proof_program_enabled::id(),
+            move_stake_and_move_lamports_ixs::id(),
+            migrate_stake_program_to_core_bpf::id(),
+            create_slashing_program::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Runtime,
+        &[
+            deprecate_rewards_sysvar::id(),
+            verify_tx_signatures_len::id(),
+            versioned_tx_message_enabled::id(),
+            instructions_sysvar_owned_by_sysvar::id(),
+            demote_program_write_locks::id(),
+            do_support_realloc::id(),
+            optimize_epoch_boundary_updates::id(),
+            reject_empty_instruction_without_program::id(),
+            evict_invalid_stakes_cache_entries::id(),
+            max_tx_account_locks::id(),
+            bank_transaction_count_fix::id(),
+            record_instruction_in_transaction_context_push::id(),
+            check_physical_overlapping::id(),
+            check_slice_translation_size::id(),
+            fix_recent_blockhashes::id(),
+            require_static_program_ids_in_transaction::id(),
+            quick_bail_on_panic::id(),
+            enable_early_verification_of_account_modifications::id(),
+            cap_accounts_data_allocations_per_transaction::id(),
+            remove_deprecated_request_unit_ix::id(),
+            increase_tx_account_lock_limit::id(),
+            limit_max_instruction_trace_length::id(),
+            cap_transaction_accounts_data_size::id(),
+            simplify_writable_program_account_check::id(),
+            last_restart_slot_sysvar::id(),
+            better_error_codes_for_tx_lamport_check::id(),
+            add_new_reserved_account_keys::id(),
+            relax_intrabatch_account_locks::id(),
+        ],
+    ),
+    (
+        FeatureCategory::Syscall,
+        &[
+            secp256k1_recover_syscall_enabled::id(),
+            blake3_syscall_enabled::id(),
+            curve25519_syscall_enabled::id(),
+            return_data_syscall_enabled::id(),
+            sol_log_data_syscall_enabled::id(),
+            fixed_memcpy_nonoverlapping_check::id(),
+            update_syscall_base_costs::id(),
+            add_get_processed_sibling_instruction_syscall::id(),
+            syscall_saturated_math::id(),
+            limit_secp256k1_recovery_id::id(),
+            error_on_syscall_bpf_function_hash_collisions::id(),
+            disable_deploy_of_alloc_free_syscall::id(),
+            stop_sibling_instruction_search_at_parent::id(),
+            check_syscall_outputs_do_not_overlap::id(),
+            enable_alt_bn128_syscall::id(),
+            simplify_alt_bn128_syscall_error_codes::id(),
+            enable_big_mod_exp_syscall::id(),
+            stop_truncating_strings_in_syscalls::id(),
+            enable_poseidon_syscall::id(),
+            remaining_compute_units_syscall_enabled::id(),
+            enable_alt_bn128_compression_syscall::id(),
+            curve25519_restrict_msm_length::id(),
+            abort_on_invalid_curve::id(),
+            get_sysvar_syscall_enabled::id(),
+            enable_get_epoch_stake_syscall::id(),
+            fix_alt_bn128_multiplication_input_length::id(),
+        ],
+    ),
+];
+
+/// Pairs of a feature and a feature that must be active before it.
+pub const FEATURE_DEPENDENCIES: &[(Pubkey, Pubkey)] = &[
+    (
+        separate_nonce_from_blockhash::id(),
+        enable_durable_nonce::id(),
+    ),
+    (
+        zk_token_sdk_enabled::id(),
+        enable_zk_transfer_with_fee::id(),
+    ),
+    (
+        zk_token_sdk_enabled::id(),
+        enable_zk_proof_from_account::id(),
+    ),
+    (update_hashes_per_tick::id(), update_hashes_per_tick2::id()),
+    (update_hashes_per_tick2::id(), update_hashes_per_tick3::id()),
+    (update_hashes_per_tick3::id(), update_hashes_per_tick4::id()),
+    (update_hashes_per_tick4::id(), update_hashes_per_tick5::id()),
+    (update_hashes_per_tick5::id(), update_hashes_per_tick6::id()),
+    (
+        enable_alt_bn128_syscall::id(),
+        enable_alt_bn128_compression_syscall::id(),
+    ),
+    (
+        enable_alt_bn128_syscall::id(),
+        simplify_alt_bn128_syscall_error_codes::id(),
+    ),
+    (
+        enable_alt_bn128_syscall::id(),
+        fix_alt_bn128_multiplication_input_length::id(),
+    ),
+    (
+        enable_chained_merkle_shreds::id(),
+        chained_merkle_conflict_duplicate_proofs::id(),
+    ),
+    (
+        enable_chained_merkle_shreds::id(),
+        drop_unchained_merkle_shreds::id(),
+    ),
+    (
+        enable_loader_v4::id(),
+        disable_new_loader_v3_deployments::id(),
+    ),
+    (accounts_lt_hash::id(), snapshots_lt_hash::id()),
+    (accounts_lt_hash::id(), remove_accounts_delta_hash::id()),
+    (
+        enable_turbine_fanout_experiments::id(),
+        disable_turbine_fanout_experiments::id(),
+    ),
+    (
+        disable_sbpf_v0_execution::id(),
+        reenable_sbpf_v0_execution::id(),
+    ),
+];
+
+/// Date each feature was added, for the features that recorded it.
+pub const FEATURE_DATES_ADDED: &[(Pubkey, &str)] = &[];
+
+lazy_static! {
+    /// Map of feature identifiers to their structured metadata
+    pub static ref FEATURE_METADATA: AHashMap<Pubkey, FeatureMetadata> = {
+        let mut metadata = FEATURE_NAMES
+            .iter()
+            .map(|(id, description)| {
+                (
+                    *id,
+                    FeatureMetadata {
+                        id: *id,
+                        description,
+                        simd: parse_simd(description),
+                        github_ref: parse_github_ref(description),
+                        category: FeatureCategory::Other,
+                        dependencies: vec![],
+                        date_added: None,
+                    },
+                )
+            })
+            .collect::<AHashMap<_, _>>();
+        for (category, ids) in FEATURE_CATEGORIES {
+            for id in *ids {
+                if let Some(entry) = metadata.get_mut(id) {
+                    entry.category = *category;
+                }
+            }
+        }
+        for (dependency, id) in FEATURE_DEPENDENCIES {
+            if let Some(entry) = metadata.get_mut(id) {
+                entry.dependencies.push(*dependency);
+            }
+        }
+        for (id, date_added) in FEATURE_DATES_ADDED {
+            if let Some(entry) = metadata.get_mut(id) {
+                entry.date_added = Some(date_added);
+            }
+        }
+        metadata
+    };
+}
+
+/// Returns the metadata of a feature.
+pub fn feature_metadata(feature_id: &Pubkey) -> Option<&'static FeatureMetadata> {
+    FEATURE_METADATA.get(feature_id)
+}
+
+/// Returns the features proposed by a SIMD.
+pub fn features_for_simd(simd: u16) -> Vec<&'static FeatureMetadata> {
+    FEATURE_METADATA
+        .values()
+        .filter(|metadata| metadata.simd == Some(simd))
+        .collect()
+}
+
+/// Parses the number following the first `SIMD-` in a description.
+fn parse_simd(description: &str) -> Option<u16> {
+    let (_, rest) = description.split_once("SIMD-")?;
+    leading_number(rest)
+}
+
+/// Parses the number following the first `#` in a description.
+fn parse_github_ref(description: &str) -> Option<u32> {
+    let (_, rest) = description.split_once('#')?;
+    leading_number(rest)
+}
+
+fn leading_number<T: core::str::FromStr>(s: &str) -> Option<T> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse().ok()
+}
+
+/// A feature whose dependencies are not respected by a [`FeatureSet`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeatureConflict {
+    /// `feature` is active but its dependency `missing` is not.
+    MissingDependency { feature: Pubkey, missing: Pubkey },
+}
+
+impl FeatureSet {
+    /// Dependencies of a feature that are not active.
+    pub fn missing_dependencies(&self, feature_id: &Pubkey) -> Vec<Pubkey> {
+        feature_metadata(feature_id)
+            .map(|metadata| {
+                metadata
+                    .dependencies
+                    .iter()
+                    .filter(|dependency| !self.is_active(dependency))
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether an inactive feature has all its dependencies active.
+    pub fn is_activatable(&self, feature_id: &Pubkey) -> bool {
+        self.inactive.contains(feature_id) && self.missing_dependencies(feature_id).is_empty()
+    }
+
+    /// Inactive features that can be activated next, sorted by id.
+    pub fn activatable_features(&self) -> Vec<&'static FeatureMetadata> {
+        let mut features = self
+            .inactive
+            .iter()
+            .filter(|id| self.is_activatable(id))
+            .filter_map(feature_metadata)
+            .collect::<Vec<_>>();
+        features.sort_by_key(|metadata| metadata.id);
+        features
+    }
+
+    /// Active features of a category, sorted by id.
+    pub fn active_features_in_category(&self, category: FeatureCategory) -> Vec<Pubkey> {
+        let mut features = self
+            .active
+            .keys()
+            .filter(|id| feature_metadata(id).map(|metadata| metadata.category) == Some(category))
+            .copied()
+            .collect::<Vec<_>>();
+        features.sort();
+        features
+    }
+
+    /// Active features whose dependencies are not active, sorted by feature
+    /// id.
+    pub fn conflicts(&self) -> Vec<FeatureConflict> {
+        let mut active = self.active.keys().copied().collect::<Vec<_>>();
+        active.sort();
+        active
+            .into_iter()
+            .flat_map(|feature| {
+                self.missing_dependencies(&feature)
+                    .into_iter()
+                    .map(move |missing| FeatureConflict::MissingDependency { feature, missing })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ahash::AHashSet};
+
+    #[test]
+    fn test_feature_metadata() {
+        assert_eq!(FEATURE_METADATA.len(), FEATURE_NAMES.len());
+
+        let metadata = feature_metadata(&raise_block_limits_to_50m::id()).unwrap();
+        assert_eq!(metadata.description, "Raise block limit to 50M SIMD-0207");
+        assert_eq!(metadata.simd, Some(207));
+        assert_eq!(metadata.github_ref, None);
+        assert_eq!(metadata.category, FeatureCategory::Consensus);
+
+        let metadata = feature_metadata(&deplete_cu_meter_on_vm_failure::id()).unwrap();
+        assert_eq!(metadata.simd, Some(182));
+        assert_eq!(metadata.github_ref, Some(3993));
+
+        let metadata = feature_metadata(&snapshots_lt_hash::id()).unwrap();
+        assert_eq!(metadata.dependencies, vec![accounts_lt_hash::id()]);
+        let metadata = feature_metadata(&reenable_sbpf_v0_execution::id()).unwrap();
+        assert_eq!(metadata.dependencies, vec![disable_sbpf_v0_execution::id()]);
+        assert_eq!(metadata.date_added, None);
+
+        assert_eq!(
+            features_for_simd(167)
+                .into_iter()
+                .map(|metadata| metadata.id)
+                .collect::<AHashSet<_>>(),
+            [
+                enable_loader_v4::id(),
+                disable_new_loader_v3_deployments::id()
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(feature_metadata(&Pubkey::new_unique()), None);
+    }
+
+    #[test]
+    fn test_feature_categories() {
+        let mut categorized = AHashSet::new();
+        for (_, ids) in FEATURE_CATEGORIES {
+            for id in *ids {
+                assert!(FEATURE_NAMES.contains_key(id), "unknown feature {id}");
+                assert!(categorized.insert(*id), "feature {id} categorized twice");
+            }
+        }
+        for (a, b) in FEATURE_DEPENDENCIES {
+            assert!(FEATURE_NAMES.contains_key(a), "unknown feature {a}");
+            assert!(FEATURE_NAMES.contains_key(b), "unknown feature {b}");
+        }
+        for (id, date_added) in FEATURE_DATES_ADDED {
+            assert!(FEATURE_NAMES.contains_key(id), "unknown feature {id}");
+            assert!(
+                date_added
+                    .split('-')
+                    .map(|part| part
+                        .bytes()
+                        .all(|b| b.is_ascii_digit())
+                        .then_some(part.len()))
+                    .eq([Some(4), Some(2), Some(2)]),
+                "malformed date {date_added} of feature {id}"
+            );
+        }
+        assert!(FEATURE_METADATA
+            .values()
+            .all(|metadata| metadata.category != FeatureCategory::Other));
+    }
+
+    #[test]
+    fn test_activation_queries() {
+        let mut feature_set = FeatureSet::default();
+        assert!(feature_set.conflicts().is_empty());
+        assert!(feature_set.is_activatable(&accounts_lt_hash::id()));
+        assert!(!feature_set.is_activatable(&snapshots_lt_hash::id()));
+        assert_eq!(
+            feature_set.missing_dependencies(&snapshots_lt_hash::id()),
+            vec![accounts_lt_hash::id()]
+        );
+        let activatable = feature_set.activatable_features();
+        assert!(activatable
+            .iter()
+            .all(|metadata| metadata.dependencies.is_empty()));
+        assert_eq!(
+            activatable.len(),
+            FEATURE_METADATA
+                .values()
+                .filter(|metadata| metadata.dependencies.is_empty())
+                .count()
+        );
+
+        // A revert can only be activated after the feature it reverts
+        assert!(feature_set.is_activatable(&disable_sbpf_v0_execution::id()));
+        assert!(!feature_set.is_activatable(&reenable_sbpf_v0_execution::id()));
+        feature_set.activate(&disable_sbpf_v0_execution::id(), 10);
+        assert!(feature_set.is_activatable(&reenable_sbpf_v0_execution::id()));
+        feature_set.activate(&reenable_sbpf_v0_execution::id(), 20);
+        assert!(feature_set.conflicts().is_empty());
+
+        feature_set.activate(&snapshots_lt_hash::id(), 10);
+        feature_set.activate(&disable_turbine_fanout_experiments::id(), 10);
+        let mut expected = vec![
+            FeatureConflict::MissingDependency {
+                feature: snapshots_lt_hash::id(),
+                missing: accounts_lt_hash::id(),
+            },
+            FeatureConflict::MissingDependency {
+                feature: disable_turbine_fanout_experiments::id(),
+                missing: enable_turbine_fanout_experiments::id(),
+            },
+        ];
+        expected.sort_by_key(|conflict| match conflict {
+            FeatureConflict::MissingDependency { feature, .. } => *feature,
+        });
+        assert_eq!(feature_set.conflicts(), expected);
+        assert_eq!(
+            feature_set.active_features_in_category(FeatureCategory::Accounts),
+            vec![snapshots_lt_hash::id()]
+        );
+
+        feature_set.activate(&accounts_lt_hash::id(), 5);
+        assert!(feature_set.is_activatable(&remove_accounts_delta_hash::id()));
+        assert!(!feature_set.is_activatable(&accounts_lt_hash::id()));
+    }
+}
@@ -0,0 +1,359 @@
+//! Comparing, exporting and importing feature sets.
+//!
+//! A [`FeatureSetSnapshot`] is a stable, human-readable form of a
+//! [`FeatureSet`] that can be written to JSON or TOML, which makes it easy to
+//! compare the features of different clusters with [`FeatureSet::diff`].
+
+#[cfg(feature = "bincode")]
+use solana_account::AccountSharedData;
+use {
+    crate::{FeatureSet, FEATURE_NAMES},
+    solana_pubkey::Pubkey,
+};
+#[cfg(feature = "serde")]
+use {
+    serde_derive::{Deserialize, Serialize},
+    std::str::FromStr,
+};
+
+/// A feature activated at a slot.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActiveFeature {
+    #[cfg_attr(feature = "serde", serde(with = "pubkey_string"))]
+    pub id: Pubkey,
+    pub slot: u64,
+    /// Description from [`FEATURE_NAMES`], for readers of an exported
+    /// snapshot. It is ignored on import.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<String>,
+}
+
+impl ActiveFeature {
+    fn new(id: Pubkey, slot: u64) -> Self {
+        Self {
+            id,
+            slot,
+            description: FEATURE_NAMES.get(&id).map(|name| name.to_string()),
+        }
+    }
+}
+
+/// A stable representation of a [`FeatureSet`].
+///
+/// Active features are ordered by activation slot, then id, and inactive
+/// features by id, so that snapshots of equal feature sets are identical.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSetSnapshot {
+    pub active: Vec<ActiveFeature>,
+    #[cfg_attr(feature = "serde", serde(with = "pubkey_string_vec"))]
+    pub inactive: Vec<Pubkey>,
+}
+
+#[cfg(feature = "serde")]
+impl FeatureSetSnapshot {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+}
+
+impl From<&FeatureSet> for FeatureSetSnapshot {
+    fn from(feature_set: &FeatureSet) -> Self {
+        let mut inactive = feature_set.inactive.iter().copied().collect::<Vec<_>>();
+        inactive.sort();
+        Self {
+            active: feature_set
+                .activation_timeline()
+                .into_iter()
+                .map(|(slot, id)| ActiveFeature::new(id, slot))
+                .collect(),
+            inactive,
+        }
+    }
+}
+
+impl From<&FeatureSetSnapshot> for FeatureSet {
+    /// Features known to this software that the snapshot does not mention
+    /// are inactive.
+    fn from(snapshot: &FeatureSetSnapshot) -> Self {
+        let mut feature_set = FeatureSet::default();
+        feature_set
+            .inactive
+            .extend(snapshot.inactive.iter().copied());
+        for feature in &snapshot.active {
+            feature_set.activate(&feature.id, feature.slot);
+        }
+        feature_set
+    }
+}
+
+/// Differences between the active features of two feature sets, each list
+/// ordered by slot, then id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSetDiff {
+    /// Features active only in the first feature set, with their activation
+    /// slot.
+    pub only_in_self: Vec<(u64, Pubkey)>,
+    /// Features active only in the second feature set, with their
+    /// activation slot.
+    pub only_in_other: Vec<(u64, Pubkey)>,
+    /// Features active in both feature sets at different slots, with the
+    /// activation slot in the first and in the second feature set.
+    pub slot_mismatches: Vec<(u64, u64, Pubkey)>,
+}
+
+impl FeatureSetDiff {
+    /// Whether both feature sets have the same features active, regardless
+    /// of activation slots.
+    pub fn same_active_features(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.same_active_features() && self.slot_mismatches.is_empty()
+    }
+}
+
+impl FeatureSet {
+    /// Active features with their activation slot, ordered by slot, then id.
+    pub fn activation_timeline(&self) -> Vec<(u64, Pubkey)> {
+        let mut timeline = self
+            .active
+            .iter()
+            .map(|(id, slot)| (*slot, *id))
+            .collect::<Vec<_>>();
+        timeline.sort();
+        timeline
+    }
+
+    /// Compares the active features of two feature sets, such as those of two
+    /// clusters.
+    pub fn diff(&self, other: &FeatureSet) -> FeatureSetDiff {
+        let only_in = |a: &FeatureSet, b: &FeatureSet| {
+            a.activation_timeline()
+                .into_iter()
+                .filter(|(_, id)| !b.is_active(id))
+                .collect()
+        };
+        let mut slot_mismatches = self
+            .active
+            .iter()
+            .filter_map(|(id, slot)| {
+                let other_slot = other.activated_slot(id)?;
+                (*slot != other_slot).then_some((*slot, other_slot, *id))
+            })
+            .collect::<Vec<_>>();
+        slot_mismatches.sort();
+        FeatureSetDiff {
+            only_in_self: only_in(self, other),
+            only_in_other: only_in(other, self),
+            slot_mismatches,
+        }
+    }
+
+    /// Builds the feature set of a cluster from its feature accounts.
+    ///
+    /// Accounts that are not feature accounts, or whose feature is not
+    /// activated yet, are skipped. Features without an account are
+    /// inactive.
+    #[cfg(feature = "bincode")]
+    pub fn from_accounts<'a>(
+        accounts: impl IntoIterator<Item = (&'a Pubkey, &'a AccountSharedData)>,
+    ) -> Self {
+        let mut feature_set = FeatureSet::default();
+        for (id, account) in accounts {
+            if let Some(slot) = solana_feature_gate_interface::from_account(account)
+                .and_then(|feature| feature.activated_at)
+            {
+                feature_set.activate(id, slot);
+            }
+        }
+        feature_set
+    }
+}
+
+#[cfg(feature = "serde")]
+mod pubkey_string {
+    use {
+        super::*,
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Pubkey::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod pubkey_string_vec {
+    use {
+        super::*,
+        serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer},
+    };
+
+    pub fn serialize<S: Serializer>(pubkeys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(pubkeys.len()))?;
+        for pubkey in pubkeys {
+            seq.serialize_element(&pubkey.to_string())?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Pubkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| Pubkey::from_str(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::*};
+
+    fn mainnet_and_testnet() -> (FeatureSet, FeatureSet) {
+        let mut mainnet = FeatureSet::default();
+        mainnet.activate(&accounts_lt_hash::id(), 300);
+        mainnet.activate(&enable_loader_v4::id(), 100);
+        let mut testnet = FeatureSet::default();
+        testnet.activate(&accounts_lt_hash::id(), 20);
+        testnet.activate(&snapshots_lt_hash::id(), 30);
+        testnet.activate(&remove_accounts_delta_hash::id(), 10);
+        (mainnet, testnet)
+    }
+
+    #[test]
+    fn test_diff() {
+        let (mainnet, testnet) = mainnet_and_testnet();
+        assert_eq!(
+            testnet.activation_timeline(),
+            vec![
+                (10, remove_accounts_delta_hash::id()),
+                (20, accounts_lt_hash::id()),
+                (30, snapshots_lt_hash::id()),
+            ]
+        );
+
+        let diff = mainnet.diff(&testnet);
+        assert_eq!(diff.only_in_self, vec![(100, enable_loader_v4::id())]);
+        assert_eq!(
+            diff.only_in_other,
+            vec![
+                (10, remove_accounts_delta_hash::id()),
+                (30, snapshots_lt_hash::id()),
+            ]
+        );
+        assert_eq!(
+            diff.slot_mismatches,
+            vec![(300, 20, accounts_lt_hash::id())]
+        );
+        assert!(!diff.same_active_features());
+        assert!(mainnet.diff(&mainnet).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let (mainnet, _) = mainnet_and_testnet();
+        let snapshot = FeatureSetSnapshot::from(&mainnet);
+        assert_eq!(
+            snapshot.active[0],
+            ActiveFeature {
+                id: enable_loader_v4::id(),
+                slot: 100,
+                description: Some("Enable Loader-v4 SIMD-0167".to_string()),
+            }
+        );
+        assert_eq!(snapshot.inactive.len(), FEATURE_NAMES.len() - 2);
+        assert_eq!(FeatureSet::from(&snapshot), mainnet);
+
+        // Features missing from a snapshot are inactive
+        let partial = FeatureSetSnapshot {
+            active: vec![ActiveFeature {
+                id: enable_loader_v4::id(),
+                slot: 100,
+                description: None,
+            }],
+            inactive: vec![],
+        };
+        let feature_set = FeatureSet::from(&partial);
+        assert_eq!(
+            feature_set.activation_timeline(),
+            vec![(100, enable_loader_v4::id())]
+        );
+        assert_eq!(feature_set.inactive.len(), FEATURE_NAMES.len() - 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_serialization() {
+        let (mainnet, _) = mainnet_and_testnet();
+        let snapshot = FeatureSetSnapshot::from(&mainnet);
+
+        let json = snapshot.to_json().unwrap();
+        assert!(json.contains(&format!(
+            "\"id\": \"{}\",\n      \"slot\": 100,",
+            enable_loader_v4::id()
+        )));
+        assert_eq!(FeatureSetSnapshot::from_json(&json).unwrap(), snapshot);
+
+        let toml = snapshot.to_toml().unwrap();
+        assert!(toml.contains(&format!(
+            "[[active]]\nid = \"{}\"\nslot = 100\n",
+            enable_loader_v4::id()
+        )));
+        assert_eq!(FeatureSetSnapshot::from_toml(&toml).unwrap(), snapshot);
+
+        assert!(FeatureSetSnapshot::from_json(
+            r#"{"active": [{"id": "not a pubkey", "slot": 0}], "inactive": []}"#
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_from_accounts() {
+        use solana_feature_gate_interface::{create_account, Feature};
+
+        let activated = create_account(
+            &Feature {
+                activated_at: Some(42),
+            },
+            1,
+        );
+        let pending = create_account(&Feature { activated_at: None }, 1);
+        let not_a_feature = AccountSharedData::new(1, 0, &Pubkey::new_unique());
+        let feature_set = FeatureSet::from_accounts([
+            (&enable_loader_v4::id(), &activated),
+            (&accounts_lt_hash::id(), &pending),
+            (&snapshots_lt_hash::id(), &not_a_feature),
+        ]);
+        assert_eq!(
+            feature_set.activation_timeline(),
+            vec![(42, enable_loader_v4::id())]
+        );
+        assert_eq!(feature_set.inactive.len(), FEATURE_NAMES.len() - 1);
+    }
+}
@@ -0,0 +1,290 @@
+//! Running tests against many feature set configurations.
+//!
+//! Code that branches on [`FeatureSet::is_active`] should be tested with the
+//! features both active and inactive. [`FeatureMatrix`] generates feature
+//! sets for a test closure and reports which configurations it failed in.
+//!
+//! ```
+//! # #![allow(deprecated)]
+//! use solana_feature_set::{enable_loader_v4, test_matrix::FeatureMatrix};
+//!
+//! FeatureMatrix::new()
+//!     .with_all_enabled()
+//!     .with_none_enabled()
+//!     .with_each_disabled()
+//!     .assert_passes(|feature_set| {
+//!         let _loader_v4 = feature_set.is_active(&enable_loader_v4::id());
+//!     });
+//! ```
+
+use {
+    crate::{FeatureSet, FEATURE_NAMES},
+    solana_pubkey::Pubkey,
+    std::{
+        any::Any,
+        fmt,
+        panic::{catch_unwind, AssertUnwindSafe},
+    },
+};
+
+/// A labeled feature set to run a test against.
+#[derive(Clone, Debug)]
+pub struct FeatureConfiguration {
+    pub label: String,
+    pub feature_set: FeatureSet,
+}
+
+/// A set of feature set configurations to run a test against.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureMatrix {
+    configurations: Vec<FeatureConfiguration>,
+}
+
+fn feature_label(feature_id: &Pubkey) -> String {
+    match FEATURE_NAMES.get(feature_id) {
+        Some(name) => format!("{feature_id} ({name})"),
+        None => feature_id.to_string(),
+    }
+}
+
+fn all_enabled_except(disabled: &[Pubkey]) -> FeatureSet {
+    let mut feature_set = FeatureSet::all_enabled();
+    for feature_id in disabled {
+        feature_set.deactivate(feature_id);
+    }
+    feature_set
+}
+
+impl FeatureMatrix {
+    /// Creates a matrix without configurations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a configuration.
+    pub fn with_configuration(mut self, label: impl Into<String>, feature_set: FeatureSet) -> Self {
+        self.configurations.push(FeatureConfiguration {
+            label: label.into(),
+            feature_set,
+        });
+        self
+    }
+
+    /// Adds [`FeatureSet::all_enabled`].
+    pub fn with_all_enabled(self) -> Self {
+        self.with_configuration("all enabled", FeatureSet::all_enabled())
+    }
+
+    /// Adds [`FeatureSet::default`], with no feature active.
+    pub fn with_none_enabled(self) -> Self {
+        self.with_configuration("none enabled", FeatureSet::default())
+    }
+
+    /// Adds one configuration per feature, with only that feature disabled.
+    pub fn with_each_disabled(self) -> Self {
+        let mut feature_ids = FEATURE_NAMES.keys().copied().collect::<Vec<_>>();
+        feature_ids.sort();
+        self.with_each_disabled_from(&feature_ids)
+    }
+
+    /// Adds one configuration per given feature, with only that feature
+    /// disabled.
+    pub fn with_each_disabled_from(mut self, feature_ids: &[Pubkey]) -> Self {
+        for feature_id in feature_ids {
+            self = self.with_configuration(
+                format!("all enabled except {}", feature_label(feature_id)),
+                all_enabled_except(&[*feature_id]),
+            );
+        }
+        self
+    }
+
+    /// Adds, for each pair of features, the configurations with either or
+    /// both of them disabled and all other features enabled.
+    pub fn with_pairs(mut self, pairs: &[(Pubkey, Pubkey)]) -> Self {
+        for (a, b) in pairs {
+            for disabled in [vec![*a], vec![*b], vec![*a, *b]] {
+                let labels = disabled.iter().map(feature_label).collect::<Vec<_>>();
+                self = self.with_configuration(
+                    format!("all enabled except {}", labels.join(" and ")),
+                    all_enabled_except(&disabled),
+                );
+            }
+        }
+        self
+    }
+
+    pub fn configurations(&self) -> &[FeatureConfiguration] {
+        &self.configurations
+    }
+
+    /// Runs `test` against every configuration, recording the ones in which
+    /// it panicked.
+    pub fn run<F: FnMut(&FeatureSet)>(&self, mut test: F) -> FeatureMatrixReport {
+        let failures = self
+            .configurations
+            .iter()
+            .filter_map(|configuration| {
+                catch_unwind(AssertUnwindSafe(|| test(&configuration.feature_set)))
+                    .err()
+                    .map(|payload| FeatureMatrixFailure {
+                        label: configuration.label.clone(),
+                        message: panic_message(payload.as_ref()),
+                    })
+            })
+            .collect();
+        FeatureMatrixReport {
+            configurations: self.configurations.len(),
+            failures,
+        }
+    }
+
+    /// Runs `test` against every configuration, and panics with a report of
+    /// the configurations that failed, if any.
+    pub fn assert_passes<F: FnMut(&FeatureSet)>(&self, test: F) {
+        let report = self.run(test);
+        assert!(report.passed(), "{report}");
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// A configuration in which a test failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeatureMatrixFailure {
+    pub label: String,
+    /// The panic message of the test.
+    pub message: String,
+}
+
+/// Outcome of [`FeatureMatrix::run`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeatureMatrixReport {
+    /// Number of configurations the test ran against.
+    pub configurations: usize,
+    pub failures: Vec<FeatureMatrixFailure>,
+}
+
+impl FeatureMatrixReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for FeatureMatrixReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} feature configurations failed",
+            self.failures.len(),
+            self.configurations
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n- {}: {}", failure.label, failure.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::*};
+
+    #[test]
+    fn test_configurations() {
+        let matrix = FeatureMatrix::new()
+            .with_all_enabled()
+            .with_none_enabled()
+            .with_each_disabled();
+        let configurations = matrix.configurations();
+        assert_eq!(configurations.len(), FEATURE_NAMES.len() + 2);
+        assert_eq!(configurations[0].feature_set, FeatureSet::all_enabled());
+        assert_eq!(configurations[1].feature_set, FeatureSet::default());
+        for configuration in &configurations[2..] {
+            assert_eq!(configuration.feature_set.inactive.len(), 1);
+        }
+
+        let matrix =
+            FeatureMatrix::new().with_pairs(&[(accounts_lt_hash::id(), snapshots_lt_hash::id())]);
+        let disabled = matrix
+            .configurations()
+            .iter()
+            .map(|configuration| {
+                let mut inactive = configuration
+                    .feature_set
+                    .inactive
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>();
+                inactive.sort();
+                inactive
+            })
+            .collect::<Vec<_>>();
+        let mut both = vec![accounts_lt_hash::id(), snapshots_lt_hash::id()];
+        both.sort();
+        assert_eq!(
+            disabled,
+            vec![
+                vec![accounts_lt_hash::id()],
+                vec![snapshots_lt_hash::id()],
+                both
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_reports_failures() {
+        let matrix = FeatureMatrix::new()
+            .with_all_enabled()
+            .with_none_enabled()
+            .with_each_disabled_from(&[enable_loader_v4::id(), accounts_lt_hash::id()]);
+
+        let mut runs = 0;
+        let report = matrix.run(|feature_set| {
+            runs += 1;
+            assert!(
+                feature_set.is_active(&enable_loader_v4::id()),
+                "loader-v4 is disabled"
+            );
+        });
+        assert_eq!(runs, 4);
+        assert!(!report.passed());
+        assert_eq!(
+            report.failures,
+            vec![
+                FeatureMatrixFailure {
+                    label: "none enabled".to_string(),
+                    message: "loader-v4 is disabled".to_string(),
+                },
+                FeatureMatrixFailure {
+                    label: format!(
+                        "all enabled except {} (Enable Loader-v4 SIMD-0167)",
+                        enable_loader_v4::id()
+                    ),
+                    message: "loader-v4 is disabled".to_string(),
+                },
+            ]
+        );
+        assert!(report
+            .to_string()
+            .starts_with("2 of 4 feature configurations failed\n- none enabled: "));
+
+        matrix.assert_passes(|_| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of 1 feature configurations failed")]
+    fn test_assert_passes() {
+        FeatureMatrix::new()
+            .with_none_enabled()
+            .assert_passes(|feature_set| assert!(feature_set.is_active(&accounts_lt_hash::id())));
+    }
+}
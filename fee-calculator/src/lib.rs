@@ -4,9 +4,14 @@
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 use log::*;
+#[cfg(feature = "simulation")]
+extern crate alloc;
 #[cfg(feature = "frozen-abi")]
 extern crate std;
 
+#[cfg(feature = "simulation")]
+pub mod simulation;
+
 #[repr(C)]
 #[cfg_attr(feature = "frozen-abi", derive(solana_frozen_abi_macro::AbiExample))]
 #[cfg_attr(
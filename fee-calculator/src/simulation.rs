@@ -0,0 +1,162 @@
+//! Simulation of the fee rate over a series of slots.
+//!
+//! Each bank derives its `FeeRateGovernor` from the one of its parent and the
+//! number of signatures the parent processed, with
+//! [`FeeRateGovernor::new_derived`]. [`simulate`] replays that for a series
+//! of per-slot signature counts, so that the governor parameters of a
+//! genesis config can be tuned against a synthetic load.
+
+use {crate::FeeRateGovernor, alloc::vec::Vec};
+
+/// The fees of one simulated slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotFees {
+    /// Index of the slot in the simulated series.
+    pub slot_index: usize,
+    pub signatures: u64,
+    /// The fee rate charged in the slot.
+    pub lamports_per_signature: u64,
+    pub fees: u64,
+    pub burned: u64,
+    /// Fees that go to the leader of the slot.
+    pub validator_revenue: u64,
+}
+
+/// The result of a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeSimulation {
+    pub slots: Vec<SlotFees>,
+    /// The governor the slot after the series would charge with.
+    pub next_fee_rate_governor: FeeRateGovernor,
+}
+
+impl FeeSimulation {
+    pub fn total_fees(&self) -> u64 {
+        self.slots
+            .iter()
+            .fold(0, |total, slot| total.saturating_add(slot.fees))
+    }
+
+    pub fn total_burned(&self) -> u64 {
+        self.slots
+            .iter()
+            .fold(0, |total, slot| total.saturating_add(slot.burned))
+    }
+
+    pub fn total_validator_revenue(&self) -> u64 {
+        self.slots.iter().fold(0, |total, slot| {
+            total.saturating_add(slot.validator_revenue)
+        })
+    }
+
+    /// Lowest and highest fee rates charged over the series.
+    pub fn lamports_per_signature_range(&self) -> Option<(u64, u64)> {
+        let rates = self.slots.iter().map(|slot| slot.lamports_per_signature);
+        Some((rates.clone().min()?, rates.max()?))
+    }
+}
+
+/// Simulates the fee rate over slots processing `signatures_per_slot`
+/// signatures. The first slot charges the rate of `fee_rate_governor`, and
+/// each following slot the rate derived from the signatures of the slot
+/// before it.
+pub fn simulate(
+    fee_rate_governor: &FeeRateGovernor,
+    signatures_per_slot: impl IntoIterator<Item = u64>,
+) -> FeeSimulation {
+    let mut fee_rate_governor = fee_rate_governor.clone();
+    let slots = signatures_per_slot
+        .into_iter()
+        .enumerate()
+        .map(|(slot_index, signatures)| {
+            let lamports_per_signature = fee_rate_governor.lamports_per_signature;
+            let fees = signatures.saturating_mul(lamports_per_signature);
+            let (validator_revenue, burned) = fee_rate_governor.burn(fees);
+            fee_rate_governor = FeeRateGovernor::new_derived(&fee_rate_governor, signatures);
+            SlotFees {
+                slot_index,
+                signatures,
+                lamports_per_signature,
+                fees,
+                burned,
+                validator_revenue,
+            }
+        })
+        .collect();
+    FeeSimulation {
+        slots,
+        next_fee_rate_governor: fee_rate_governor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec};
+
+    fn fee_rate_governor(burn_percent: u8) -> FeeRateGovernor {
+        FeeRateGovernor {
+            lamports_per_signature: 10_000,
+            burn_percent,
+            ..FeeRateGovernor::new(10_000, 100)
+        }
+    }
+
+    #[test]
+    fn test_simulate_steady_load() {
+        let fee_rate_governor = fee_rate_governor(50);
+        let simulation = simulate(&fee_rate_governor, vec![100; 10]);
+        assert!(simulation
+            .slots
+            .iter()
+            .all(|slot| slot.lamports_per_signature == 10_000));
+        assert_eq!(simulation.total_fees(), 10 * 100 * 10_000);
+        assert_eq!(simulation.total_burned(), simulation.total_fees() / 2);
+        assert_eq!(
+            simulation.total_validator_revenue(),
+            simulation.total_fees() / 2
+        );
+        assert_eq!(
+            simulation.next_fee_rate_governor.lamports_per_signature,
+            10_000
+        );
+    }
+
+    #[test]
+    fn test_simulate_load_spike() {
+        let fee_rate_governor = fee_rate_governor(100);
+        // Fees rise by 5% of the target per slot under load, up to 10x the
+        // target, and fall back down to half of it once the load is gone
+        let load = vec![10_000; 300].into_iter().chain(vec![0; 300]);
+        let simulation = simulate(&fee_rate_governor, load);
+
+        let rates: Vec<_> = simulation
+            .slots
+            .iter()
+            .map(|slot| slot.lamports_per_signature)
+            .collect();
+        assert_eq!(&rates[..3], &[10_000, 10_500, 11_000]);
+        assert_eq!(rates[299], 100_000);
+        assert_eq!(rates[300], 100_000);
+        assert_eq!(rates[301], 99_500);
+        assert_eq!(rates[599], 5_000);
+        assert_eq!(
+            simulation.lamports_per_signature_range(),
+            Some((5_000, 100_000))
+        );
+        assert_eq!(simulation.total_burned(), simulation.total_fees());
+        assert_eq!(simulation.total_validator_revenue(), 0);
+        assert_eq!(simulation.slots[300].fees, 0);
+
+        // A governor without a target signature rate keeps a fixed rate
+        let fixed = FeeRateGovernor::new(10_000, 0);
+        let simulation = simulate(&fixed, vec![10_000, 0, 10_000]);
+        assert_eq!(
+            simulation.lamports_per_signature_range(),
+            Some((10_000, 10_000))
+        );
+        assert_eq!(
+            simulate(&fixed, vec![]).lamports_per_signature_range(),
+            None
+        );
+    }
+}
@@ -0,0 +1,639 @@
+//! Estimating what a transaction will cost.
+//!
+//! [`FeeEstimator`] combines the [`FeeStructure`], the current
+//! [`FeeRateGovernor`] and the active features of a cluster to compute the
+//! fee of a message before it is sent, and how that fee is split between
+//! the burn and the leader.
+//!
+//! The compute unit limit of a message without a `SetComputeUnitLimit`
+//! instruction is derived from its instructions. Once
+//! `reserve_minimal_cus_for_builtin_instructions` is active, instructions of
+//! builtin programs only reserve 3,000 units, as in the runtime's builtin
+//! cost table. Builtins migrating to BPF count as builtins until their
+//! migration feature is active.
+
+use {
+    crate::{FeeBudgetLimits, FeeDetails, FeeStructure},
+    borsh::BorshDeserialize,
+    solana_compute_budget_interface::ComputeBudgetInstruction,
+    solana_fee_calculator::FeeRateGovernor,
+    solana_instruction::error::InstructionError,
+    solana_message::{
+        v0::LoadedAddresses, SanitizedMessage, SanitizedVersionedMessage, SimpleAddressLoader,
+        TransactionSignatureDetails, VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::{
+        address_lookup_table, bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable,
+        compute_budget, config, ed25519_program, loader_v4, secp256k1_program, stake,
+        system_program, vote,
+    },
+    solana_transaction_error::TransactionError,
+    std::{collections::HashSet, num::NonZeroU32},
+};
+
+/// Compute units reserved by an instruction when the transaction does not
+/// set a compute unit limit.
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Compute units reserved by an instruction of a builtin program when the
+/// transaction does not set a compute unit limit, once
+/// `reserve_minimal_cus_for_builtin_instructions` is active.
+const MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT: u32 = 3_000;
+/// The largest compute unit limit of a transaction.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// The largest amount of account data a transaction may load, in bytes.
+const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: NonZeroU32 = match NonZeroU32::new(64 * 1024 * 1024) {
+    Some(bytes) => bytes,
+    None => panic!("must be non-zero"),
+};
+/// Compute units charged per 32 KiB of requested heap.
+const DEFAULT_HEAP_COST: u64 = 8;
+/// Compute unit prices are set in micro-lamports.
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// Builtin programs and precompiles of the runtime's builtin cost table that
+/// are not migrating to BPF.
+const BUILTIN_PROGRAMS: [Pubkey; 9] = [
+    system_program::ID,
+    vote::ID,
+    bpf_loader_deprecated::ID,
+    bpf_loader::ID,
+    bpf_loader_upgradeable::ID,
+    loader_v4::ID,
+    compute_budget::ID,
+    secp256k1_program::ID,
+    ed25519_program::ID,
+];
+
+/// The features that change the fee of a transaction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FeeFeatures {
+    pub reserve_minimal_cus_for_builtin_instructions: bool,
+    pub reward_full_priority_fee: bool,
+    pub include_loaded_accounts_data_size_in_fee_calculation: bool,
+    pub migrate_stake_program_to_core_bpf: bool,
+    pub migrate_config_program_to_core_bpf: bool,
+    pub migrate_address_lookup_table_program_to_core_bpf: bool,
+}
+
+impl FeeFeatures {
+    /// Whether instructions of `program_id` reserve the compute units of a
+    /// builtin program.
+    fn is_builtin(&self, program_id: &Pubkey) -> bool {
+        let migrated = if stake::check_id(program_id) {
+            self.migrate_stake_program_to_core_bpf
+        } else if config::check_id(program_id) {
+            self.migrate_config_program_to_core_bpf
+        } else if address_lookup_table::check_id(program_id) {
+            self.migrate_address_lookup_table_program_to_core_bpf
+        } else {
+            return BUILTIN_PROGRAMS.contains(program_id);
+        };
+        !migrated
+    }
+}
+
+#[allow(deprecated)]
+impl From<&solana_feature_set::FeatureSet> for FeeFeatures {
+    fn from(feature_set: &solana_feature_set::FeatureSet) -> Self {
+        use solana_feature_set::{
+            include_loaded_accounts_data_size_in_fee_calculation,
+            migrate_address_lookup_table_program_to_core_bpf, migrate_config_program_to_core_bpf,
+            migrate_stake_program_to_core_bpf, reserve_minimal_cus_for_builtin_instructions,
+            reward_full_priority_fee,
+        };
+        Self {
+            reserve_minimal_cus_for_builtin_instructions: feature_set
+                .is_active(&reserve_minimal_cus_for_builtin_instructions::id()),
+            reward_full_priority_fee: feature_set.is_active(&reward_full_priority_fee::id()),
+            include_loaded_accounts_data_size_in_fee_calculation: feature_set
+                .is_active(&include_loaded_accounts_data_size_in_fee_calculation::id()),
+            migrate_stake_program_to_core_bpf: feature_set
+                .is_active(&migrate_stake_program_to_core_bpf::id()),
+            migrate_config_program_to_core_bpf: feature_set
+                .is_active(&migrate_config_program_to_core_bpf::id()),
+            migrate_address_lookup_table_program_to_core_bpf: feature_set
+                .is_active(&migrate_address_lookup_table_program_to_core_bpf::id()),
+        }
+    }
+}
+
+/// The signatures a transaction pays for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureCounts {
+    pub transaction: u64,
+    pub ed25519: u64,
+    pub secp256k1: u64,
+    pub secp256r1: u64,
+}
+
+impl SignatureCounts {
+    pub fn total(&self) -> u64 {
+        self.transaction
+            .saturating_add(self.ed25519)
+            .saturating_add(self.secp256k1)
+            .saturating_add(self.secp256r1)
+    }
+}
+
+impl From<&TransactionSignatureDetails> for SignatureCounts {
+    fn from(details: &TransactionSignatureDetails) -> Self {
+        Self {
+            transaction: details.num_transaction_signatures(),
+            ed25519: details.num_ed25519_instruction_signatures(),
+            secp256k1: details.num_secp256k1_instruction_signatures(),
+            secp256r1: details.num_secp256r1_instruction_signatures(),
+        }
+    }
+}
+
+/// The estimated cost of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub signatures: SignatureCounts,
+    pub compute_unit_limit: u32,
+    /// Price of a compute unit, in micro-lamports.
+    pub compute_unit_price: u64,
+    pub loaded_accounts_data_size_limit: NonZeroU32,
+    pub fee_details: FeeDetails,
+    /// Part of the fee that is burnt.
+    pub burned: u64,
+    /// Part of the fee that goes to the leader.
+    pub reward: u64,
+}
+
+impl FeeEstimate {
+    pub fn total_fee(&self) -> u64 {
+        self.fee_details.total_fee()
+    }
+}
+
+/// The estimates of a message under two sets of features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureImpact {
+    pub current: FeeEstimate,
+    pub with_features: FeeEstimate,
+}
+
+impl FeatureImpact {
+    /// Change of the total fee, in lamports.
+    pub fn total_fee_change(&self) -> i128 {
+        i128::from(self.with_features.total_fee())
+            .saturating_sub(i128::from(self.current.total_fee()))
+    }
+
+    /// Change of the leader reward, in lamports.
+    pub fn reward_change(&self) -> i128 {
+        i128::from(self.with_features.reward).saturating_sub(i128::from(self.current.reward))
+    }
+}
+
+/// The compute budget a message requests with its compute budget
+/// instructions.
+#[derive(Default)]
+struct RequestedBudget {
+    heap_frame: bool,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    loaded_accounts_data_size_limit: Option<u32>,
+}
+
+impl RequestedBudget {
+    /// Records the compute budget instruction at `index`. Like the runtime,
+    /// fails on instructions that cannot be decoded and on a request that is
+    /// repeated. The size of a requested heap frame is not checked, as it
+    /// does not change the fee.
+    fn add(&mut self, index: u8, mut data: &[u8]) -> Result<(), TransactionError> {
+        let duplicate = match ComputeBudgetInstruction::deserialize(&mut data) {
+            Ok(ComputeBudgetInstruction::RequestHeapFrame(_)) => {
+                std::mem::replace(&mut self.heap_frame, true)
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                self.compute_unit_limit.replace(units).is_some()
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                self.compute_unit_price.replace(micro_lamports).is_some()
+            }
+            Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => self
+                .loaded_accounts_data_size_limit
+                .replace(bytes)
+                .is_some(),
+            Ok(ComputeBudgetInstruction::Unused) | Err(_) => {
+                return Err(TransactionError::InstructionError(
+                    index,
+                    InstructionError::InvalidInstructionData,
+                ))
+            }
+        };
+        if duplicate {
+            return Err(TransactionError::DuplicateInstruction(index));
+        }
+        Ok(())
+    }
+}
+
+/// Estimates transaction fees for a cluster.
+#[derive(Debug, Clone, Copy)]
+pub struct FeeEstimator<'a> {
+    pub fee_structure: &'a FeeStructure,
+    pub fee_rate_governor: &'a FeeRateGovernor,
+    pub features: FeeFeatures,
+}
+
+impl<'a> FeeEstimator<'a> {
+    pub fn new(
+        fee_structure: &'a FeeStructure,
+        fee_rate_governor: &'a FeeRateGovernor,
+        features: FeeFeatures,
+    ) -> Self {
+        Self {
+            fee_structure,
+            fee_rate_governor,
+            features,
+        }
+    }
+
+    /// Estimates the fee of a message, or returns the error the runtime
+    /// rejects it with before charging a fee.
+    pub fn estimate_sanitized(
+        &self,
+        message: &SanitizedMessage,
+    ) -> Result<FeeEstimate, TransactionError> {
+        let mut requested = RequestedBudget::default();
+        let mut default_limit = 0u32;
+        for (index, (program_id, instruction)) in message.program_instructions_iter().enumerate() {
+            let is_compute_budget = compute_budget::check_id(program_id);
+            if is_compute_budget {
+                requested.add(index as u8, &instruction.data)?;
+            }
+            let instruction_limit = if self.features.reserve_minimal_cus_for_builtin_instructions {
+                if self.features.is_builtin(program_id) {
+                    MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT
+                } else {
+                    DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+                }
+            } else if is_compute_budget {
+                0
+            } else {
+                DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+            };
+            default_limit = default_limit.saturating_add(instruction_limit);
+        }
+        let compute_unit_limit = requested
+            .compute_unit_limit
+            .unwrap_or(default_limit)
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+        let compute_unit_price = requested.compute_unit_price.unwrap_or_default();
+        let loaded_accounts_data_size_limit = match requested.loaded_accounts_data_size_limit {
+            Some(bytes) => NonZeroU32::new(bytes)
+                .ok_or(TransactionError::InvalidLoadedAccountsDataSizeLimit)?
+                .min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+            None => MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+        };
+        let prioritization_fee = u128::from(compute_unit_price)
+            .saturating_mul(u128::from(compute_unit_limit))
+            .div_ceil(u128::from(MICRO_LAMPORTS_PER_LAMPORT));
+
+        let budget_limits = FeeBudgetLimits {
+            loaded_accounts_data_size_limit,
+            heap_cost: DEFAULT_HEAP_COST,
+            compute_unit_limit: u64::from(compute_unit_limit),
+            prioritization_fee: u64::try_from(prioritization_fee).unwrap_or(u64::MAX),
+        };
+        #[allow(deprecated)]
+        let fee_details = self.fee_structure.calculate_fee_details(
+            message,
+            self.fee_rate_governor.lamports_per_signature,
+            &budget_limits,
+            self.features
+                .include_loaded_accounts_data_size_in_fee_calculation,
+        );
+        let burned = if self.features.reward_full_priority_fee {
+            self.fee_rate_governor.burn(fee_details.transaction_fee()).1
+        } else {
+            self.fee_rate_governor.burn(fee_details.total_fee()).1
+        };
+        Ok(FeeEstimate {
+            signatures: SignatureCounts::from(&message.get_signature_details()),
+            compute_unit_limit,
+            compute_unit_price,
+            loaded_accounts_data_size_limit,
+            fee_details,
+            burned,
+            reward: fee_details.total_fee().saturating_sub(burned),
+        })
+    }
+
+    /// Estimates the fee of a message that is not sanitized yet. Addresses
+    /// loaded from lookup tables only count as write locks, so they are not
+    /// resolved.
+    pub fn estimate_versioned(
+        &self,
+        message: &VersionedMessage,
+    ) -> Result<FeeEstimate, TransactionError> {
+        let message = SanitizedVersionedMessage::try_new(message.clone())
+            .map_err(|_| TransactionError::SanitizeFailure)?;
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in message.message.address_table_lookups().unwrap_or_default() {
+            loaded_addresses
+                .writable
+                .extend(lookup.writable_indexes.iter().map(|_| Pubkey::new_unique()));
+            loaded_addresses
+                .readonly
+                .extend(lookup.readonly_indexes.iter().map(|_| Pubkey::new_unique()));
+        }
+        let message = SanitizedMessage::try_new(
+            message,
+            SimpleAddressLoader::Enabled(loaded_addresses),
+            &HashSet::default(),
+        )?;
+        self.estimate_sanitized(&message)
+    }
+
+    /// Estimates a message under the current features and under `features`.
+    pub fn feature_impact(
+        &self,
+        message: &SanitizedMessage,
+        features: FeeFeatures,
+    ) -> Result<FeatureImpact, TransactionError> {
+        Ok(FeatureImpact {
+            current: self.estimate_sanitized(message)?,
+            with_features: Self { features, ..*self }.estimate_sanitized(message)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_message::{
+            compiled_instruction::CompiledInstruction,
+            v0::{self, MessageAddressTableLookup},
+            Message, MessageHeader,
+        },
+        solana_sdk_ids::secp256r1_program,
+    };
+
+    fn fee_rate_governor() -> FeeRateGovernor {
+        FeeRateGovernor {
+            lamports_per_signature: 5_000,
+            ..FeeRateGovernor::default()
+        }
+    }
+
+    fn sanitize(instructions: &[Instruction]) -> SanitizedMessage {
+        let message = Message::new(instructions, Some(&Pubkey::new_unique()));
+        SanitizedMessage::try_from_legacy_message(message, &HashSet::default()).unwrap()
+    }
+
+    fn instructions() -> Vec<Instruction> {
+        let program = Pubkey::new_unique();
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            Instruction::new_with_bytes(secp256r1_program::id(), &[2, 0], vec![]),
+            Instruction::new_with_bytes(ed25519_program::id(), &[1, 0], vec![]),
+            Instruction::new_with_bytes(secp256k1_program::id(), &[3], vec![]),
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            ),
+            Instruction::new_with_bytes(system_program::id(), &[], vec![]),
+        ]
+    }
+
+    #[test]
+    fn test_estimate() {
+        let fee_structure = FeeStructure::default();
+        let fee_rate_governor = fee_rate_governor();
+        let estimator =
+            FeeEstimator::new(&fee_structure, &fee_rate_governor, FeeFeatures::default());
+        let message = sanitize(&instructions());
+
+        let estimate = estimator.estimate_sanitized(&message).unwrap();
+        assert_eq!(
+            estimate.signatures,
+            SignatureCounts {
+                transaction: 1,
+                ed25519: 1,
+                secp256k1: 3,
+                secp256r1: 2,
+            }
+        );
+        // Five instructions besides the compute budget one
+        assert_eq!(estimate.compute_unit_limit, 1_000_000);
+        assert_eq!(estimate.compute_unit_price, 10_000);
+        assert_eq!(
+            estimate.loaded_accounts_data_size_limit,
+            MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+        );
+        assert_eq!(estimate.fee_details, FeeDetails::new(7 * 5_000, 10_000));
+        assert_eq!(estimate.burned, (35_000 + 10_000) / 2);
+        assert_eq!(estimate.reward, (35_000 + 10_000) / 2);
+
+        // No fees are charged while the rate is zero
+        let free = FeeRateGovernor::default();
+        let estimate = FeeEstimator::new(&fee_structure, &free, FeeFeatures::default())
+            .estimate_sanitized(&message)
+            .unwrap();
+        assert_eq!(estimate.total_fee(), 0);
+    }
+
+    #[test]
+    fn test_estimate_versioned() {
+        let fee_structure = FeeStructure {
+            lamports_per_write_lock: 100,
+            ..FeeStructure::default()
+        };
+        let fee_rate_governor = fee_rate_governor();
+        let estimator =
+            FeeEstimator::new(&fee_structure, &fee_rate_governor, FeeFeatures::default());
+        let legacy = Message::new(&instructions(), Some(&Pubkey::new_unique()));
+        let sanitized =
+            SanitizedMessage::try_from_legacy_message(legacy.clone(), &HashSet::default()).unwrap();
+        assert_eq!(
+            estimator.estimate_versioned(&VersionedMessage::Legacy(legacy)),
+            estimator.estimate_sanitized(&sanitized)
+        );
+
+        // The payer and the writable address of the lookup table are locked
+        let mut message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![],
+                vec![2, 3],
+            )],
+            ..v0::Message::default()
+        };
+        let estimate = estimator
+            .estimate_versioned(&VersionedMessage::V0(message.clone()))
+            .unwrap();
+        assert_eq!(estimate.fee_details, FeeDetails::new(5_000 + 2 * 100, 0));
+
+        message.header.num_required_signatures = 0;
+        assert_eq!(
+            estimator.estimate_versioned(&VersionedMessage::V0(message)),
+            Err(TransactionError::SanitizeFailure)
+        );
+    }
+
+    #[test]
+    fn test_estimate_rejected() {
+        let fee_structure = FeeStructure::default();
+        let fee_rate_governor = fee_rate_governor();
+        let estimator =
+            FeeEstimator::new(&fee_structure, &fee_rate_governor, FeeFeatures::default());
+
+        let mut instructions = instructions();
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(20_000));
+        assert_eq!(
+            estimator.estimate_sanitized(&sanitize(&instructions)),
+            Err(TransactionError::DuplicateInstruction(6))
+        );
+
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+            ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+            ComputeBudgetInstruction::set_compute_unit_limit(2_000),
+        ];
+        assert_eq!(
+            estimator.estimate_sanitized(&sanitize(&instructions)),
+            Err(TransactionError::DuplicateInstruction(2))
+        );
+
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+            Instruction::new_with_bytes(compute_budget::id(), &[9], vec![]),
+        ];
+        assert_eq!(
+            estimator.estimate_sanitized(&sanitize(&instructions)),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+
+        let instructions = [ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(0)];
+        assert_eq!(
+            estimator.estimate_sanitized(&sanitize(&instructions)),
+            Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_feature_impact() {
+        use solana_feature_set::{
+            reserve_minimal_cus_for_builtin_instructions, reward_full_priority_fee, FeatureSet,
+        };
+
+        let fee_structure = FeeStructure::default();
+        let fee_rate_governor = fee_rate_governor();
+        let estimator =
+            FeeEstimator::new(&fee_structure, &fee_rate_governor, FeeFeatures::default());
+        let message = sanitize(&instructions());
+
+        let mut feature_set = FeatureSet::default();
+        feature_set.activate(&reward_full_priority_fee::id(), 0);
+        feature_set.activate(&reserve_minimal_cus_for_builtin_instructions::id(), 0);
+        let impact = estimator
+            .feature_impact(&message, FeeFeatures::from(&feature_set))
+            .unwrap();
+        let estimate = impact.with_features;
+        // Only the secp256r1 and the unknown program instructions are not
+        // builtin
+        assert_eq!(estimate.compute_unit_limit, 4 * 3_000 + 2 * 200_000);
+        let prioritization_fee = 412_000 * 10_000 / 1_000_000;
+        assert_eq!(
+            estimate.fee_details,
+            FeeDetails::new(7 * 5_000, prioritization_fee)
+        );
+        assert_eq!(estimate.burned, 7 * 5_000 / 2);
+        assert_eq!(estimate.reward, 7 * 5_000 / 2 + prioritization_fee);
+        assert_eq!(
+            impact.total_fee_change(),
+            i128::from(prioritization_fee) - 10_000
+        );
+        assert_eq!(
+            impact.reward_change(),
+            i128::from(7 * 5_000 / 2 + prioritization_fee) - (35_000 + 10_000) / 2
+        );
+
+        // An explicit limit overrides the default one, up to the maximum
+        let mut instructions = instructions();
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(2_000_000));
+        assert_eq!(
+            estimator
+                .estimate_sanitized(&sanitize(&instructions))
+                .unwrap()
+                .compute_unit_limit,
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_builtin_programs() {
+        use solana_feature_set::{
+            migrate_stake_program_to_core_bpf, reserve_minimal_cus_for_builtin_instructions,
+            FeatureSet,
+        };
+
+        let fee_structure = FeeStructure::default();
+        let fee_rate_governor = fee_rate_governor();
+        let message = sanitize(
+            &[
+                stake::id(),
+                config::id(),
+                address_lookup_table::id(),
+                ed25519_program::id(),
+                secp256k1_program::id(),
+                vote::id(),
+                Pubkey::new_unique(),
+            ]
+            .map(|program_id| Instruction::new_with_bytes(program_id, &[], vec![])),
+        );
+        let compute_unit_limit = |features| {
+            FeeEstimator::new(&fee_structure, &fee_rate_governor, features)
+                .estimate_sanitized(&message)
+                .unwrap()
+                .compute_unit_limit
+        };
+
+        let mut feature_set = FeatureSet::default();
+        feature_set.activate(&reserve_minimal_cus_for_builtin_instructions::id(), 0);
+        // Migrating builtins are builtins until they are migrated
+        assert_eq!(
+            compute_unit_limit(FeeFeatures::from(&feature_set)),
+            6 * 3_000 + 200_000
+        );
+        feature_set.activate(&migrate_stake_program_to_core_bpf::id(), 0);
+        assert_eq!(
+            compute_unit_limit(FeeFeatures::from(&feature_set)),
+            5 * 3_000 + 2 * 200_000
+        );
+        assert_eq!(
+            compute_unit_limit(FeeFeatures {
+                reserve_minimal_cus_for_builtin_instructions: true,
+                migrate_stake_program_to_core_bpf: true,
+                migrate_config_program_to_core_bpf: true,
+                migrate_address_lookup_table_program_to_core_bpf: true,
+                ..FeeFeatures::default()
+            }),
+            3 * 3_000 + 4 * 200_000
+        );
+    }
+}
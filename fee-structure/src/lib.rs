@@ -2,6 +2,9 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
 
+#[cfg(all(feature = "estimate", not(target_os = "solana")))]
+pub mod estimate;
+
 #[cfg(not(target_os = "solana"))]
 use solana_message::SanitizedMessage;
 use {solana_native_token::sol_to_lamports, std::num::NonZeroU32};
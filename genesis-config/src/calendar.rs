@@ -0,0 +1,365 @@
+//! Conversions between slots, epochs and wall-clock time.
+//!
+//! Slots are nominally `ns_per_slot` long, but actual slot times drift from
+//! that. A [`Calendar`] interpolates between observed [`BlockTimestamp`]
+//! samples, such as the timestamps of votes or the `unix_timestamp` of the
+//! `Clock` sysvar, and extrapolates past them at the average rate observed
+//! between the first and the last sample. Epoch boundaries, including the
+//! short epochs of a warmup period, come from the `EpochSchedule`.
+
+use {
+    crate::GenesisConfig,
+    solana_clock::{Clock, Epoch, Slot, UnixTimestamp},
+    solana_epoch_schedule::EpochSchedule,
+    solana_vote_interface::state::BlockTimestamp,
+    std::{fmt, time::Duration},
+};
+
+/// Reasons a sample can not be added to a calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalendarError {
+    /// The timestamp of the sample goes back in time relative to the
+    /// samples around it.
+    NonMonotonicSample {
+        slot: Slot,
+        timestamp: UnixTimestamp,
+    },
+}
+
+impl std::error::Error for CalendarError {}
+
+impl fmt::Display for CalendarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NonMonotonicSample { slot, timestamp } => write!(
+                f,
+                "timestamp {timestamp} of slot {slot} is out of order with the other samples"
+            ),
+        }
+    }
+}
+
+/// The next change of the leader schedule epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderScheduleEpochChange {
+    /// The epoch whose leader schedule gets computed.
+    pub leader_schedule_epoch: Epoch,
+    /// The first slot with the new leader schedule epoch.
+    pub slot: Slot,
+    /// Estimated time from the current slot to `slot`.
+    pub time_until: Duration,
+}
+
+/// Conversions between slots, epochs and Unix timestamps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    epoch_schedule: EpochSchedule,
+    /// Nominal slot duration, used until two samples are known.
+    seconds_per_slot: f64,
+    /// Samples ordered by slot, with non-decreasing timestamps.
+    samples: Vec<BlockTimestamp>,
+}
+
+impl Calendar {
+    /// A calendar whose slot 0 is at `genesis_timestamp`.
+    pub fn new(
+        epoch_schedule: EpochSchedule,
+        slot_duration: Duration,
+        genesis_timestamp: UnixTimestamp,
+    ) -> Self {
+        Self {
+            epoch_schedule,
+            seconds_per_slot: slot_duration.as_secs_f64(),
+            samples: vec![BlockTimestamp {
+                slot: 0,
+                timestamp: genesis_timestamp,
+            }],
+        }
+    }
+
+    pub fn from_genesis_config(genesis_config: &GenesisConfig) -> Self {
+        let ns_per_slot = u64::try_from(genesis_config.ns_per_slot()).unwrap_or(u64::MAX);
+        Self::new(
+            genesis_config.epoch_schedule.clone(),
+            Duration::from_nanos(ns_per_slot),
+            genesis_config.creation_time,
+        )
+    }
+
+    pub fn epoch_schedule(&self) -> &EpochSchedule {
+        &self.epoch_schedule
+    }
+
+    pub fn samples(&self) -> &[BlockTimestamp] {
+        &self.samples
+    }
+
+    /// Adds an observed timestamp, replacing any sample of the same slot.
+    pub fn add_sample(&mut self, sample: BlockTimestamp) -> Result<(), CalendarError> {
+        let index = self
+            .samples
+            .partition_point(|other| other.slot < sample.slot);
+        let replaces = self
+            .samples
+            .get(index)
+            .is_some_and(|other| other.slot == sample.slot);
+        let previous = index.checked_sub(1).and_then(|i| self.samples.get(i));
+        let next = self.samples.get(if replaces {
+            index.saturating_add(1)
+        } else {
+            index
+        });
+        if previous.is_some_and(|previous| previous.timestamp > sample.timestamp)
+            || next.is_some_and(|next| next.timestamp < sample.timestamp)
+        {
+            return Err(CalendarError::NonMonotonicSample {
+                slot: sample.slot,
+                timestamp: sample.timestamp,
+            });
+        }
+        if replaces {
+            self.samples[index] = sample;
+        } else {
+            self.samples.insert(index, sample);
+        }
+        Ok(())
+    }
+
+    /// Adds the timestamp of a `Clock` sysvar.
+    pub fn add_clock(&mut self, clock: &Clock) -> Result<(), CalendarError> {
+        self.add_sample(BlockTimestamp {
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        })
+    }
+
+    /// The average slot duration over the samples, or the nominal one until
+    /// two samples are known.
+    pub fn seconds_per_slot(&self) -> f64 {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last))
+                if last.slot > first.slot && last.timestamp > first.timestamp =>
+            {
+                seconds_per_slot_between(first, last)
+            }
+            _ => self.seconds_per_slot,
+        }
+    }
+
+    /// Estimated Unix timestamp of `slot`, in fractional seconds.
+    fn estimate_time(&self, slot: Slot) -> f64 {
+        let index = self.samples.partition_point(|sample| sample.slot <= slot);
+        let (anchor, next) = match index.checked_sub(1) {
+            Some(i) => (&self.samples[i], self.samples.get(index)),
+            None => (&self.samples[0], None),
+        };
+        let seconds_per_slot = match next {
+            Some(next) => seconds_per_slot_between(anchor, next),
+            None => self.seconds_per_slot(),
+        };
+        anchor.timestamp as f64 + (slot as f64 - anchor.slot as f64) * seconds_per_slot
+    }
+
+    /// Estimated Unix timestamp of `slot`.
+    pub fn slot_to_timestamp(&self, slot: Slot) -> UnixTimestamp {
+        self.estimate_time(slot).round() as UnixTimestamp
+    }
+
+    /// Estimated slot at `timestamp`.
+    pub fn timestamp_to_slot(&self, timestamp: UnixTimestamp) -> Slot {
+        let index = self
+            .samples
+            .partition_point(|sample| sample.timestamp <= timestamp);
+        let (anchor, next) = match index.checked_sub(1) {
+            Some(i) => (&self.samples[i], self.samples.get(index)),
+            None => (&self.samples[0], None),
+        };
+        let seconds_per_slot = match next {
+            Some(next) => seconds_per_slot_between(anchor, next),
+            None => self.seconds_per_slot(),
+        };
+        if seconds_per_slot <= 0.0 {
+            return anchor.slot;
+        }
+        let slot =
+            anchor.slot as f64 + (timestamp as f64 - anchor.timestamp as f64) / seconds_per_slot;
+        slot.max(0.0) as Slot
+    }
+
+    /// The first slot of `epoch`.
+    pub fn epoch_start_slot(&self, epoch: Epoch) -> Slot {
+        self.epoch_schedule.get_first_slot_in_epoch(epoch)
+    }
+
+    /// Estimated Unix timestamp of the start of `epoch`.
+    pub fn epoch_start_timestamp(&self, epoch: Epoch) -> UnixTimestamp {
+        self.slot_to_timestamp(self.epoch_start_slot(epoch))
+    }
+
+    /// Estimated epoch at `timestamp`.
+    pub fn timestamp_to_epoch(&self, timestamp: UnixTimestamp) -> Epoch {
+        self.epoch_schedule
+            .get_epoch(self.timestamp_to_slot(timestamp))
+    }
+
+    /// Estimated time from `from` to `to`, zero if `to` is not after `from`.
+    pub fn time_between(&self, from: Slot, to: Slot) -> Duration {
+        let seconds = self.estimate_time(to) - self.estimate_time(from);
+        Duration::try_from_secs_f64(seconds).unwrap_or_default()
+    }
+
+    /// The estimated `Clock` sysvar at `slot`.
+    pub fn clock(&self, slot: Slot) -> Clock {
+        let epoch = self.epoch_schedule.get_epoch(slot);
+        Clock {
+            slot,
+            epoch_start_timestamp: self.epoch_start_timestamp(epoch),
+            epoch,
+            leader_schedule_epoch: self.epoch_schedule.get_leader_schedule_epoch(slot),
+            unix_timestamp: self.slot_to_timestamp(slot),
+        }
+    }
+
+    /// The next slot after `current_slot` at which the leader schedule
+    /// epoch changes, that is when the stakes for the leader schedule of a
+    /// new epoch are captured.
+    pub fn next_leader_schedule_epoch(&self, current_slot: Slot) -> LeaderScheduleEpochChange {
+        let current = self.epoch_schedule.get_leader_schedule_epoch(current_slot);
+        // The leader schedule epoch increases at the start of each warmup
+        // epoch, and then every `slots_per_epoch` slots
+        let epoch = self.epoch_schedule.get_epoch(current_slot);
+        let mut low = current_slot.saturating_add(1);
+        let mut high = current_slot
+            .saturating_add(self.epoch_schedule.get_slots_in_epoch(epoch))
+            .saturating_add(self.epoch_schedule.slots_per_epoch);
+        while low < high {
+            let middle = low.saturating_add(high.saturating_sub(low) / 2);
+            if self.epoch_schedule.get_leader_schedule_epoch(middle) > current {
+                high = middle;
+            } else {
+                low = middle.saturating_add(1);
+            }
+        }
+        LeaderScheduleEpochChange {
+            leader_schedule_epoch: self.epoch_schedule.get_leader_schedule_epoch(low),
+            slot: low,
+            time_until: self.time_between(current_slot, low),
+        }
+    }
+}
+
+/// Average slot duration between two samples, computed in floating point
+/// so that extreme timestamps cannot overflow.
+fn seconds_per_slot_between(from: &BlockTimestamp, to: &BlockTimestamp) -> f64 {
+    (to.timestamp as f64 - from.timestamp as f64) / (to.slot as f64 - from.slot as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar() -> Calendar {
+        // 32 slots in the first epoch, then 64, then 128 per epoch
+        Calendar::new(
+            EpochSchedule::custom(128, 128, true),
+            Duration::from_millis(400),
+            1_000,
+        )
+    }
+
+    #[test]
+    fn test_nominal_calendar() {
+        let calendar = calendar();
+        assert_eq!(calendar.seconds_per_slot(), 0.4);
+        assert_eq!(calendar.epoch_start_slot(1), 32);
+        assert_eq!(calendar.epoch_start_slot(3), 224);
+        assert_eq!(calendar.epoch_start_timestamp(1), 1_000 + 13);
+        assert_eq!(calendar.slot_to_timestamp(100), 1_040);
+        assert_eq!(calendar.timestamp_to_slot(1_040), 100);
+        assert_eq!(calendar.timestamp_to_slot(0), 0);
+        assert_eq!(calendar.timestamp_to_epoch(1_040), 2);
+        assert_eq!(calendar.time_between(0, 10), Duration::from_secs(4));
+        assert_eq!(calendar.time_between(10, 0), Duration::ZERO);
+
+        let clock = calendar.clock(100);
+        assert_eq!(clock.epoch, 2);
+        assert_eq!(clock.leader_schedule_epoch, 3);
+        assert_eq!(clock.epoch_start_timestamp, 1_000 + 38);
+        assert_eq!(clock.unix_timestamp, 1_040);
+
+        let genesis_config = GenesisConfig::default();
+        let calendar = Calendar::from_genesis_config(&genesis_config);
+        assert_eq!(
+            Duration::from_secs_f64(calendar.seconds_per_slot()).as_nanos(),
+            genesis_config.ns_per_slot()
+        );
+    }
+
+    #[test]
+    fn test_drift_correction() {
+        let mut calendar = calendar();
+        // Slots run at 500ms until slot 100, then at 1s
+        calendar
+            .add_sample(BlockTimestamp {
+                slot: 100,
+                timestamp: 1_050,
+            })
+            .unwrap();
+        calendar
+            .add_clock(&Clock {
+                slot: 200,
+                unix_timestamp: 1_150,
+                ..Clock::default()
+            })
+            .unwrap();
+        assert_eq!(calendar.slot_to_timestamp(50), 1_025);
+        assert_eq!(calendar.slot_to_timestamp(150), 1_100);
+        assert_eq!(calendar.timestamp_to_slot(1_100), 150);
+        // Extrapolated at the average of 0.75s per slot
+        assert_eq!(calendar.seconds_per_slot(), 0.75);
+        assert_eq!(calendar.slot_to_timestamp(300), 1_225);
+        assert_eq!(calendar.timestamp_to_slot(1_225), 300);
+
+        assert_eq!(
+            calendar.add_sample(BlockTimestamp {
+                slot: 150,
+                timestamp: 1_200,
+            }),
+            Err(CalendarError::NonMonotonicSample {
+                slot: 150,
+                timestamp: 1_200,
+            })
+        );
+        calendar
+            .add_sample(BlockTimestamp {
+                slot: 100,
+                timestamp: 1_060,
+            })
+            .unwrap();
+        assert_eq!(calendar.samples().len(), 3);
+        assert_eq!(calendar.slot_to_timestamp(100), 1_060);
+
+        // Timestamps far from the samples do not overflow
+        assert_eq!(calendar.timestamp_to_slot(UnixTimestamp::MIN), 0);
+        assert!(calendar.timestamp_to_slot(UnixTimestamp::MAX) > 300);
+    }
+
+    #[test]
+    fn test_next_leader_schedule_epoch() {
+        let calendar = calendar();
+        // During warmup the schedule of the next epoch is captured at the
+        // start of each epoch
+        let change = calendar.next_leader_schedule_epoch(10);
+        assert_eq!(change.leader_schedule_epoch, 2);
+        assert_eq!(change.slot, 32);
+        assert!((change.time_until.as_secs_f64() - 22.0 * 0.4).abs() < 1e-6);
+
+        let change = calendar.next_leader_schedule_epoch(32);
+        assert_eq!((change.leader_schedule_epoch, change.slot), (3, 96));
+
+        // Afterwards it is captured `leader_schedule_slot_offset` slots
+        // before the epoch starts
+        let change = calendar.next_leader_schedule_epoch(230);
+        assert_eq!((change.leader_schedule_epoch, change.slot), (5, 352));
+    }
+}
@@ -0,0 +1,619 @@
+//! Inspecting genesis configs before a cluster is launched.
+//!
+//! [`validate`] flags common mistakes in a genesis config,
+//! [`GenesisSummary`] breaks its capitalization down by owner and
+//! [`GenesisDiff`] lists what differs between two genesis configs.
+
+use {
+    crate::GenesisConfig,
+    solana_account::Account,
+    solana_epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
+    solana_native_token::SolAmount,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fmt,
+    },
+};
+
+/// A likely mistake in a genesis config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenesisIssue {
+    /// An account holds fewer lamports than rent exemption requires.
+    NotRentExempt {
+        address: Pubkey,
+        lamports: u64,
+        minimum: u64,
+    },
+    ZeroTicksPerSlot,
+    ZeroTickDuration,
+    ZeroHashesPerTick,
+    /// The target tick count of the PoH config does not end on a slot
+    /// boundary.
+    TickCountNotSlotAligned {
+        target_tick_count: u64,
+        ticks_per_slot: u64,
+    },
+    SlotsPerEpochTooSmall {
+        slots_per_epoch: u64,
+    },
+    /// The warmup fields of the epoch schedule do not match its slots per
+    /// epoch.
+    InconsistentEpochSchedule {
+        expected: EpochSchedule,
+    },
+    /// The leader schedule of an epoch would be computed more than an epoch
+    /// ahead of it.
+    LeaderScheduleSlotOffsetTooLarge {
+        leader_schedule_slot_offset: u64,
+        slots_per_epoch: u64,
+    },
+    ZeroCapitalization,
+    CapitalizationOverflow,
+    /// A native instruction processor reuses the name or the program id of
+    /// an earlier one.
+    DuplicateNativeProcessor {
+        name: String,
+        program_id: Pubkey,
+    },
+}
+
+impl fmt::Display for GenesisIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotRentExempt {
+                address,
+                lamports,
+                minimum,
+            } => write!(
+                f,
+                "account {address} holds {lamports} lamports, less than the rent exempt \
+                 minimum of {minimum}"
+            ),
+            Self::ZeroTicksPerSlot => f.write_str("ticks per slot is zero"),
+            Self::ZeroTickDuration => f.write_str("target tick duration is zero"),
+            Self::ZeroHashesPerTick => f.write_str("hashes per tick is zero"),
+            Self::TickCountNotSlotAligned {
+                target_tick_count,
+                ticks_per_slot,
+            } => write!(
+                f,
+                "target tick count {target_tick_count} is not a multiple of {ticks_per_slot} \
+                 ticks per slot"
+            ),
+            Self::SlotsPerEpochTooSmall { slots_per_epoch } => write!(
+                f,
+                "{slots_per_epoch} slots per epoch is less than the minimum of \
+                 {MINIMUM_SLOTS_PER_EPOCH}"
+            ),
+            Self::InconsistentEpochSchedule { expected } => write!(
+                f,
+                "epoch schedule warmup fields do not match its slots per epoch, expected first \
+                 normal epoch {} and first normal slot {}",
+                expected.first_normal_epoch, expected.first_normal_slot
+            ),
+            Self::LeaderScheduleSlotOffsetTooLarge {
+                leader_schedule_slot_offset,
+                slots_per_epoch,
+            } => write!(
+                f,
+                "leader schedule slot offset {leader_schedule_slot_offset} is more than \
+                 {slots_per_epoch} slots per epoch"
+            ),
+            Self::ZeroCapitalization => f.write_str("capitalization is zero"),
+            Self::CapitalizationOverflow => f.write_str("capitalization overflows"),
+            Self::DuplicateNativeProcessor { name, program_id } => write!(
+                f,
+                "native instruction processor {name} ({program_id}) is defined twice"
+            ),
+        }
+    }
+}
+
+fn capitalization<'a>(accounts: impl IntoIterator<Item = &'a Account>) -> Option<u64> {
+    accounts
+        .into_iter()
+        .try_fold(0u64, |total, account| total.checked_add(account.lamports))
+}
+
+/// Checks a genesis config for common mistakes, returning every issue
+/// found.
+pub fn validate(genesis_config: &GenesisConfig) -> Vec<GenesisIssue> {
+    let mut issues = vec![];
+
+    for (address, account) in &genesis_config.accounts {
+        let minimum = genesis_config.rent.minimum_balance(account.data.len());
+        if account.lamports < minimum {
+            issues.push(GenesisIssue::NotRentExempt {
+                address: *address,
+                lamports: account.lamports,
+                minimum,
+            });
+        }
+    }
+
+    let ticks_per_slot = genesis_config.ticks_per_slot;
+    let poh_config = &genesis_config.poh_config;
+    if ticks_per_slot == 0 {
+        issues.push(GenesisIssue::ZeroTicksPerSlot);
+    }
+    if poh_config.target_tick_duration.is_zero() {
+        issues.push(GenesisIssue::ZeroTickDuration);
+    }
+    if poh_config.hashes_per_tick == Some(0) {
+        issues.push(GenesisIssue::ZeroHashesPerTick);
+    }
+    if let Some(target_tick_count) = poh_config.target_tick_count {
+        if target_tick_count
+            .checked_rem(ticks_per_slot)
+            .is_some_and(|remainder| remainder != 0)
+        {
+            issues.push(GenesisIssue::TickCountNotSlotAligned {
+                target_tick_count,
+                ticks_per_slot,
+            });
+        }
+    }
+
+    let epoch_schedule = &genesis_config.epoch_schedule;
+    let slots_per_epoch = epoch_schedule.slots_per_epoch;
+    if slots_per_epoch < MINIMUM_SLOTS_PER_EPOCH {
+        issues.push(GenesisIssue::SlotsPerEpochTooSmall { slots_per_epoch });
+    } else {
+        let expected = EpochSchedule::custom(
+            slots_per_epoch,
+            epoch_schedule.leader_schedule_slot_offset,
+            epoch_schedule.warmup,
+        );
+        if expected != *epoch_schedule {
+            issues.push(GenesisIssue::InconsistentEpochSchedule { expected });
+        }
+        let leader_schedule_slot_offset = epoch_schedule.leader_schedule_slot_offset;
+        if leader_schedule_slot_offset > slots_per_epoch {
+            issues.push(GenesisIssue::LeaderScheduleSlotOffsetTooLarge {
+                leader_schedule_slot_offset,
+                slots_per_epoch,
+            });
+        }
+    }
+
+    match capitalization(genesis_config.accounts.values()) {
+        Some(0) => issues.push(GenesisIssue::ZeroCapitalization),
+        Some(_) => {}
+        None => issues.push(GenesisIssue::CapitalizationOverflow),
+    }
+
+    let mut names = BTreeSet::new();
+    let mut program_ids = BTreeSet::new();
+    for (name, program_id) in &genesis_config.native_instruction_processors {
+        let new_name = names.insert(name);
+        let new_program_id = program_ids.insert(program_id);
+        if !new_name || !new_program_id {
+            issues.push(GenesisIssue::DuplicateNativeProcessor {
+                name: name.clone(),
+                program_id: *program_id,
+            });
+        }
+    }
+
+    issues
+}
+
+/// The accounts of a genesis config owned by the same program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OwnerSummary {
+    pub accounts: usize,
+    pub lamports: u64,
+    pub data_len: usize,
+}
+
+/// Capitalization of a genesis config, broken down by owner.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenesisSummary {
+    /// Lamports held by the accounts, saturating at `u64::MAX`. Rewards
+    /// pools do not count towards capitalization.
+    pub capitalization: u64,
+    pub accounts: usize,
+    pub executable_accounts: usize,
+    pub rewards_pools_lamports: u64,
+    pub owners: BTreeMap<Pubkey, OwnerSummary>,
+}
+
+impl GenesisSummary {
+    pub fn new(genesis_config: &GenesisConfig) -> Self {
+        let mut summary = Self {
+            accounts: genesis_config.accounts.len(),
+            rewards_pools_lamports: capitalization(genesis_config.rewards_pools.values())
+                .unwrap_or(u64::MAX),
+            ..Self::default()
+        };
+        for account in genesis_config.accounts.values() {
+            summary.capitalization = summary.capitalization.saturating_add(account.lamports);
+            if account.executable {
+                summary.executable_accounts = summary.executable_accounts.saturating_add(1);
+            }
+            let owner = summary.owners.entry(account.owner).or_default();
+            owner.accounts = owner.accounts.saturating_add(1);
+            owner.lamports = owner.lamports.saturating_add(account.lamports);
+            owner.data_len = owner.data_len.saturating_add(account.data.len());
+        }
+        summary
+    }
+
+    /// The owners, from the one holding the most lamports.
+    pub fn owners_by_lamports(&self) -> Vec<(&Pubkey, &OwnerSummary)> {
+        let mut owners: Vec<_> = self.owners.iter().collect();
+        owners.sort_by(|(_, a), (_, b)| b.lamports.cmp(&a.lamports));
+        owners
+    }
+}
+
+impl fmt::Display for GenesisSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Capitalization: {} in {} accounts ({} executable)",
+            SolAmount::from_base_units(self.capitalization).to_string_with_ticker(),
+            self.accounts,
+            self.executable_accounts,
+        )?;
+        writeln!(
+            f,
+            "Rewards pools: {}",
+            SolAmount::from_base_units(self.rewards_pools_lamports).to_string_with_ticker()
+        )?;
+        for (owner, summary) in self.owners_by_lamports() {
+            writeln!(
+                f,
+                "  {owner}: {} in {} accounts, {} bytes",
+                SolAmount::from_base_units(summary.lamports).to_string_with_ticker(),
+                summary.accounts,
+                summary.data_len,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A parameter that differs between two genesis configs, formatted with
+/// `Debug`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// An account that differs between two genesis configs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountDiff {
+    Added {
+        address: Pubkey,
+        account: Account,
+    },
+    Removed {
+        address: Pubkey,
+        account: Account,
+    },
+    Changed {
+        address: Pubkey,
+        old: Account,
+        new: Account,
+    },
+}
+
+impl AccountDiff {
+    pub fn address(&self) -> &Pubkey {
+        match self {
+            Self::Added { address, .. }
+            | Self::Removed { address, .. }
+            | Self::Changed { address, .. } => address,
+        }
+    }
+}
+
+/// The differences between two genesis configs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenesisDiff {
+    pub fields: Vec<FieldDiff>,
+    pub accounts: Vec<AccountDiff>,
+    pub rewards_pools: Vec<AccountDiff>,
+    pub added_native_processors: Vec<(String, Pubkey)>,
+    pub removed_native_processors: Vec<(String, Pubkey)>,
+}
+
+fn diff_accounts(
+    old: &BTreeMap<Pubkey, Account>,
+    new: &BTreeMap<Pubkey, Account>,
+) -> Vec<AccountDiff> {
+    let addresses: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    addresses
+        .into_iter()
+        .filter_map(|address| match (old.get(address), new.get(address)) {
+            (Some(old), Some(new)) => (old != new).then(|| AccountDiff::Changed {
+                address: *address,
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            (Some(account), None) => Some(AccountDiff::Removed {
+                address: *address,
+                account: account.clone(),
+            }),
+            (None, Some(account)) => Some(AccountDiff::Added {
+                address: *address,
+                account: account.clone(),
+            }),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+impl GenesisDiff {
+    pub fn new(old: &GenesisConfig, new: &GenesisConfig) -> Self {
+        let mut fields = vec![];
+        macro_rules! diff_field {
+            ($field:ident) => {
+                if old.$field != new.$field {
+                    fields.push(FieldDiff {
+                        field: stringify!($field),
+                        old: format!("{:?}", old.$field),
+                        new: format!("{:?}", new.$field),
+                    });
+                }
+            };
+        }
+        diff_field!(creation_time);
+        diff_field!(cluster_type);
+        diff_field!(ticks_per_slot);
+        diff_field!(poh_config);
+        diff_field!(fee_rate_governor);
+        diff_field!(rent);
+        diff_field!(inflation);
+        diff_field!(epoch_schedule);
+
+        let processors_not_in = |config: &GenesisConfig, other: &GenesisConfig| {
+            config
+                .native_instruction_processors
+                .iter()
+                .filter(|processor| !other.native_instruction_processors.contains(processor))
+                .cloned()
+                .collect()
+        };
+        Self {
+            fields,
+            accounts: diff_accounts(&old.accounts, &new.accounts),
+            rewards_pools: diff_accounts(&old.rewards_pools, &new.rewards_pools),
+            added_native_processors: processors_not_in(new, old),
+            removed_native_processors: processors_not_in(old, new),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.accounts.is_empty()
+            && self.rewards_pools.is_empty()
+            && self.added_native_processors.is_empty()
+            && self.removed_native_processors.is_empty()
+    }
+}
+
+fn fmt_account_diffs(f: &mut fmt::Formatter, kind: &str, diffs: &[AccountDiff]) -> fmt::Result {
+    for diff in diffs {
+        match diff {
+            AccountDiff::Added { address, account } => writeln!(
+                f,
+                "+ {kind} {address}: {} lamports, owner {}, {} bytes",
+                account.lamports,
+                account.owner,
+                account.data.len()
+            )?,
+            AccountDiff::Removed { address, account } => writeln!(
+                f,
+                "- {kind} {address}: {} lamports, owner {}, {} bytes",
+                account.lamports,
+                account.owner,
+                account.data.len()
+            )?,
+            AccountDiff::Changed { address, old, new } => {
+                write!(f, "~ {kind} {address}:")?;
+                if old.lamports != new.lamports {
+                    write!(f, " lamports {} -> {}", old.lamports, new.lamports)?;
+                }
+                if old.owner != new.owner {
+                    write!(f, " owner {} -> {}", old.owner, new.owner)?;
+                }
+                if old.data != new.data {
+                    write!(f, " data {} -> {} bytes", old.data.len(), new.data.len())?;
+                }
+                if old.executable != new.executable {
+                    write!(f, " executable {} -> {}", old.executable, new.executable)?;
+                }
+                if old.rent_epoch != new.rent_epoch {
+                    write!(f, " rent epoch {} -> {}", old.rent_epoch, new.rent_epoch)?;
+                }
+                writeln!(f)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for GenesisDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for FieldDiff { field, old, new } in &self.fields {
+            writeln!(f, "~ {field}: {old} -> {new}")?;
+        }
+        fmt_account_diffs(f, "account", &self.accounts)?;
+        fmt_account_diffs(f, "rewards pool", &self.rewards_pools)?;
+        for (name, program_id) in &self.added_native_processors {
+            writeln!(f, "+ native instruction processor {name} ({program_id})")?;
+        }
+        for (name, program_id) in &self.removed_native_processors {
+            writeln!(f, "- native instruction processor {name} ({program_id})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_native_token::LAMPORTS_PER_SOL, std::time::Duration};
+
+    fn genesis_config() -> GenesisConfig {
+        let mut genesis_config = GenesisConfig::default();
+        let rent_exempt = genesis_config.rent.minimum_balance(0);
+        genesis_config.accounts.insert(
+            Pubkey::new_unique(),
+            Account::new(LAMPORTS_PER_SOL, 0, &Pubkey::default()),
+        );
+        genesis_config.accounts.insert(
+            Pubkey::new_unique(),
+            Account::new(rent_exempt, 0, &Pubkey::default()),
+        );
+        genesis_config
+            .add_native_instruction_processor("vote".to_string(), solana_sdk_ids::vote::id());
+        genesis_config
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut genesis_config = genesis_config();
+        assert_eq!(validate(&genesis_config), vec![]);
+
+        let address = Pubkey::new_unique();
+        genesis_config
+            .accounts
+            .insert(address, Account::new(1, 10, &Pubkey::default()));
+        genesis_config.poh_config.target_tick_count = Some(100);
+        genesis_config.ticks_per_slot = 64;
+        genesis_config.poh_config.hashes_per_tick = Some(0);
+        genesis_config.poh_config.target_tick_duration = Duration::ZERO;
+        genesis_config.epoch_schedule = EpochSchedule::custom(1000, 2000, true);
+        genesis_config.epoch_schedule.first_normal_slot = 0;
+        genesis_config.add_native_instruction_processor("vote".to_string(), Pubkey::new_unique());
+        assert_eq!(
+            validate(&genesis_config),
+            vec![
+                GenesisIssue::NotRentExempt {
+                    address,
+                    lamports: 1,
+                    minimum: genesis_config.rent.minimum_balance(10),
+                },
+                GenesisIssue::ZeroTickDuration,
+                GenesisIssue::ZeroHashesPerTick,
+                GenesisIssue::TickCountNotSlotAligned {
+                    target_tick_count: 100,
+                    ticks_per_slot: 64,
+                },
+                GenesisIssue::InconsistentEpochSchedule {
+                    expected: EpochSchedule::custom(1000, 2000, true),
+                },
+                GenesisIssue::LeaderScheduleSlotOffsetTooLarge {
+                    leader_schedule_slot_offset: 2000,
+                    slots_per_epoch: 1000,
+                },
+                GenesisIssue::DuplicateNativeProcessor {
+                    name: "vote".to_string(),
+                    program_id: genesis_config.native_instruction_processors[1].1,
+                },
+            ]
+        );
+
+        let mut genesis_config = GenesisConfig {
+            ticks_per_slot: 0,
+            ..GenesisConfig::default()
+        };
+        genesis_config.epoch_schedule.slots_per_epoch = 16;
+        assert_eq!(
+            validate(&genesis_config),
+            vec![
+                GenesisIssue::ZeroTicksPerSlot,
+                GenesisIssue::SlotsPerEpochTooSmall {
+                    slots_per_epoch: 16
+                },
+                GenesisIssue::ZeroCapitalization,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut genesis_config = genesis_config();
+        let program = Pubkey::new_unique();
+        let mut account = Account::new(3 * LAMPORTS_PER_SOL, 100, &program);
+        account.executable = true;
+        genesis_config
+            .accounts
+            .insert(Pubkey::new_unique(), account);
+        let rent_exempt = genesis_config.rent.minimum_balance(0);
+
+        let summary = GenesisSummary::new(&genesis_config);
+        assert_eq!(summary.capitalization, 4 * LAMPORTS_PER_SOL + rent_exempt);
+        assert_eq!(summary.accounts, 3);
+        assert_eq!(summary.executable_accounts, 1);
+        assert_eq!(
+            summary.owners_by_lamports(),
+            vec![
+                (
+                    &program,
+                    &OwnerSummary {
+                        accounts: 1,
+                        lamports: 3 * LAMPORTS_PER_SOL,
+                        data_len: 100,
+                    }
+                ),
+                (
+                    &Pubkey::default(),
+                    &OwnerSummary {
+                        accounts: 2,
+                        lamports: LAMPORTS_PER_SOL + rent_exempt,
+                        data_len: 0,
+                    }
+                ),
+            ]
+        );
+        assert!(summary
+            .to_string()
+            .starts_with("Capitalization: 4.00089088 SOL in 3 accounts (1 executable)\n"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = genesis_config();
+        assert!(GenesisDiff::new(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.ticks_per_slot = 8;
+        let (changed, _) = new.accounts.iter_mut().next().unwrap();
+        let changed = *changed;
+        new.accounts.get_mut(&changed).unwrap().lamports += 1;
+        let added = Pubkey::new_unique();
+        new.accounts
+            .insert(added, Account::new(LAMPORTS_PER_SOL, 0, &Pubkey::default()));
+        new.native_instruction_processors.clear();
+
+        let diff = GenesisDiff::new(&old, &new);
+        assert_eq!(
+            diff.fields,
+            vec![FieldDiff {
+                field: "ticks_per_slot",
+                old: old.ticks_per_slot.to_string(),
+                new: "8".to_string(),
+            }]
+        );
+        let addresses: Vec<_> = diff.accounts.iter().map(AccountDiff::address).collect();
+        let mut expected = vec![&changed, &added];
+        expected.sort();
+        assert_eq!(addresses, expected);
+        assert!(diff.rewards_pools.is_empty());
+        assert!(diff.added_native_processors.is_empty());
+        assert_eq!(
+            diff.removed_native_processors,
+            old.native_instruction_processors
+        );
+        let display = diff.to_string();
+        assert!(display.contains(&format!("~ ticks_per_slot: {} -> 8\n", old.ticks_per_slot)));
+        assert!(display.contains(&format!("+ account {added}: ")));
+        assert!(display.contains("- native instruction processor vote"));
+    }
+}
@@ -44,6 +44,12 @@ use {
     },
 };
 
+#[cfg(feature = "calendar")]
+pub mod calendar;
+pub mod inspect;
+#[cfg(feature = "spec")]
+pub mod spec;
+
 pub const DEFAULT_GENESIS_FILE: &str = "genesis.bin";
 pub const DEFAULT_GENESIS_ARCHIVE: &str = "genesis.tar.bz2";
 pub const DEFAULT_GENESIS_DOWNLOAD_PATH: &str = "/genesis.tar.bz2";
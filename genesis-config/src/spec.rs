@@ -0,0 +1,989 @@
+//! Building a genesis config from a declarative spec.
+//!
+//! A [`GenesisSpec`] is read from a TOML or YAML file and lists everything a
+//! cluster starts with: accounts, programs preloaded under loader v3 or v4,
+//! bootstrap validators with their vote and stake accounts, activated
+//! features and the parameters of the cluster. Building the same spec always
+//! produces the same genesis config, and so the same genesis hash.
+//!
+//! ```toml
+//! cluster_type = "Development"
+//! ticks_per_slot = 8
+//! features = ["7txXZZD6Um59YoLMF7XUNimbMjsqsWhc7g2EniiTrmp1"]
+//!
+//! [epoch_schedule]
+//! slots_per_epoch = 64
+//! warmup = false
+//!
+//! [[accounts]]
+//! address = "9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq"
+//! balance = "1_000"
+//!
+//! [[programs]]
+//! address = "CwhcjmWVGZHoGsogykhwSBVFF8V3ErBXGnrBLhz6qTND"
+//! elf = "target/deploy/program.so"
+//! loader = "v4"
+//!
+//! [[validators]]
+//! identity = "5UVsQrrUVkkqQZLNRd7VTo6LeRtjLAvDWrxH1bm6xJ3M"
+//! vote_account = "9C3Mq9NNCrmrnW1i7VfsDtzAf1LSJtyqRCq2YFUj8XfR"
+//! stake_account = "EN6j5wPBg6CXySxM2fLHG2hhHC6Wd3NRXaPadfcCFhUr"
+//! identity_balance = "500"
+//! stake = "1_000_000"
+//! ```
+
+use {
+    crate::GenesisConfig,
+    serde_derive::Deserialize,
+    solana_account::{Account, AccountSharedData},
+    solana_clock::{Clock, UnixTimestamp, DEFAULT_TICKS_PER_SLOT},
+    solana_cluster_type::ClusterType,
+    solana_epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
+    solana_feature_gate_interface::Feature,
+    solana_fee_calculator::FeeRateGovernor,
+    solana_inflation::Inflation,
+    solana_loader_v3_interface::state::UpgradeableLoaderState,
+    solana_loader_v4_interface::state::{LoaderV4State, LoaderV4Status},
+    solana_native_token::SolAmount,
+    solana_poh_config::PohConfig,
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    solana_sdk_ids::{bpf_loader_upgradeable, loader_v4, stake, system_program, vote},
+    solana_stake_interface::{
+        stake_flags::StakeFlags,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2},
+    },
+    solana_vote_interface::state::{VoteInit, VoteState, VoteStateVersions},
+    std::{
+        collections::BTreeSet,
+        fmt, fs, io,
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::Duration,
+    },
+};
+
+/// Reasons a spec can not be read or built.
+#[derive(Debug)]
+pub enum GenesisSpecError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Yaml(serde_norway::Error),
+    /// The spec file is neither `.toml`, `.yaml` nor `.yml`.
+    UnknownFormat(PathBuf),
+    /// A file referenced by the spec can not be read.
+    File {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// Two accounts of the spec have the same address.
+    DuplicateAccount(Pubkey),
+    /// An account of the spec holds fewer lamports than rent exemption
+    /// requires.
+    NotRentExempt {
+        address: Pubkey,
+        lamports: u64,
+        minimum: u64,
+    },
+    InvalidTicksPerSlot,
+    InvalidEpochSchedule(String),
+    InvalidCommission {
+        vote_account: Pubkey,
+        commission: u8,
+    },
+    /// The total balance of the spec does not fit in a `u64`.
+    CapitalizationOverflow,
+}
+
+impl std::error::Error for GenesisSpecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) | Self::File { error, .. } => Some(error),
+            Self::Toml(error) => Some(error),
+            Self::Yaml(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GenesisSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Toml(error) => write!(f, "invalid TOML spec: {error}"),
+            Self::Yaml(error) => write!(f, "invalid YAML spec: {error}"),
+            Self::UnknownFormat(path) => {
+                write!(f, "unknown spec format for {}", path.display())
+            }
+            Self::File { path, error } => write!(f, "can not read {}: {error}", path.display()),
+            Self::DuplicateAccount(address) => write!(f, "account {address} is defined twice"),
+            Self::NotRentExempt {
+                address,
+                lamports,
+                minimum,
+            } => write!(
+                f,
+                "account {address} holds {lamports} lamports, less than the rent exempt \
+                 minimum of {minimum}"
+            ),
+            Self::InvalidTicksPerSlot => f.write_str("ticks per slot must be positive"),
+            Self::InvalidEpochSchedule(reason) => write!(f, "invalid epoch schedule: {reason}"),
+            Self::InvalidCommission {
+                vote_account,
+                commission,
+            } => write!(
+                f,
+                "commission {commission} of vote account {vote_account} is above 100"
+            ),
+            Self::CapitalizationOverflow => f.write_str("total balance overflows"),
+        }
+    }
+}
+
+impl From<io::Error> for GenesisSpecError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Deserializes a pubkey from its base58 string.
+fn deserialize_pubkey<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Pubkey, D::Error> {
+    let address = <String as serde::Deserialize>::deserialize(deserializer)?;
+    Pubkey::from_str(&address).map_err(|error| {
+        serde::de::Error::custom(format_args!("invalid address {address}: {error}"))
+    })
+}
+
+fn deserialize_optional_pubkey<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Pubkey>, D::Error> {
+    #[derive(Deserialize)]
+    struct Address(#[serde(deserialize_with = "deserialize_pubkey")] Pubkey);
+    let address = <Option<Address> as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(address.map(|Address(address)| address))
+}
+
+fn deserialize_pubkeys<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Pubkey>, D::Error> {
+    #[derive(Deserialize)]
+    struct Address(#[serde(deserialize_with = "deserialize_pubkey")] Pubkey);
+    let addresses = <Vec<Address> as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(addresses
+        .into_iter()
+        .map(|Address(address)| address)
+        .collect())
+}
+
+/// An account created at genesis.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub address: Pubkey,
+    pub balance: SolAmount,
+    /// Defaults to the system program.
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub owner: Option<Pubkey>,
+    /// A file holding the data of the account, relative to the spec.
+    #[serde(default)]
+    pub data_file: Option<PathBuf>,
+    #[serde(default)]
+    pub executable: bool,
+}
+
+/// The loader a program is preloaded under.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgramLoader {
+    #[default]
+    V3,
+    V4,
+}
+
+/// A program deployed at genesis.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProgramSpec {
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub address: Pubkey,
+    /// The ELF of the program, relative to the spec.
+    pub elf: PathBuf,
+    #[serde(default)]
+    pub loader: ProgramLoader,
+    /// The authority of the program, which is immutable (loader v3) or
+    /// finalized (loader v4) without one.
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub upgrade_authority: Option<Pubkey>,
+}
+
+/// A bootstrap validator, with its vote account and a stake account
+/// delegated to it that is active from the first epoch.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorSpec {
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub identity: Pubkey,
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub vote_account: Pubkey,
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub stake_account: Pubkey,
+    /// Balance of the identity, which pays for votes.
+    pub identity_balance: SolAmount,
+    /// Stake delegated to the vote account, on top of the rent exempt
+    /// reserve of the stake account.
+    pub stake: SolAmount,
+    #[serde(default)]
+    pub commission: u8,
+}
+
+/// The `Rent` of the cluster; missing fields keep their default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RentSpec {
+    pub lamports_per_byte_year: Option<u64>,
+    pub exemption_threshold: Option<f64>,
+    pub burn_percent: Option<u8>,
+}
+
+impl RentSpec {
+    pub fn build(&self) -> Rent {
+        let default = Rent::default();
+        Rent {
+            lamports_per_byte_year: self
+                .lamports_per_byte_year
+                .unwrap_or(default.lamports_per_byte_year),
+            exemption_threshold: self
+                .exemption_threshold
+                .unwrap_or(default.exemption_threshold),
+            burn_percent: self.burn_percent.unwrap_or(default.burn_percent),
+        }
+    }
+}
+
+/// The `Inflation` of the cluster; missing fields keep their default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InflationSpec {
+    pub initial: Option<f64>,
+    pub terminal: Option<f64>,
+    pub taper: Option<f64>,
+    pub foundation: Option<f64>,
+    pub foundation_term: Option<f64>,
+}
+
+impl InflationSpec {
+    pub fn build(&self) -> Inflation {
+        let mut inflation = Inflation::default();
+        inflation.initial = self.initial.unwrap_or(inflation.initial);
+        inflation.terminal = self.terminal.unwrap_or(inflation.terminal);
+        inflation.taper = self.taper.unwrap_or(inflation.taper);
+        inflation.foundation = self.foundation.unwrap_or(inflation.foundation);
+        inflation.foundation_term = self.foundation_term.unwrap_or(inflation.foundation_term);
+        inflation
+    }
+}
+
+/// The `FeeRateGovernor` of the cluster; missing fields keep their default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeRateGovernorSpec {
+    pub target_lamports_per_signature: Option<u64>,
+    pub target_signatures_per_slot: Option<u64>,
+    pub burn_percent: Option<u8>,
+}
+
+impl FeeRateGovernorSpec {
+    pub fn build(&self) -> FeeRateGovernor {
+        let default = FeeRateGovernor::default();
+        let mut fee_rate_governor = FeeRateGovernor::new(
+            self.target_lamports_per_signature
+                .unwrap_or(default.target_lamports_per_signature),
+            self.target_signatures_per_slot
+                .unwrap_or(default.target_signatures_per_slot),
+        );
+        fee_rate_governor.burn_percent = self.burn_percent.unwrap_or(default.burn_percent);
+        fee_rate_governor
+    }
+}
+
+/// The `EpochSchedule` of the cluster; missing fields keep their default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EpochScheduleSpec {
+    pub slots_per_epoch: Option<u64>,
+    /// Defaults to the slots per epoch.
+    pub leader_schedule_slot_offset: Option<u64>,
+    pub warmup: Option<bool>,
+}
+
+impl EpochScheduleSpec {
+    pub fn build(&self) -> Result<EpochSchedule, GenesisSpecError> {
+        let default = EpochSchedule::default();
+        let slots_per_epoch = self.slots_per_epoch.unwrap_or(default.slots_per_epoch);
+        if slots_per_epoch < MINIMUM_SLOTS_PER_EPOCH {
+            return Err(GenesisSpecError::InvalidEpochSchedule(format!(
+                "{slots_per_epoch} slots per epoch is less than the minimum of \
+                 {MINIMUM_SLOTS_PER_EPOCH}"
+            )));
+        }
+        Ok(EpochSchedule::custom(
+            slots_per_epoch,
+            self.leader_schedule_slot_offset.unwrap_or(slots_per_epoch),
+            self.warmup.unwrap_or(default.warmup),
+        ))
+    }
+}
+
+/// The `PohConfig` of the cluster; missing fields keep their default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PohConfigSpec {
+    pub target_tick_duration_us: Option<u64>,
+    pub target_tick_count: Option<u64>,
+    pub hashes_per_tick: Option<u64>,
+}
+
+impl PohConfigSpec {
+    pub fn build(&self) -> PohConfig {
+        let default = PohConfig::default();
+        PohConfig {
+            target_tick_duration: self
+                .target_tick_duration_us
+                .map(Duration::from_micros)
+                .unwrap_or(default.target_tick_duration),
+            target_tick_count: self.target_tick_count.or(default.target_tick_count),
+            hashes_per_tick: self.hashes_per_tick.or(default.hashes_per_tick),
+        }
+    }
+}
+
+/// A native instruction processor loaded at genesis.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NativeInstructionProcessorSpec {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub program_id: Pubkey,
+}
+
+/// The declarative description of a genesis config.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisSpec {
+    /// Defaults to the Unix epoch, so that the genesis hash only depends on
+    /// the spec.
+    pub creation_time: UnixTimestamp,
+    pub cluster_type: Option<ClusterType>,
+    pub ticks_per_slot: Option<u64>,
+    pub poh_config: PohConfigSpec,
+    pub fee_rate_governor: FeeRateGovernorSpec,
+    pub rent: RentSpec,
+    pub inflation: InflationSpec,
+    pub epoch_schedule: EpochScheduleSpec,
+    /// Features active from genesis.
+    #[serde(deserialize_with = "deserialize_pubkeys")]
+    pub features: Vec<Pubkey>,
+    pub accounts: Vec<AccountSpec>,
+    pub programs: Vec<ProgramSpec>,
+    pub validators: Vec<ValidatorSpec>,
+    pub native_instruction_processors: Vec<NativeInstructionProcessorSpec>,
+}
+
+fn read_file(base_dir: &Path, path: &Path) -> Result<Vec<u8>, GenesisSpecError> {
+    let path = base_dir.join(path);
+    fs::read(&path).map_err(|error| GenesisSpecError::File { path, error })
+}
+
+/// The account of a feature activated at genesis.
+fn feature_account(rent: &Rent) -> AccountSharedData {
+    let lamports = rent.minimum_balance(Feature::size_of()).max(1);
+    solana_feature_gate_interface::create_account(
+        &Feature {
+            activated_at: Some(0),
+        },
+        lamports,
+    )
+}
+
+/// The accounts of a program under loader v3: the program, and its program
+/// data at `programdata_address`.
+fn loader_v3_accounts(
+    programdata_address: &Pubkey,
+    elf: &[u8],
+    upgrade_authority: Option<Pubkey>,
+    rent: &Rent,
+) -> (Account, Account) {
+    let mut program_data = vec![0; UpgradeableLoaderState::size_of_programdata(elf.len())];
+    bincode::serialize_into(
+        &mut program_data[..],
+        &UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: upgrade_authority,
+        },
+    )
+    .unwrap();
+    program_data[UpgradeableLoaderState::size_of_programdata_metadata()..].copy_from_slice(elf);
+    let program = bincode::serialize(&UpgradeableLoaderState::Program {
+        programdata_address: *programdata_address,
+    })
+    .unwrap();
+    (
+        Account {
+            lamports: rent.minimum_balance(program.len()),
+            data: program,
+            owner: bpf_loader_upgradeable::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+        Account {
+            lamports: rent.minimum_balance(program_data.len()),
+            data: program_data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+}
+
+/// The account of a program deployed at `address` under loader v4. Without an
+/// authority, the program is finalized and is its own next version.
+fn loader_v4_account(
+    address: &Pubkey,
+    elf: &[u8],
+    authority: Option<Pubkey>,
+    rent: &Rent,
+) -> Account {
+    let (authority_address_or_next_version, status) = match authority {
+        Some(authority) => (authority, LoaderV4Status::Deployed),
+        None => (*address, LoaderV4Status::Finalized),
+    };
+    let mut data =
+        Vec::with_capacity(LoaderV4State::program_data_offset().saturating_add(elf.len()));
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(authority_address_or_next_version.as_ref());
+    data.extend_from_slice(&(status as u64).to_le_bytes());
+    data.extend_from_slice(elf);
+    Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: loader_v4::id(),
+        executable: true,
+        rent_epoch: 0,
+    }
+}
+
+/// The vote and stake accounts of a bootstrap validator.
+fn validator_accounts(validator: &ValidatorSpec, rent: &Rent) -> (Account, Account) {
+    let vote_state = VoteState::new(
+        &VoteInit {
+            node_pubkey: validator.identity,
+            authorized_voter: validator.identity,
+            authorized_withdrawer: validator.identity,
+            commission: validator.commission,
+        },
+        &Clock::default(),
+    );
+    let mut vote_data = vec![0; VoteState::size_of()];
+    VoteState::serialize(&VoteStateVersions::new_current(vote_state), &mut vote_data).unwrap();
+
+    let rent_exempt_reserve = rent.minimum_balance(StakeStateV2::size_of());
+    let stake_state = StakeStateV2::Stake(
+        Meta {
+            rent_exempt_reserve,
+            authorized: Authorized::auto(&validator.identity),
+            lockup: Lockup::default(),
+        },
+        Stake {
+            // Bootstrap stake is active from the first epoch
+            delegation: Delegation::new(
+                &validator.vote_account,
+                validator.stake.base_units(),
+                u64::MAX,
+            ),
+            credits_observed: 0,
+        },
+        StakeFlags::empty(),
+    );
+    let mut stake_data = vec![0; StakeStateV2::size_of()];
+    bincode::serialize_into(&mut stake_data[..], &stake_state).unwrap();
+
+    (
+        Account {
+            lamports: rent.minimum_balance(vote_data.len()).max(1),
+            data: vote_data,
+            owner: vote::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+        Account {
+            lamports: validator
+                .stake
+                .base_units()
+                .saturating_add(rent_exempt_reserve),
+            data: stake_data,
+            owner: stake::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+}
+
+impl GenesisSpec {
+    pub fn from_toml_str(spec: &str) -> Result<Self, GenesisSpecError> {
+        toml::from_str(spec).map_err(GenesisSpecError::Toml)
+    }
+
+    pub fn from_yaml_str(spec: &str) -> Result<Self, GenesisSpecError> {
+        serde_norway::from_str(spec).map_err(GenesisSpecError::Yaml)
+    }
+
+    /// Reads a spec from a `.toml`, `.yaml` or `.yml` file.
+    pub fn from_file(path: &Path) -> Result<Self, GenesisSpecError> {
+        let spec = fs::read_to_string(path).map_err(|error| GenesisSpecError::File {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&spec),
+            Some("yaml" | "yml") => Self::from_yaml_str(&spec),
+            _ => Err(GenesisSpecError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Builds the genesis config, reading the files the spec refers to
+    /// relative to `base_dir`.
+    pub fn build(&self, base_dir: &Path) -> Result<GenesisConfig, GenesisSpecError> {
+        let ticks_per_slot = self.ticks_per_slot.unwrap_or(DEFAULT_TICKS_PER_SLOT);
+        if ticks_per_slot == 0 {
+            return Err(GenesisSpecError::InvalidTicksPerSlot);
+        }
+        let rent = self.rent.build();
+        let mut genesis_config = GenesisConfig {
+            creation_time: self.creation_time,
+            ticks_per_slot,
+            poh_config: self.poh_config.build(),
+            fee_rate_governor: self.fee_rate_governor.build(),
+            rent: rent.clone(),
+            inflation: self.inflation.build(),
+            epoch_schedule: self.epoch_schedule.build()?,
+            cluster_type: self.cluster_type.unwrap_or(ClusterType::Development),
+            ..GenesisConfig::default()
+        };
+        // Accounts already holding an address; `GenesisConfig::accounts`
+        // would silently keep the last one
+        let mut addresses = BTreeSet::new();
+        let mut add_account = |address: Pubkey, account: Account| {
+            if !addresses.insert(address) {
+                return Err(GenesisSpecError::DuplicateAccount(address));
+            }
+            genesis_config.accounts.insert(address, account);
+            Ok(())
+        };
+
+        for address in &self.features {
+            add_account(*address, feature_account(&rent).into())?;
+        }
+        for spec in &self.accounts {
+            let data = match &spec.data_file {
+                Some(path) => read_file(base_dir, path)?,
+                None => vec![],
+            };
+            let minimum = rent.minimum_balance(data.len());
+            let lamports = spec.balance.base_units();
+            if lamports < minimum {
+                return Err(GenesisSpecError::NotRentExempt {
+                    address: spec.address,
+                    lamports,
+                    minimum,
+                });
+            }
+            let account = Account {
+                lamports,
+                data,
+                owner: spec.owner.unwrap_or(system_program::id()),
+                executable: spec.executable,
+                rent_epoch: 0,
+            };
+            add_account(spec.address, account)?;
+        }
+        for spec in &self.programs {
+            let elf = read_file(base_dir, &spec.elf)?;
+            match spec.loader {
+                ProgramLoader::V3 => {
+                    let programdata_address =
+                        solana_loader_v3_interface::get_program_data_address(&spec.address);
+                    let (program, program_data) = loader_v3_accounts(
+                        &programdata_address,
+                        &elf,
+                        spec.upgrade_authority,
+                        &rent,
+                    );
+                    add_account(spec.address, program)?;
+                    add_account(programdata_address, program_data)?;
+                }
+                ProgramLoader::V4 => {
+                    add_account(
+                        spec.address,
+                        loader_v4_account(&spec.address, &elf, spec.upgrade_authority, &rent),
+                    )?;
+                }
+            }
+        }
+        for spec in &self.validators {
+            if spec.commission > 100 {
+                return Err(GenesisSpecError::InvalidCommission {
+                    vote_account: spec.vote_account,
+                    commission: spec.commission,
+                });
+            }
+            let identity_balance = spec.identity_balance.base_units();
+            let minimum = rent.minimum_balance(0);
+            if identity_balance < minimum {
+                return Err(GenesisSpecError::NotRentExempt {
+                    address: spec.identity,
+                    lamports: identity_balance,
+                    minimum,
+                });
+            }
+            let identity = Account::new(identity_balance, 0, &system_program::id());
+            let (vote_account, stake_account) = validator_accounts(spec, &rent);
+            add_account(spec.identity, identity)?;
+            add_account(spec.vote_account, vote_account)?;
+            add_account(spec.stake_account, stake_account)?;
+        }
+
+        genesis_config
+            .accounts
+            .values()
+            .try_fold(0u64, |total, account| total.checked_add(account.lamports))
+            .ok_or(GenesisSpecError::CapitalizationOverflow)?;
+        for processor in &self.native_instruction_processors {
+            genesis_config
+                .add_native_instruction_processor(processor.name.clone(), processor.program_id);
+        }
+        Ok(genesis_config)
+    }
+
+    /// Builds the genesis config and writes it to the ledger at
+    /// `ledger_path`.
+    pub fn write(
+        &self,
+        base_dir: &Path,
+        ledger_path: &Path,
+    ) -> Result<GenesisConfig, GenesisSpecError> {
+        let genesis_config = self.build(base_dir)?;
+        genesis_config.write(ledger_path)?;
+        Ok(genesis_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_native_token::LAMPORTS_PER_SOL, solana_signer::Signer};
+
+    const ELF: &[u8] = &[0x7f, b'E', b'L', b'F', 2, 1, 1, 0];
+
+    fn make_tmp_dir(name: &str) -> PathBuf {
+        let out_dir = std::env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_string());
+        let path: PathBuf = [
+            out_dir,
+            "tmp".to_string(),
+            format!("{}-{}", name, solana_keypair::Keypair::new().pubkey()),
+        ]
+        .iter()
+        .collect();
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("program.so"), ELF).unwrap();
+        path
+    }
+
+    struct Addresses {
+        account: Pubkey,
+        feature: Pubkey,
+        v3_program: Pubkey,
+        v4_program: Pubkey,
+        authority: Pubkey,
+        identity: Pubkey,
+        vote_account: Pubkey,
+        stake_account: Pubkey,
+    }
+
+    impl Addresses {
+        fn new() -> Self {
+            Self {
+                account: Pubkey::new_unique(),
+                feature: Pubkey::new_unique(),
+                v3_program: Pubkey::new_unique(),
+                v4_program: Pubkey::new_unique(),
+                authority: Pubkey::new_unique(),
+                identity: Pubkey::new_unique(),
+                vote_account: Pubkey::new_unique(),
+                stake_account: Pubkey::new_unique(),
+            }
+        }
+
+        fn toml(&self) -> String {
+            format!(
+                r#"
+cluster_type = "Testnet"
+ticks_per_slot = 8
+features = ["{feature}"]
+
+[epoch_schedule]
+slots_per_epoch = 64
+warmup = false
+
+[fee_rate_governor]
+target_lamports_per_signature = 5_000
+burn_percent = 100
+
+[rent]
+burn_percent = 0
+
+[[accounts]]
+address = "{account}"
+balance = "1_000.5"
+
+[[programs]]
+address = "{v3_program}"
+elf = "program.so"
+upgrade_authority = "{authority}"
+
+[[programs]]
+address = "{v4_program}"
+elf = "program.so"
+loader = "v4"
+
+[[validators]]
+identity = "{identity}"
+vote_account = "{vote_account}"
+stake_account = "{stake_account}"
+identity_balance = "500"
+stake = "1_000_000"
+commission = 10
+"#,
+                feature = self.feature,
+                account = self.account,
+                v3_program = self.v3_program,
+                v4_program = self.v4_program,
+                authority = self.authority,
+                identity = self.identity,
+                vote_account = self.vote_account,
+                stake_account = self.stake_account,
+            )
+        }
+
+        fn yaml(&self) -> String {
+            format!(
+                r#"
+cluster_type: Testnet
+ticks_per_slot: 8
+features: ["{feature}"]
+epoch_schedule:
+  slots_per_epoch: 64
+  warmup: false
+fee_rate_governor:
+  target_lamports_per_signature: 5000
+  burn_percent: 100
+rent:
+  burn_percent: 0
+accounts:
+  - address: "{account}"
+    balance: "1000.500"
+programs:
+  - address: "{v3_program}"
+    elf: program.so
+    upgrade_authority: "{authority}"
+  - address: "{v4_program}"
+    elf: program.so
+    loader: v4
+validators:
+  - identity: "{identity}"
+    vote_account: "{vote_account}"
+    stake_account: "{stake_account}"
+    identity_balance: "500"
+    stake: "1000000"
+    commission: 10
+"#,
+                feature = self.feature,
+                account = self.account,
+                v3_program = self.v3_program,
+                v4_program = self.v4_program,
+                authority = self.authority,
+                identity = self.identity,
+                vote_account = self.vote_account,
+                stake_account = self.stake_account,
+            )
+        }
+    }
+
+    #[test]
+    fn test_build_genesis_config() {
+        let base_dir = make_tmp_dir("genesis_spec");
+        let addresses = Addresses::new();
+        let spec_path = base_dir.join("genesis.toml");
+        fs::write(&spec_path, addresses.toml()).unwrap();
+        let spec = GenesisSpec::from_file(&spec_path).unwrap();
+        let ledger_path = base_dir.join("ledger");
+        let genesis_config = spec.write(&base_dir, &ledger_path).unwrap();
+
+        assert_eq!(genesis_config.creation_time, 0);
+        assert_eq!(genesis_config.cluster_type, ClusterType::Testnet);
+        assert_eq!(genesis_config.ticks_per_slot, 8);
+        assert_eq!(
+            genesis_config.epoch_schedule,
+            EpochSchedule::custom(64, 64, false)
+        );
+        assert_eq!(genesis_config.fee_rate_governor.burn_percent, 100);
+        assert_eq!(genesis_config.rent.burn_percent, 0);
+        // feature, account, two v3 accounts, one v4 account and three
+        // validator accounts
+        assert_eq!(genesis_config.accounts.len(), 8);
+
+        let feature = &genesis_config.accounts[&addresses.feature];
+        assert_eq!(feature.owner, solana_sdk_ids::feature::id());
+        assert_eq!(
+            bincode::deserialize::<Feature>(&feature.data).unwrap(),
+            Feature {
+                activated_at: Some(0)
+            }
+        );
+        assert_eq!(
+            genesis_config.accounts[&addresses.account].lamports,
+            1_000 * LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2
+        );
+
+        let program = &genesis_config.accounts[&addresses.v3_program];
+        assert!(program.executable);
+        let programdata_address =
+            solana_loader_v3_interface::get_program_data_address(&addresses.v3_program);
+        let program_data = &genesis_config.accounts[&programdata_address];
+        assert_eq!(
+            UpgradeableLoaderState::from_account_data(&program_data.data),
+            Some((
+                UpgradeableLoaderState::ProgramData {
+                    slot: 0,
+                    upgrade_authority_address: Some(addresses.authority),
+                },
+                ELF
+            ))
+        );
+        let program = &genesis_config.accounts[&addresses.v4_program];
+        let (state, elf) = LoaderV4State::from_account_data(&program.data).unwrap();
+        assert_eq!(state.status, LoaderV4Status::Finalized);
+        assert_eq!(
+            state.authority_address_or_next_version,
+            addresses.v4_program
+        );
+        assert_eq!(elf, ELF);
+
+        let vote_account = &genesis_config.accounts[&addresses.vote_account];
+        let vote_state = VoteState::deserialize(&vote_account.data).unwrap();
+        assert_eq!(vote_state.node_pubkey, addresses.identity);
+        assert_eq!(vote_state.commission, 10);
+        let stake_account = &genesis_config.accounts[&addresses.stake_account];
+        let stake_state = bincode::deserialize::<StakeStateV2>(&stake_account.data).unwrap();
+        let delegation = stake_state.delegation().unwrap();
+        assert_eq!(delegation.voter_pubkey, addresses.vote_account);
+        assert_eq!(delegation.stake, 1_000_000 * LAMPORTS_PER_SOL);
+        assert_eq!(delegation.activation_epoch, u64::MAX);
+        assert_eq!(
+            stake_account.lamports,
+            delegation.stake + genesis_config.rent.minimum_balance(StakeStateV2::size_of())
+        );
+
+        // The same spec always builds the same genesis config
+        let loaded = GenesisConfig::load(&ledger_path).unwrap();
+        assert_eq!(loaded.hash(), genesis_config.hash());
+        let yaml_spec = GenesisSpec::from_yaml_str(&addresses.yaml()).unwrap();
+        assert_eq!(yaml_spec, spec);
+        assert_eq!(
+            yaml_spec.build(&base_dir).unwrap().hash(),
+            genesis_config.hash()
+        );
+        let _ignored = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let address = Pubkey::new_unique();
+        let spec = GenesisSpec::from_toml_str(&format!(
+            r#"
+features = ["{address}"]
+
+[[accounts]]
+address = "{address}"
+balance = "1"
+"#
+        ))
+        .unwrap();
+        assert!(matches!(
+            spec.build(Path::new(".")),
+            Err(GenesisSpecError::DuplicateAccount(duplicate)) if duplicate == address
+        ));
+
+        let spec = GenesisSpec::from_toml_str(&format!(
+            r#"
+[[accounts]]
+address = "{address}"
+balance = "0.000000001"
+data_file = "missing.bin"
+"#
+        ))
+        .unwrap();
+        assert!(matches!(
+            spec.build(Path::new(".")),
+            Err(GenesisSpecError::File { .. })
+        ));
+
+        let spec = GenesisSpec::from_toml_str(&format!(
+            r#"
+[[accounts]]
+address = "{address}"
+balance = "0"
+"#
+        ))
+        .unwrap();
+        assert!(matches!(
+            spec.build(Path::new(".")),
+            Err(GenesisSpecError::NotRentExempt { lamports: 0, .. })
+        ));
+
+        let identity = Pubkey::new_unique();
+        let spec = GenesisSpec::from_toml_str(&format!(
+            r#"
+[[validators]]
+identity = "{identity}"
+vote_account = "{}"
+stake_account = "{}"
+identity_balance = "0.000000001"
+stake = "1"
+"#,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ))
+        .unwrap();
+        assert!(matches!(
+            spec.build(Path::new(".")),
+            Err(GenesisSpecError::NotRentExempt { address, lamports: 1, .. }) if address == identity
+        ));
+
+        let spec = GenesisSpec::from_toml_str("[epoch_schedule]\nslots_per_epoch = 16").unwrap();
+        assert!(matches!(
+            spec.build(Path::new(".")),
+            Err(GenesisSpecError::InvalidEpochSchedule(_))
+        ));
+
+        assert!(matches!(
+            GenesisSpec::from_toml_str("features = [\"not an address\"]"),
+            Err(GenesisSpecError::Toml(_))
+        ));
+        assert!(matches!(
+            GenesisSpec::from_file(Path::new("genesis.json")),
+            Err(GenesisSpecError::File { .. })
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//! Projections of the supply under an inflation schedule.
+//!
+//! Rewards for an epoch are minted at the start of the next one, at the
+//! validator rate of the year inflation has reached by then, in proportion to
+//! the capitalization at the start of the epoch and to the length of the
+//! epoch in years. [`project`] repeats that calculation epoch after epoch,
+//! optionally taking fees and rent burnt during each epoch out of the
+//! supply.
+
+use {
+    crate::Inflation, solana_epoch_schedule::EpochSchedule, solana_fee_calculator::FeeRateGovernor,
+    solana_rent::Rent,
+};
+
+/// Fees collected during a projection, of which the governor burns its
+/// `burn_percent`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeBurn {
+    pub fee_rate_governor: FeeRateGovernor,
+    /// Fees collected per slot.
+    pub fees_per_slot: u64,
+}
+
+/// Rent collected during a projection, of which its `burn_percent` is
+/// burnt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RentBurn {
+    pub rent: Rent,
+    /// Rent collected per epoch.
+    pub rent_collected_per_epoch: u64,
+}
+
+/// The inputs of a projection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectionConfig {
+    pub inflation: Inflation,
+    pub epoch_schedule: EpochSchedule,
+    /// Slots per year, as in the genesis config.
+    pub slots_per_year: f64,
+    /// Capitalization at the start of the first projected epoch.
+    pub initial_capitalization: u64,
+    /// The first projected epoch.
+    pub start_epoch: u64,
+    /// The epoch inflation was activated in.
+    pub inflation_start_epoch: u64,
+    /// Fraction of the supply that is staked and earns the rewards.
+    pub staked_fraction: f64,
+    pub fee_burn: Option<FeeBurn>,
+    pub rent_burn: Option<RentBurn>,
+}
+
+/// The projected supply over one epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochProjection {
+    pub epoch: u64,
+    pub slots: u64,
+    /// The validator inflation rate the rewards of the epoch are minted at.
+    pub validator_rate: f64,
+    /// Rewards minted for the epoch.
+    pub minted: u64,
+    pub fees_burned: u64,
+    pub rent_burned: u64,
+    /// Supply at the end of the epoch, including the minted rewards.
+    pub supply: u64,
+    /// Rewards relative to the staked supply, as an annual rate.
+    pub staking_yield: f64,
+    /// Change of the supply, as an annual rate.
+    pub net_inflation_rate: f64,
+}
+
+impl ProjectionConfig {
+    /// Length of `epoch` in years.
+    pub fn epoch_duration_in_years(&self, epoch: u64) -> f64 {
+        self.epoch_schedule.get_slots_in_epoch(epoch) as f64 / self.slots_per_year
+    }
+
+    /// Years of inflation elapsed at the start of `epoch`.
+    pub fn slot_in_year_for_inflation(&self, epoch: u64) -> f64 {
+        let num_slots = self
+            .epoch_schedule
+            .get_first_slot_in_epoch(epoch)
+            .saturating_sub(
+                self.epoch_schedule
+                    .get_first_slot_in_epoch(self.inflation_start_epoch),
+            );
+        num_slots as f64 / self.slots_per_year
+    }
+
+    fn project_epoch(&self, epoch: u64, supply: u64) -> EpochProjection {
+        let slots = self.epoch_schedule.get_slots_in_epoch(epoch);
+        let duration_in_years = self.epoch_duration_in_years(epoch);
+        let validator_rate = if epoch < self.inflation_start_epoch {
+            0.0
+        } else {
+            self.inflation
+                .validator(self.slot_in_year_for_inflation(epoch.saturating_add(1)))
+        };
+        let minted = (validator_rate * supply as f64 * duration_in_years) as u64;
+        let fees_burned = self.fee_burn.as_ref().map_or(0, |fee_burn| {
+            let fees = fee_burn.fees_per_slot.saturating_mul(slots);
+            fee_burn.fee_rate_governor.burn(fees).1
+        });
+        let rent_burned = self.rent_burn.as_ref().map_or(0, |rent_burn| {
+            rent_burn
+                .rent
+                .calculate_burn(rent_burn.rent_collected_per_epoch)
+                .0
+        });
+        let end_supply = supply
+            .saturating_add(minted)
+            .saturating_sub(fees_burned)
+            .saturating_sub(rent_burned);
+        let staked = supply as f64 * self.staked_fraction;
+        let staking_yield = if staked > 0.0 {
+            minted as f64 / staked / duration_in_years
+        } else {
+            0.0
+        };
+        let net_inflation_rate = if supply > 0 {
+            (end_supply as f64 - supply as f64) / supply as f64 / duration_in_years
+        } else {
+            0.0
+        };
+        EpochProjection {
+            epoch,
+            slots,
+            validator_rate,
+            minted,
+            fees_burned,
+            rent_burned,
+            supply: end_supply,
+            staking_yield,
+            net_inflation_rate,
+        }
+    }
+}
+
+/// Projects the supply over `epochs` epochs from the start epoch of
+/// `config`.
+pub fn project(config: &ProjectionConfig, epochs: u64) -> Vec<EpochProjection> {
+    let mut supply = config.initial_capitalization;
+    (config.start_epoch..config.start_epoch.saturating_add(epochs))
+        .map(|epoch| {
+            let projection = config.project_epoch(epoch, supply);
+            supply = projection.supply;
+            projection
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS_PER_YEAR: f64 = 78_840_000.0;
+
+    fn config() -> ProjectionConfig {
+        ProjectionConfig {
+            inflation: Inflation::default(),
+            epoch_schedule: EpochSchedule::without_warmup(),
+            slots_per_year: SLOTS_PER_YEAR,
+            initial_capitalization: 500_000_000 * 1_000_000_000,
+            start_epoch: 0,
+            inflation_start_epoch: 0,
+            staked_fraction: 0.5,
+            fee_burn: None,
+            rent_burn: None,
+        }
+    }
+
+    #[test]
+    fn test_project() {
+        let config = config();
+        let projections = project(&config, 200);
+        assert_eq!(projections.len(), 200);
+
+        let first = projections[0];
+        let duration = 432_000.0 / SLOTS_PER_YEAR;
+        let rate = config.inflation.validator(duration);
+        assert_eq!(first.validator_rate, rate);
+        assert_eq!(
+            first.minted,
+            (rate * config.initial_capitalization as f64 * duration) as u64
+        );
+        assert_eq!(first.supply, config.initial_capitalization + first.minted);
+        assert!((first.staking_yield - rate / 0.5).abs() < 1e-9);
+        assert!((first.net_inflation_rate - rate).abs() < 1e-9);
+
+        // Rewards compound while the rate tapers
+        for pair in projections.windows(2) {
+            assert_eq!(pair[1].supply, pair[0].supply + pair[1].minted);
+            assert!(pair[1].validator_rate <= pair[0].validator_rate);
+        }
+        let last = projections.last().unwrap();
+        assert!(last.validator_rate < first.validator_rate);
+        assert_eq!(
+            last.supply,
+            config.initial_capitalization + projections.iter().map(|p| p.minted).sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_project_with_burn() {
+        let mut config = config();
+        config.inflation = Inflation::new_disabled();
+        config.start_epoch = 10;
+        config.fee_burn = Some(FeeBurn {
+            fee_rate_governor: FeeRateGovernor::default(),
+            fees_per_slot: 10_000,
+        });
+        config.rent_burn = Some(RentBurn {
+            rent: Rent {
+                burn_percent: 100,
+                ..Rent::default()
+            },
+            rent_collected_per_epoch: 1_000,
+        });
+        let projections = project(&config, 2);
+        assert_eq!(projections[0].epoch, 10);
+        assert_eq!(projections[0].minted, 0);
+        assert_eq!(projections[0].fees_burned, 432_000 * 10_000 / 2);
+        assert_eq!(projections[0].rent_burned, 1_000);
+        assert_eq!(
+            projections[1].supply,
+            config.initial_capitalization - 2 * (432_000 * 5_000 + 1_000)
+        );
+        assert!(projections[0].net_inflation_rate < 0.0);
+        assert_eq!(projections[0].staking_yield, 0.0);
+
+        // No rewards are minted before inflation starts
+        config.inflation = Inflation::default();
+        config.inflation_start_epoch = 11;
+        let projections = project(&config, 2);
+        assert_eq!(projections[0].minted, 0);
+        assert!(projections[1].minted > 0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Parsing the accounts of the upgradeable loader.
+//!
+//! The program held by a buffer or program data account is padded with
+//! zeros up to the size of the account. [`ProgramBytes::elf`] strips the
+//! padding, so that the hashes of a deployed program can be compared with
+//! the hashes of a verifiable build of its ELF. [`ParseAccountError`] and
+//! [`ProgramBytes`] are shared with the parsers of the v4 loader.
+
+use {crate::state::UpgradeableLoaderState, solana_hash::Hash, solana_pubkey::Pubkey, std::fmt};
+
+/// Reasons an account can not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAccountError {
+    /// The account is not owned by the loader.
+    InvalidOwner(Pubkey),
+    /// The data of the account does not hold a valid state.
+    InvalidData,
+}
+
+impl std::error::Error for ParseAccountError {}
+
+impl fmt::Display for ParseAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidOwner(owner) => write!(f, "account is owned by {owner}"),
+            Self::InvalidData => f.write_str("account data is invalid"),
+        }
+    }
+}
+
+/// The program held by an account, with its trailing padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramBytes<'a>(pub &'a [u8]);
+
+impl<'a> ProgramBytes<'a> {
+    /// The ELF of the program, without the trailing zeros.
+    pub fn elf(&self) -> &'a [u8] {
+        let len = self
+            .0
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |i| i.saturating_add(1));
+        &self.0[..len]
+    }
+
+    /// The SHA-256 hash of [`elf`](Self::elf).
+    pub fn sha256_hash(&self) -> Hash {
+        solana_sha256_hasher::hash(self.elf())
+    }
+
+    /// The BLAKE3 hash of [`elf`](Self::elf).
+    pub fn blake3_hash(&self) -> solana_blake3_hasher::Hash {
+        solana_blake3_hasher::hash(self.elf())
+    }
+}
+
+/// An account of the upgradeable loader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeableLoaderAccount<'a> {
+    Uninitialized,
+    Buffer {
+        authority_address: Option<Pubkey>,
+        program: ProgramBytes<'a>,
+    },
+    Program {
+        programdata_address: Pubkey,
+    },
+    ProgramData {
+        /// Slot that the program was last deployed or upgraded in.
+        slot: u64,
+        upgrade_authority_address: Option<Pubkey>,
+        program: ProgramBytes<'a>,
+    },
+}
+
+impl<'a> UpgradeableLoaderAccount<'a> {
+    /// Parses an account from its owner and data, checking that the loader
+    /// owns it. An `AccountSharedData` is parsed with
+    /// `parse(account.owner(), account.data())`.
+    pub fn parse(owner: &Pubkey, data: &'a [u8]) -> Result<Self, ParseAccountError> {
+        if *owner != solana_sdk_ids::bpf_loader_upgradeable::id() {
+            return Err(ParseAccountError::InvalidOwner(*owner));
+        }
+        let (state, program) = UpgradeableLoaderState::from_account_data(data)
+            .ok_or(ParseAccountError::InvalidData)?;
+        Ok(match state {
+            UpgradeableLoaderState::Uninitialized => Self::Uninitialized,
+            UpgradeableLoaderState::Buffer { authority_address } => Self::Buffer {
+                authority_address,
+                program: ProgramBytes(program),
+            },
+            UpgradeableLoaderState::Program {
+                programdata_address,
+            } => Self::Program {
+                programdata_address,
+            },
+            UpgradeableLoaderState::ProgramData {
+                slot,
+                upgrade_authority_address,
+            } => Self::ProgramData {
+                slot,
+                upgrade_authority_address,
+                program: ProgramBytes(program),
+            },
+        })
+    }
+
+    /// The authority of a buffer, or the upgrade authority of a program data
+    /// account.
+    pub fn authority(&self) -> Option<Pubkey> {
+        match self {
+            Self::Buffer {
+                authority_address, ..
+            } => *authority_address,
+            Self::ProgramData {
+                upgrade_authority_address,
+                ..
+            } => *upgrade_authority_address,
+            Self::Uninitialized | Self::Program { .. } => None,
+        }
+    }
+
+    /// The slot a program data account was last deployed or upgraded in.
+    pub fn last_deploy_slot(&self) -> Option<u64> {
+        match self {
+            Self::ProgramData { slot, .. } => Some(*slot),
+            _ => None,
+        }
+    }
+
+    /// The program held by a buffer or program data account.
+    pub fn program(&self) -> Option<ProgramBytes<'a>> {
+        match self {
+            Self::Buffer { program, .. } | Self::ProgramData { program, .. } => Some(*program),
+            Self::Uninitialized | Self::Program { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk_ids::bpf_loader_upgradeable};
+
+    fn account_data(
+        state: &UpgradeableLoaderState,
+        metadata_len: usize,
+        program: &[u8],
+    ) -> Vec<u8> {
+        let mut data = vec![0; metadata_len];
+        bincode::serialize_into(&mut data[..], state).unwrap();
+        data.extend_from_slice(program);
+        data
+    }
+
+    #[test]
+    fn test_parse_program_data() {
+        let authority = Pubkey::new_unique();
+        let elf = [0x7f, b'E', b'L', b'F', 0, 1, 2];
+        let mut padded = elf.to_vec();
+        padded.resize(64, 0);
+        let data = account_data(
+            &UpgradeableLoaderState::ProgramData {
+                slot: 42,
+                upgrade_authority_address: Some(authority),
+            },
+            UpgradeableLoaderState::size_of_programdata_metadata(),
+            &padded,
+        );
+        let parsed = UpgradeableLoaderAccount::parse(&bpf_loader_upgradeable::id(), &data).unwrap();
+        assert_eq!(parsed.authority(), Some(authority));
+        assert_eq!(parsed.last_deploy_slot(), Some(42));
+        let program = parsed.program().unwrap();
+        assert_eq!(program.0, &padded[..]);
+        assert_eq!(program.elf(), &elf[..]);
+        assert_eq!(program.sha256_hash(), solana_sha256_hasher::hash(&elf));
+        assert_eq!(program.blake3_hash(), solana_blake3_hasher::hash(&elf));
+    }
+
+    #[test]
+    fn test_parse_accounts() {
+        let owner = bpf_loader_upgradeable::id();
+        let programdata_address = Pubkey::new_unique();
+        let data = account_data(
+            &UpgradeableLoaderState::Program {
+                programdata_address,
+            },
+            UpgradeableLoaderState::size_of_program(),
+            &[],
+        );
+        let parsed = UpgradeableLoaderAccount::parse(&owner, &data).unwrap();
+        assert_eq!(
+            parsed,
+            UpgradeableLoaderAccount::Program {
+                programdata_address
+            }
+        );
+        assert_eq!(parsed.authority(), None);
+        assert_eq!(parsed.program(), None);
+
+        let data = account_data(
+            &UpgradeableLoaderState::Buffer {
+                authority_address: None,
+            },
+            UpgradeableLoaderState::size_of_buffer_metadata(),
+            &[1, 0],
+        );
+        assert_eq!(
+            UpgradeableLoaderAccount::parse(&owner, &data),
+            Ok(UpgradeableLoaderAccount::Buffer {
+                authority_address: None,
+                program: ProgramBytes(&[1, 0]),
+            })
+        );
+
+        let other_owner = Pubkey::new_unique();
+        assert_eq!(
+            UpgradeableLoaderAccount::parse(&other_owner, &data),
+            Err(ParseAccountError::InvalidOwner(other_owner))
+        );
+        assert_eq!(
+            UpgradeableLoaderAccount::parse(&owner, &data[..5]),
+            Err(ParseAccountError::InvalidData)
+        );
+    }
+}
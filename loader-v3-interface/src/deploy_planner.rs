@@ -0,0 +1,587 @@
+//! Planning the transactions of a program deployment.
+//!
+//! Deploying a program with the upgradeable loader takes many transactions:
+//! a buffer account is created, the program is written to it in chunks small
+//! enough to fit in a packet, and the buffer is then deployed to a new
+//! program or used to upgrade an existing one. [`plan_deployment`] computes
+//! these transactions, and the lamports they require, without access to a
+//! cluster.
+//!
+//! A deployment interrupted while writing can be resumed by passing the data
+//! of the buffer account, in which case only the chunks that differ from the
+//! program are written again.
+
+use {
+    crate::{
+        instruction::{create_buffer, upgrade, write},
+        state::UpgradeableLoaderState,
+    },
+    solana_instruction::{error::InstructionError, Instruction},
+    solana_message::Message,
+    solana_packet::PACKET_DATA_SIZE,
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    std::fmt,
+};
+
+/// Size of a transaction signature.
+const SIGNATURE_BYTES: usize = 64;
+
+/// What to do with the buffer once the program is written to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployTarget {
+    /// Deploys a new program at `program_address`, whose keypair signs the
+    /// deployment, with room for upgrades of up to `max_data_len` bytes.
+    Deploy {
+        program_address: Pubkey,
+        max_data_len: usize,
+    },
+    /// Upgrades the program at `program_address`, sending the lamports of
+    /// the buffer to `spill_address`.
+    Upgrade {
+        program_address: Pubkey,
+        spill_address: Pubkey,
+    },
+}
+
+/// Accounts and parameters of a deployment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeployConfig {
+    /// Pays for transaction fees and rent.
+    pub payer: Pubkey,
+    /// Authority of the buffer, and upgrade authority of the program.
+    pub authority: Pubkey,
+    /// The buffer the program is written to. Its keypair signs the creation
+    /// of the buffer.
+    pub buffer: Pubkey,
+    pub target: DeployTarget,
+    pub rent: Rent,
+    pub lamports_per_signature: u64,
+}
+
+/// The step of a deployment a transaction performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployStage {
+    CreateBuffer,
+    Write { offset: u32, len: usize },
+    Deploy,
+    Upgrade,
+}
+
+/// The instructions of one transaction, with the accounts that must sign it.
+/// `stage` tells what the transaction does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionBatch<S = DeployStage> {
+    pub stage: S,
+    pub instructions: Vec<Instruction>,
+    /// Signers, the payer first.
+    pub signers: Vec<Pubkey>,
+}
+
+impl<S> InstructionBatch<S> {
+    pub fn new(stage: S, instructions: Vec<Instruction>, payer: &Pubkey) -> Self {
+        let mut signers = vec![*payer];
+        for instruction in &instructions {
+            for account in &instruction.accounts {
+                if account.is_signer && !signers.contains(&account.pubkey) {
+                    signers.push(account.pubkey);
+                }
+            }
+        }
+        Self {
+            stage,
+            instructions,
+            signers,
+        }
+    }
+
+    /// Size of the transaction, with the payer as fee payer.
+    pub fn transaction_size(&self) -> usize {
+        transaction_size(&self.instructions, &self.signers[0])
+    }
+}
+
+/// Lamports a deployment needs from the payer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LamportsRequired {
+    /// Rent exemption of a new buffer.
+    pub buffer: u64,
+    /// Rent exemption of a new program account.
+    pub program: u64,
+    /// Rent exemption of a new program data account.
+    pub program_data: u64,
+    /// Transaction fees.
+    pub fees: u64,
+    /// Lamports of a buffer created by the plan, returned to the payer when
+    /// deploying a new program closes the buffer. The balance of a reused
+    /// buffer is not known to the plan, and is not included.
+    pub refunded: u64,
+}
+
+impl LamportsRequired {
+    /// The balance the payer needs before the deployment.
+    pub fn total(&self) -> u64 {
+        self.buffer
+            .saturating_add(self.program)
+            .saturating_add(self.program_data)
+            .saturating_add(self.fees)
+    }
+}
+
+/// The transactions of a deployment, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeploymentPlan {
+    pub batches: Vec<InstructionBatch>,
+    pub lamports_required: LamportsRequired,
+    /// Program bytes written by the plan.
+    pub bytes_to_write: usize,
+    /// Program bytes already in the buffer, which are not written again.
+    pub bytes_already_written: usize,
+}
+
+/// Reasons a deployment cannot be planned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployPlanError {
+    EmptyProgram,
+    /// The program is larger than a buffer can hold.
+    ProgramTooLarge(usize),
+    /// The maximum data length of a new program is less than its length.
+    MaxDataLenTooSmall {
+        program_len: usize,
+        max_data_len: usize,
+    },
+    /// The existing buffer is not a buffer account.
+    InvalidBuffer,
+    /// The existing buffer is not the size of the program. Deploying a larger
+    /// buffer would keep its trailing bytes after the program.
+    BufferSizeMismatch {
+        program_len: usize,
+        buffer_len: usize,
+    },
+    /// The existing buffer has another authority, or none.
+    BufferAuthorityMismatch {
+        authority: Option<Pubkey>,
+    },
+    /// A single instruction does not fit in a transaction.
+    TransactionTooLarge(InstructionError),
+}
+
+impl std::error::Error for DeployPlanError {}
+
+impl fmt::Display for DeployPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyProgram => f.write_str("program is empty"),
+            Self::ProgramTooLarge(len) => write!(f, "program of {len} bytes is too large"),
+            Self::MaxDataLenTooSmall {
+                program_len,
+                max_data_len,
+            } => write!(
+                f,
+                "maximum data length {max_data_len} is less than the program length {program_len}"
+            ),
+            Self::InvalidBuffer => f.write_str("existing account is not a buffer"),
+            Self::BufferSizeMismatch {
+                program_len,
+                buffer_len,
+            } => write!(
+                f,
+                "buffer of {buffer_len} bytes does not match a program of {program_len} bytes"
+            ),
+            Self::BufferAuthorityMismatch {
+                authority: Some(authority),
+            } => write!(f, "buffer authority is {authority}"),
+            Self::BufferAuthorityMismatch { authority: None } => f.write_str("buffer is immutable"),
+            Self::TransactionTooLarge(err) => write!(f, "instruction does not fit a packet: {err}"),
+        }
+    }
+}
+
+fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let num_signatures = usize::from(message.header.num_required_signatures);
+    // The signature count is a short vec length, a single byte below 128
+    num_signatures
+        .saturating_mul(SIGNATURE_BYTES)
+        .saturating_add(message.serialize().len())
+        .saturating_add(1)
+}
+
+/// The largest chunk of program a single write transaction can carry.
+pub fn max_write_chunk_size(payer: &Pubkey, buffer: &Pubkey, authority: &Pubkey) -> usize {
+    max_chunk_size(payer, write(buffer, authority, 0, vec![]))
+}
+
+/// The largest chunk of program a single transaction of `empty_write`, an
+/// instruction of any loader that writes no bytes yet, can carry.
+pub fn max_chunk_size(payer: &Pubkey, empty_write: Instruction) -> usize {
+    let baseline = transaction_size(&[empty_write], payer);
+    // The length prefix of the instruction data grows by a byte with the chunk
+    PACKET_DATA_SIZE.saturating_sub(baseline).saturating_sub(1)
+}
+
+/// Checks that `buffer_data` is a buffer with `authority`, that holds exactly
+/// `program_len` bytes, returning the program part of the data.
+fn existing_program_data<'a>(
+    buffer_data: &'a [u8],
+    authority: &Pubkey,
+    program_len: usize,
+) -> Result<&'a [u8], DeployPlanError> {
+    let Some((UpgradeableLoaderState::Buffer { authority_address }, program_data)) =
+        UpgradeableLoaderState::from_account_data(buffer_data)
+    else {
+        return Err(DeployPlanError::InvalidBuffer);
+    };
+    if authority_address != Some(*authority) {
+        return Err(DeployPlanError::BufferAuthorityMismatch {
+            authority: authority_address,
+        });
+    }
+    if program_data.len() != program_len {
+        return Err(DeployPlanError::BufferSizeMismatch {
+            program_len,
+            buffer_len: program_data.len(),
+        });
+    }
+    Ok(program_data)
+}
+
+/// Plans the deployment of `program`, the bytes of an ELF.
+///
+/// If `existing_buffer` is the data of the buffer account from an earlier,
+/// interrupted deployment, the buffer is not created again and only the
+/// chunks of the program it does not hold yet are written.
+pub fn plan_deployment(
+    program: &[u8],
+    config: &DeployConfig,
+    existing_buffer: Option<&[u8]>,
+) -> Result<DeploymentPlan, DeployPlanError> {
+    let DeployConfig {
+        payer,
+        authority,
+        buffer,
+        target,
+        rent,
+        lamports_per_signature,
+    } = config;
+    if program.is_empty() {
+        return Err(DeployPlanError::EmptyProgram);
+    }
+    if u32::try_from(program.len()).is_err() {
+        return Err(DeployPlanError::ProgramTooLarge(program.len()));
+    }
+    if let DeployTarget::Deploy { max_data_len, .. } = target {
+        if *max_data_len < program.len() {
+            return Err(DeployPlanError::MaxDataLenTooSmall {
+                program_len: program.len(),
+                max_data_len: *max_data_len,
+            });
+        }
+    }
+
+    let mut batches = vec![];
+    let mut lamports_required = LamportsRequired::default();
+    let buffer_lamports =
+        rent.minimum_balance(UpgradeableLoaderState::size_of_buffer(program.len()));
+    let written = match existing_buffer {
+        Some(buffer_data) => existing_program_data(buffer_data, authority, program.len())?,
+        None => {
+            lamports_required.buffer = buffer_lamports;
+            batches.push(InstructionBatch::new(
+                DeployStage::CreateBuffer,
+                create_buffer(payer, buffer, authority, buffer_lamports, program.len())
+                    .map_err(DeployPlanError::TransactionTooLarge)?,
+                payer,
+            ));
+            &[]
+        }
+    };
+
+    let chunk_size = max_write_chunk_size(payer, buffer, authority);
+    let mut bytes_to_write = 0usize;
+    for (index, chunk) in program.chunks(chunk_size).enumerate() {
+        let start = index.saturating_mul(chunk_size);
+        if written.get(start..start.saturating_add(chunk.len())) == Some(chunk) {
+            continue;
+        }
+        // Checked to fit in a u32 above
+        let offset = start as u32;
+        bytes_to_write = bytes_to_write.saturating_add(chunk.len());
+        batches.push(InstructionBatch::new(
+            DeployStage::Write {
+                offset,
+                len: chunk.len(),
+            },
+            vec![write(buffer, authority, offset, chunk.to_vec())],
+            payer,
+        ));
+    }
+
+    match target {
+        DeployTarget::Deploy {
+            program_address,
+            max_data_len,
+        } => {
+            let program_lamports = rent.minimum_balance(UpgradeableLoaderState::size_of_program());
+            lamports_required.program = program_lamports;
+            lamports_required.program_data =
+                rent.minimum_balance(UpgradeableLoaderState::size_of_programdata(*max_data_len));
+            if existing_buffer.is_none() {
+                lamports_required.refunded = buffer_lamports;
+            }
+            #[allow(deprecated)]
+            let instructions = crate::instruction::deploy_with_max_program_len(
+                payer,
+                program_address,
+                buffer,
+                authority,
+                program_lamports,
+                *max_data_len,
+            )
+            .map_err(DeployPlanError::TransactionTooLarge)?;
+            batches.push(InstructionBatch::new(
+                DeployStage::Deploy,
+                instructions,
+                payer,
+            ));
+        }
+        DeployTarget::Upgrade {
+            program_address,
+            spill_address,
+        } => {
+            batches.push(InstructionBatch::new(
+                DeployStage::Upgrade,
+                vec![upgrade(program_address, buffer, authority, spill_address)],
+                payer,
+            ));
+        }
+    }
+
+    lamports_required.fees = batches
+        .iter()
+        .map(|batch| (batch.signers.len() as u64).saturating_mul(*lamports_per_signature))
+        .sum();
+
+    Ok(DeploymentPlan {
+        batches,
+        lamports_required,
+        bytes_to_write,
+        bytes_already_written: program.len().saturating_sub(bytes_to_write),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(target: DeployTarget) -> DeployConfig {
+        DeployConfig {
+            payer: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            buffer: Pubkey::new_unique(),
+            target,
+            rent: Rent::default(),
+            lamports_per_signature: 5_000,
+        }
+    }
+
+    fn program(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn buffer_data(authority: Option<Pubkey>, program: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; UpgradeableLoaderState::size_of_buffer_metadata()];
+        bincode::serialize_into(
+            &mut data[..],
+            &UpgradeableLoaderState::Buffer {
+                authority_address: authority,
+            },
+        )
+        .unwrap();
+        data.extend_from_slice(program);
+        data
+    }
+
+    #[test]
+    fn test_plan_deployment() {
+        let program_address = Pubkey::new_unique();
+        let config = config(DeployTarget::Deploy {
+            program_address,
+            max_data_len: 20_000,
+        });
+        let program = program(10_000);
+        let plan = plan_deployment(&program, &config, None).unwrap();
+
+        let chunk_size = max_write_chunk_size(&config.payer, &config.buffer, &config.authority);
+        let writes = program.len().div_ceil(chunk_size);
+        assert_eq!(plan.batches.len(), writes + 2);
+        assert_eq!(plan.batches[0].stage, DeployStage::CreateBuffer);
+        assert_eq!(plan.batches[0].signers, vec![config.payer, config.buffer]);
+        assert_eq!(
+            plan.batches[1].stage,
+            DeployStage::Write {
+                offset: 0,
+                len: chunk_size
+            }
+        );
+        assert_eq!(
+            plan.batches[writes].stage,
+            DeployStage::Write {
+                offset: ((writes - 1) * chunk_size) as u32,
+                len: program.len() - (writes - 1) * chunk_size,
+            }
+        );
+        assert_eq!(
+            plan.batches[writes + 1].signers,
+            vec![config.payer, program_address, config.authority]
+        );
+        for batch in &plan.batches {
+            assert!(batch.transaction_size() <= PACKET_DATA_SIZE);
+        }
+        // Full chunks fill the packet
+        assert_eq!(plan.batches[1].transaction_size(), PACKET_DATA_SIZE);
+
+        assert_eq!(plan.bytes_to_write, program.len());
+        let rent = &config.rent;
+        let buffer_lamports = rent.minimum_balance(UpgradeableLoaderState::size_of_buffer(10_000));
+        assert_eq!(
+            plan.lamports_required,
+            LamportsRequired {
+                buffer: buffer_lamports,
+                program: rent.minimum_balance(UpgradeableLoaderState::size_of_program()),
+                program_data: rent
+                    .minimum_balance(UpgradeableLoaderState::size_of_programdata(20_000)),
+                fees: (2 + writes as u64 * 2 + 3) * 5_000,
+                refunded: buffer_lamports,
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_resume() {
+        let config = config(DeployTarget::Upgrade {
+            program_address: Pubkey::new_unique(),
+            spill_address: Pubkey::new_unique(),
+        });
+        let program = program(5_000);
+        let chunk_size = max_write_chunk_size(&config.payer, &config.buffer, &config.authority);
+
+        // The second chunk was not written, and the buffer was created
+        // without the tail of the program
+        let mut written = program.clone();
+        written[chunk_size..2 * chunk_size].fill(0);
+        written.truncate(4 * chunk_size);
+        written.resize(program.len(), 0);
+        let buffer = buffer_data(Some(config.authority), &written);
+
+        let plan = plan_deployment(&program, &config, Some(&buffer)).unwrap();
+        let stages = plan
+            .batches
+            .iter()
+            .map(|batch| batch.stage)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stages,
+            vec![
+                DeployStage::Write {
+                    offset: chunk_size as u32,
+                    len: chunk_size,
+                },
+                DeployStage::Write {
+                    offset: (4 * chunk_size) as u32,
+                    len: chunk_size,
+                },
+                DeployStage::Write {
+                    offset: (5 * chunk_size) as u32,
+                    len: program.len() - 5 * chunk_size,
+                },
+                DeployStage::Upgrade,
+            ]
+        );
+        assert_eq!(plan.bytes_already_written, 3 * chunk_size);
+        assert_eq!(
+            plan.lamports_required,
+            LamportsRequired {
+                fees: 8 * 5_000,
+                ..LamportsRequired::default()
+            }
+        );
+
+        // Everything written
+        let buffer = buffer_data(Some(config.authority), &program);
+        let plan = plan_deployment(&program, &config, Some(&buffer)).unwrap();
+        assert_eq!(plan.batches.len(), 1);
+        assert_eq!(plan.bytes_to_write, 0);
+
+        // Deploying a reused buffer does not count its unknown balance
+        let config = DeployConfig {
+            target: DeployTarget::Deploy {
+                program_address: Pubkey::new_unique(),
+                max_data_len: program.len(),
+            },
+            ..config
+        };
+        let plan = plan_deployment(&program, &config, Some(&buffer)).unwrap();
+        assert_eq!(plan.batches.len(), 1);
+        assert_eq!(plan.lamports_required.buffer, 0);
+        assert_eq!(plan.lamports_required.refunded, 0);
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let config = config(DeployTarget::Deploy {
+            program_address: Pubkey::new_unique(),
+            max_data_len: 100,
+        });
+        assert_eq!(
+            plan_deployment(&[], &config, None),
+            Err(DeployPlanError::EmptyProgram)
+        );
+        assert_eq!(
+            plan_deployment(&program(101), &config, None),
+            Err(DeployPlanError::MaxDataLenTooSmall {
+                program_len: 101,
+                max_data_len: 100
+            })
+        );
+
+        let program = program(100);
+        let other_authority = Pubkey::new_unique();
+        for (buffer, err) in [
+            (vec![1, 0, 0], DeployPlanError::InvalidBuffer),
+            (
+                bincode::serialize(&UpgradeableLoaderState::Uninitialized).unwrap(),
+                DeployPlanError::InvalidBuffer,
+            ),
+            (
+                buffer_data(Some(config.authority), &program[..50]),
+                DeployPlanError::BufferSizeMismatch {
+                    program_len: 100,
+                    buffer_len: 50,
+                },
+            ),
+            (
+                buffer_data(
+                    Some(config.authority),
+                    &[program.as_slice(), &[0; 10]].concat(),
+                ),
+                DeployPlanError::BufferSizeMismatch {
+                    program_len: 100,
+                    buffer_len: 110,
+                },
+            ),
+            (
+                buffer_data(Some(other_authority), &program),
+                DeployPlanError::BufferAuthorityMismatch {
+                    authority: Some(other_authority),
+                },
+            ),
+            (
+                buffer_data(None, &program),
+                DeployPlanError::BufferAuthorityMismatch { authority: None },
+            ),
+        ] {
+            assert_eq!(plan_deployment(&program, &config, Some(&buffer)), Err(err));
+        }
+    }
+}
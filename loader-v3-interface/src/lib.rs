@@ -19,6 +19,10 @@
 
 use solana_pubkey::Pubkey;
 
+#[cfg(feature = "account")]
+pub mod account;
+#[cfg(feature = "deploy-planner")]
+pub mod deploy_planner;
 pub mod instruction;
 pub mod state;
 
@@ -62,6 +62,55 @@ impl UpgradeableLoaderState {
     pub const fn size_of_programdata(program_len: usize) -> usize {
         Self::size_of_programdata_metadata().saturating_add(program_len)
     }
+
+    /// Reads the state at the start of the data of an account.
+    ///
+    /// Returns the state with the data that follows its metadata, which is
+    /// the program of a buffer or programdata account, or `None` if the data
+    /// does not hold a state.
+    pub fn from_account_data(data: &[u8]) -> Option<(Self, &[u8])> {
+        let (tag, rest) = data.split_first_chunk::<4>()?;
+        match u32::from_le_bytes(*tag) {
+            0 => Some((Self::Uninitialized, &[])),
+            1 => {
+                let (metadata, program) = data.split_at_checked(Self::size_of_buffer_metadata())?;
+                let authority_address = read_option_pubkey(&metadata[4..])?;
+                Some((Self::Buffer { authority_address }, program))
+            }
+            2 => {
+                let programdata_address = Pubkey::try_from(rest.get(..32)?).expect("32 byte slice");
+                Some((
+                    Self::Program {
+                        programdata_address,
+                    },
+                    &[],
+                ))
+            }
+            3 => {
+                let (metadata, program) =
+                    data.split_at_checked(Self::size_of_programdata_metadata())?;
+                let slot = u64::from_le_bytes(metadata[4..12].try_into().unwrap());
+                let upgrade_authority_address = read_option_pubkey(&metadata[12..])?;
+                Some((
+                    Self::ProgramData {
+                        slot,
+                        upgrade_authority_address,
+                    },
+                    program,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reads an optional pubkey, padded to the size of a present pubkey.
+fn read_option_pubkey(data: &[u8]) -> Option<Option<Pubkey>> {
+    match data.split_first()? {
+        (0, _) => Some(None),
+        (1, pubkey) => Some(Some(Pubkey::try_from(pubkey).ok()?)),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -108,4 +157,63 @@ mod tests {
         let size = serialized_size(&program_state).unwrap();
         assert_eq!(UpgradeableLoaderState::size_of_program() as u64, size);
     }
+
+    #[test]
+    fn test_from_account_data() {
+        let authority_address = Some(Pubkey::new_unique());
+        for (state, metadata_len, program) in [
+            (
+                UpgradeableLoaderState::Uninitialized,
+                UpgradeableLoaderState::size_of_uninitialized(),
+                &[][..],
+            ),
+            (
+                UpgradeableLoaderState::Buffer { authority_address },
+                UpgradeableLoaderState::size_of_buffer_metadata(),
+                &[1, 2, 3][..],
+            ),
+            (
+                UpgradeableLoaderState::Buffer {
+                    authority_address: None,
+                },
+                UpgradeableLoaderState::size_of_buffer_metadata(),
+                &[1, 2, 3][..],
+            ),
+            (
+                UpgradeableLoaderState::Program {
+                    programdata_address: Pubkey::new_unique(),
+                },
+                UpgradeableLoaderState::size_of_program(),
+                &[][..],
+            ),
+            (
+                UpgradeableLoaderState::ProgramData {
+                    slot: 42,
+                    upgrade_authority_address: authority_address,
+                },
+                UpgradeableLoaderState::size_of_programdata_metadata(),
+                &[4, 5][..],
+            ),
+        ] {
+            let mut data = vec![0; metadata_len];
+            bincode::serialize_into(&mut data[..], &state).unwrap();
+            data.extend_from_slice(program);
+            assert_eq!(
+                UpgradeableLoaderState::from_account_data(&data),
+                Some((state, program))
+            );
+            assert_eq!(
+                UpgradeableLoaderState::from_account_data(&data[..metadata_len - 1]),
+                None
+            );
+        }
+        assert_eq!(
+            UpgradeableLoaderState::from_account_data(&[4, 0, 0, 0]),
+            None
+        );
+        let mut data = vec![0; UpgradeableLoaderState::size_of_buffer_metadata()];
+        data[0] = 1;
+        data[4] = 2;
+        assert_eq!(UpgradeableLoaderState::from_account_data(&data), None);
+    }
 }
@@ -0,0 +1,106 @@
+//! Parsing the program accounts of the loader.
+//!
+//! The program held by an account can be padded with zeros up to the size
+//! of the account. [`ProgramBytes::elf`] strips the padding, so that the
+//! hashes of a deployed program can be compared with the hashes of a
+//! verifiable build of its ELF.
+
+pub use solana_loader_v3_interface::account::{ParseAccountError, ProgramBytes};
+use {
+    crate::state::{LoaderV4State, LoaderV4Status},
+    solana_pubkey::Pubkey,
+};
+
+/// A program account of the loader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoaderV4Account<'a> {
+    pub state: LoaderV4State,
+    pub program: ProgramBytes<'a>,
+}
+
+impl<'a> LoaderV4Account<'a> {
+    /// Parses an account from its owner and data, checking that the loader
+    /// owns it. An `AccountSharedData` is parsed with
+    /// `parse(account.owner(), account.data())`.
+    pub fn parse(owner: &Pubkey, data: &'a [u8]) -> Result<Self, ParseAccountError> {
+        if *owner != solana_sdk_ids::loader_v4::id() {
+            return Err(ParseAccountError::InvalidOwner(*owner));
+        }
+        let (state, program) =
+            LoaderV4State::from_account_data(data).ok_or(ParseAccountError::InvalidData)?;
+        Ok(Self {
+            state,
+            program: ProgramBytes(program),
+        })
+    }
+
+    /// The authority of the program, unless it is finalized.
+    pub fn authority(&self) -> Option<Pubkey> {
+        (self.state.status != LoaderV4Status::Finalized)
+            .then_some(self.state.authority_address_or_next_version)
+    }
+
+    /// The next version of a finalized program.
+    pub fn next_version(&self) -> Option<Pubkey> {
+        (self.state.status == LoaderV4Status::Finalized)
+            .then_some(self.state.authority_address_or_next_version)
+    }
+
+    /// The slot the program was last deployed, retracted or initialized in.
+    pub fn last_deploy_slot(&self) -> u64 {
+        self.state.slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk_ids::loader_v4};
+
+    fn account_data(
+        slot: u64,
+        address: &Pubkey,
+        status: LoaderV4Status,
+        program: &[u8],
+    ) -> Vec<u8> {
+        let mut data = slot.to_le_bytes().to_vec();
+        data.extend_from_slice(address.as_ref());
+        data.extend_from_slice(&(status as u64).to_le_bytes());
+        data.extend_from_slice(program);
+        data
+    }
+
+    #[test]
+    fn test_parse_account() {
+        let owner = loader_v4::id();
+        let authority = Pubkey::new_unique();
+        let elf = [0x7f, b'E', b'L', b'F', 0, 1, 2];
+        let mut padded = elf.to_vec();
+        padded.resize(64, 0);
+        let data = account_data(42, &authority, LoaderV4Status::Deployed, &padded);
+        let parsed = LoaderV4Account::parse(&owner, &data).unwrap();
+        assert_eq!(parsed.authority(), Some(authority));
+        assert_eq!(parsed.next_version(), None);
+        assert_eq!(parsed.last_deploy_slot(), 42);
+        assert_eq!(parsed.program.0, &padded[..]);
+        assert_eq!(parsed.program.elf(), &elf[..]);
+
+        let next_version = Pubkey::new_unique();
+        let finalized = account_data(7, &next_version, LoaderV4Status::Finalized, &[]);
+        let parsed = LoaderV4Account::parse(&owner, &finalized).unwrap();
+        assert_eq!(parsed.authority(), None);
+        assert_eq!(parsed.next_version(), Some(next_version));
+        assert_eq!(parsed.program.elf(), &[] as &[u8]);
+
+        let other_owner = Pubkey::new_unique();
+        assert_eq!(
+            LoaderV4Account::parse(&other_owner, &data),
+            Err(ParseAccountError::InvalidOwner(other_owner))
+        );
+        let mut invalid_status = data;
+        invalid_status[40] = 3;
+        assert_eq!(
+            LoaderV4Account::parse(&owner, &invalid_status),
+            Err(ParseAccountError::InvalidData)
+        );
+    }
+}
@@ -4,7 +4,11 @@
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+#[cfg(feature = "account")]
+pub mod account;
 pub mod instruction;
+#[cfg(feature = "lifecycle")]
+pub mod lifecycle;
 pub mod state;
 
 /// Cooldown before a program can be un-/redeployed again
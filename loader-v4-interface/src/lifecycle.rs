@@ -0,0 +1,1094 @@
+//! Planning the transactions that manage a program.
+//!
+//! Programs of loader v4 move between the [`LoaderV4Status`]es through
+//! instructions that are only legal in some states: a program is written
+//! while retracted, deploying and retracting wait for
+//! [`DEPLOYMENT_COOLDOWN_IN_SLOTS`], and a finalized program can not be
+//! changed anymore. The planners of this module check the state of the
+//! accounts involved and return the transactions to send, in order, so that
+//! an illegal transaction is rejected before it is sent.
+
+use {
+    crate::{
+        instruction::{
+            create_buffer, deploy, deploy_from_source, finalize, retract, set_program_length,
+            transfer_authority, write,
+        },
+        state::{LoaderV4State, LoaderV4Status},
+        DEPLOYMENT_COOLDOWN_IN_SLOTS,
+    },
+    solana_instruction::Instruction,
+    solana_loader_v3_interface::{deploy_planner, state::UpgradeableLoaderState},
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    std::fmt,
+};
+
+/// Accounts and cluster parameters shared by the planners.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleConfig {
+    /// Pays for transaction fees and rent, and receives the lamports freed
+    /// when an account shrinks.
+    pub payer: Pubkey,
+    /// Authority of the program.
+    pub authority: Pubkey,
+    /// The program managed.
+    pub program_address: Pubkey,
+    pub rent: Rent,
+    pub lamports_per_signature: u64,
+    /// The slot the first transaction of a plan lands in.
+    pub current_slot: u64,
+}
+
+/// The lamports and data of an account, as fetched from a cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExistingAccount<'a> {
+    pub lamports: u64,
+    pub data: &'a [u8],
+}
+
+/// The step of a plan a transaction performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleStep {
+    /// Creates and initializes the account of a program or buffer.
+    Create {
+        address: Pubkey,
+        len: u32,
+    },
+    /// Funds and resizes a retracted program or buffer.
+    SetProgramLength {
+        address: Pubkey,
+        len: u32,
+    },
+    Write {
+        address: Pubkey,
+        offset: u32,
+        len: usize,
+    },
+    Retract,
+    Deploy,
+    /// Deploys the program from a staging buffer, which is consumed.
+    DeployFromSource {
+        source: Pubkey,
+    },
+    TransferAuthority {
+        new_authority: Pubkey,
+    },
+    Finalize {
+        next_version: Pubkey,
+    },
+    /// Migrates a loader v3 program to loader v4.
+    Migrate,
+}
+
+/// A step of a plan, with the earliest slot its transaction can land in,
+/// assuming the earlier transactions of the plan land in the current slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledStep {
+    pub step: LifecycleStep,
+    pub min_slot: u64,
+}
+
+/// The instructions of one transaction, with the accounts that must sign it.
+pub type InstructionBatch = deploy_planner::InstructionBatch<ScheduledStep>;
+
+fn scheduled_batch(
+    step: LifecycleStep,
+    instructions: Vec<Instruction>,
+    payer: &Pubkey,
+    min_slot: u64,
+) -> InstructionBatch {
+    InstructionBatch::new(ScheduledStep { step, min_slot }, instructions, payer)
+}
+
+/// The transactions of a plan, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecyclePlan {
+    pub batches: Vec<InstructionBatch>,
+    /// Lamports the payer funds accounts with.
+    pub rent_lamports: u64,
+    /// Transaction fees.
+    pub fee_lamports: u64,
+}
+
+impl LifecyclePlan {
+    fn new(batches: Vec<InstructionBatch>, rent_lamports: u64, config: &LifecycleConfig) -> Self {
+        let fee_lamports = batches
+            .iter()
+            .map(|batch| (batch.signers.len() as u64).saturating_mul(config.lamports_per_signature))
+            .sum();
+        Self {
+            batches,
+            rent_lamports,
+            fee_lamports,
+        }
+    }
+
+    /// The balance the payer needs before the plan is executed.
+    pub fn total_lamports(&self) -> u64 {
+        self.rent_lamports.saturating_add(self.fee_lamports)
+    }
+}
+
+/// Reasons a plan is illegal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleError {
+    EmptyProgram,
+    /// The program is larger than an account of loader v4 can hold.
+    ProgramTooLarge(usize),
+    /// The account is not a program of loader v4.
+    InvalidProgramAccount(Pubkey),
+    /// The account is not the program data of a loader v3 program.
+    InvalidProgramData,
+    /// The account is managed by another authority.
+    AuthorityMismatch {
+        address: Pubkey,
+        authority: Pubkey,
+    },
+    /// The loader v3 program has no upgrade authority.
+    Immutable,
+    /// A finalized program can not be changed.
+    Finalized,
+    /// The program can not move from one status to the other.
+    InvalidTransition {
+        from: LoaderV4Status,
+        to: LoaderV4Status,
+    },
+    /// The program was deployed or retracted too recently.
+    Cooldown {
+        ready_at_slot: u64,
+    },
+}
+
+impl std::error::Error for LifecycleError {}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyProgram => f.write_str("program is empty"),
+            Self::ProgramTooLarge(len) => write!(f, "program of {len} bytes is too large"),
+            Self::InvalidProgramAccount(address) => {
+                write!(f, "account {address} is not a loader v4 program")
+            }
+            Self::InvalidProgramData => f.write_str("account is not a loader v3 program data"),
+            Self::AuthorityMismatch { address, authority } => {
+                write!(f, "account {address} has authority {authority}")
+            }
+            Self::Immutable => f.write_str("program has no upgrade authority"),
+            Self::Finalized => f.write_str("program is finalized"),
+            Self::InvalidTransition { from, to } => {
+                write!(f, "program can not go from {from:?} to {to:?}")
+            }
+            Self::Cooldown { ready_at_slot } => {
+                write!(f, "program is in cooldown until slot {ready_at_slot}")
+            }
+        }
+    }
+}
+
+/// Reads the state of a program of loader v4 from the data of its account.
+pub fn program_state(address: &Pubkey, data: &[u8]) -> Result<LoaderV4State, LifecycleError> {
+    LoaderV4State::from_account_data(data)
+        .map(|(state, _)| state)
+        .ok_or(LifecycleError::InvalidProgramAccount(*address))
+}
+
+/// Checks that the program can move from its status to `to` in
+/// `current_slot`.
+pub fn check_transition(
+    state: &LoaderV4State,
+    to: LoaderV4Status,
+    current_slot: u64,
+) -> Result<(), LifecycleError> {
+    let from = state.status;
+    match (from, to) {
+        (LoaderV4Status::Finalized, _) => return Err(LifecycleError::Finalized),
+        (LoaderV4Status::Retracted, LoaderV4Status::Deployed)
+        | (LoaderV4Status::Deployed, LoaderV4Status::Retracted) => {
+            // A freshly initialized program has never been deployed
+            let ready_at_slot = state.slot.saturating_add(DEPLOYMENT_COOLDOWN_IN_SLOTS);
+            if state.slot != 0 && ready_at_slot > current_slot {
+                return Err(LifecycleError::Cooldown { ready_at_slot });
+            }
+        }
+        (LoaderV4Status::Deployed, LoaderV4Status::Finalized) => {}
+        _ => return Err(LifecycleError::InvalidTransition { from, to }),
+    }
+    Ok(())
+}
+
+/// The earliest slot a program can be deployed in, once retracted in
+/// `current_slot` if it is deployed.
+fn deploy_ready_slot(state: &LoaderV4State, current_slot: u64) -> u64 {
+    match state.status {
+        LoaderV4Status::Deployed => current_slot.saturating_add(DEPLOYMENT_COOLDOWN_IN_SLOTS),
+        _ if state.slot == 0 => current_slot,
+        _ => current_slot.max(state.slot.saturating_add(DEPLOYMENT_COOLDOWN_IN_SLOTS)),
+    }
+}
+
+/// Reads the state of a program, checking that `authority` manages it.
+fn managed_program_state(
+    address: &Pubkey,
+    data: &[u8],
+    authority: &Pubkey,
+) -> Result<LoaderV4State, LifecycleError> {
+    let state = program_state(address, data)?;
+    if state.status == LoaderV4Status::Finalized {
+        return Err(LifecycleError::Finalized);
+    }
+    if state.authority_address_or_next_version != *authority {
+        return Err(LifecycleError::AuthorityMismatch {
+            address: *address,
+            authority: state.authority_address_or_next_version,
+        });
+    }
+    Ok(state)
+}
+
+/// The largest chunk of program a single write transaction can carry.
+pub fn max_write_chunk_size(payer: &Pubkey, address: &Pubkey, authority: &Pubkey) -> usize {
+    deploy_planner::max_chunk_size(payer, write(address, authority, 0, vec![]))
+}
+
+/// Plans the transactions that put `program` in the account at `address`,
+/// which is retracted once they land. Returns the lamports the payer funds
+/// the account with.
+fn plan_upload(
+    program: &[u8],
+    address: &Pubkey,
+    existing: Option<&ExistingAccount>,
+    config: &LifecycleConfig,
+    batches: &mut Vec<InstructionBatch>,
+) -> u64 {
+    let LifecycleConfig {
+        payer,
+        authority,
+        rent,
+        current_slot,
+        ..
+    } = config;
+    // Checked by the planners
+    let len = program.len() as u32;
+    let required_lamports =
+        rent.minimum_balance(LoaderV4State::program_data_offset().saturating_add(program.len()));
+    let (rent_lamports, written) = match existing {
+        None => {
+            batches.push(scheduled_batch(
+                LifecycleStep::Create {
+                    address: *address,
+                    len,
+                },
+                create_buffer(payer, address, required_lamports, authority, len, payer),
+                payer,
+                *current_slot,
+            ));
+            (required_lamports, &[][..])
+        }
+        Some(ExistingAccount { lamports, data }) => {
+            // Checked by the planners
+            let (_, written) = LoaderV4State::from_account_data(data).unwrap();
+            let top_up = required_lamports.saturating_sub(*lamports);
+            if written.len() != program.len() {
+                let mut instructions = vec![];
+                if top_up > 0 {
+                    instructions.push(solana_system_interface::instruction::transfer(
+                        payer, address, top_up,
+                    ));
+                }
+                instructions.push(set_program_length(address, authority, len, payer));
+                batches.push(scheduled_batch(
+                    LifecycleStep::SetProgramLength {
+                        address: *address,
+                        len,
+                    },
+                    instructions,
+                    payer,
+                    *current_slot,
+                ));
+            }
+            (top_up, written)
+        }
+    };
+
+    let chunk_size = max_write_chunk_size(payer, address, authority);
+    for (index, chunk) in program.chunks(chunk_size).enumerate() {
+        let start = index.saturating_mul(chunk_size);
+        if written.get(start..start.saturating_add(chunk.len())) == Some(chunk) {
+            continue;
+        }
+        let offset = start as u32;
+        batches.push(scheduled_batch(
+            LifecycleStep::Write {
+                address: *address,
+                offset,
+                len: chunk.len(),
+            },
+            vec![write(address, authority, offset, chunk.to_vec())],
+            payer,
+            *current_slot,
+        ));
+    }
+    rent_lamports
+}
+
+fn check_program_len(program: &[u8]) -> Result<(), LifecycleError> {
+    if program.is_empty() {
+        return Err(LifecycleError::EmptyProgram);
+    }
+    if u32::try_from(program.len()).is_err() {
+        return Err(LifecycleError::ProgramTooLarge(program.len()));
+    }
+    Ok(())
+}
+
+/// Plans the deployment of `program`, the bytes of an ELF, in place.
+///
+/// `existing` is the account of the program if it exists. A deployed program
+/// is retracted, resized and rewritten, leaving it unavailable until it is
+/// deployed again. Chunks the account already holds are not written again,
+/// so an interrupted deployment can be resumed.
+pub fn plan_deploy(
+    program: &[u8],
+    config: &LifecycleConfig,
+    existing: Option<ExistingAccount>,
+) -> Result<LifecyclePlan, LifecycleError> {
+    check_program_len(program)?;
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    let mut batches = vec![];
+    let mut deploy_slot = *current_slot;
+    if let Some(account) = &existing {
+        let state = managed_program_state(program_address, account.data, authority)?;
+        deploy_slot = deploy_ready_slot(&state, *current_slot);
+        if state.status == LoaderV4Status::Deployed {
+            check_transition(&state, LoaderV4Status::Retracted, *current_slot)?;
+            batches.push(scheduled_batch(
+                LifecycleStep::Retract,
+                vec![retract(program_address, authority)],
+                payer,
+                *current_slot,
+            ));
+        }
+    }
+    let rent_lamports = plan_upload(
+        program,
+        program_address,
+        existing.as_ref(),
+        config,
+        &mut batches,
+    );
+    batches.push(scheduled_batch(
+        LifecycleStep::Deploy,
+        vec![deploy(program_address, authority)],
+        payer,
+        deploy_slot,
+    ));
+    Ok(LifecyclePlan::new(batches, rent_lamports, config))
+}
+
+/// Plans the upgrade of a program from a staging buffer.
+///
+/// `program` is written to the buffer at `buffer_address` while the program
+/// stays deployed, and the program is then retracted and deployed from the
+/// buffer, which keeps it unavailable for the cooldown only. `existing_buffer`
+/// is the account of the buffer if it was created by an earlier attempt.
+pub fn plan_deploy_from_buffer(
+    program: &[u8],
+    buffer_address: &Pubkey,
+    config: &LifecycleConfig,
+    existing_program: ExistingAccount,
+    existing_buffer: Option<ExistingAccount>,
+) -> Result<LifecyclePlan, LifecycleError> {
+    check_program_len(program)?;
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    let state = managed_program_state(program_address, existing_program.data, authority)?;
+    if let Some(buffer) = &existing_buffer {
+        let buffer_state = managed_program_state(buffer_address, buffer.data, authority)?;
+        if buffer_state.status != LoaderV4Status::Retracted {
+            return Err(LifecycleError::InvalidTransition {
+                from: buffer_state.status,
+                to: LoaderV4Status::Retracted,
+            });
+        }
+    }
+
+    let mut batches = vec![];
+    let rent_lamports = plan_upload(
+        program,
+        buffer_address,
+        existing_buffer.as_ref(),
+        config,
+        &mut batches,
+    );
+    if state.status == LoaderV4Status::Deployed {
+        check_transition(&state, LoaderV4Status::Retracted, *current_slot)?;
+        batches.push(scheduled_batch(
+            LifecycleStep::Retract,
+            vec![retract(program_address, authority)],
+            payer,
+            *current_slot,
+        ));
+    }
+    let deploy_slot = deploy_ready_slot(&state, *current_slot);
+    batches.push(scheduled_batch(
+        LifecycleStep::DeployFromSource {
+            source: *buffer_address,
+        },
+        vec![deploy_from_source(
+            program_address,
+            authority,
+            buffer_address,
+        )],
+        payer,
+        deploy_slot,
+    ));
+    Ok(LifecyclePlan::new(batches, rent_lamports, config))
+}
+
+/// Plans the retraction of a deployed program.
+pub fn plan_retract(
+    config: &LifecycleConfig,
+    existing_program: ExistingAccount,
+) -> Result<LifecyclePlan, LifecycleError> {
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    let state = managed_program_state(program_address, existing_program.data, authority)?;
+    check_transition(&state, LoaderV4Status::Retracted, *current_slot)?;
+    let batch = scheduled_batch(
+        LifecycleStep::Retract,
+        vec![retract(program_address, authority)],
+        payer,
+        *current_slot,
+    );
+    Ok(LifecyclePlan::new(vec![batch], 0, config))
+}
+
+/// Plans the transfer of the authority over a program to `new_authority`,
+/// which signs the transfer.
+pub fn plan_transfer_authority(
+    config: &LifecycleConfig,
+    existing_program: ExistingAccount,
+    new_authority: &Pubkey,
+) -> Result<LifecyclePlan, LifecycleError> {
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    managed_program_state(program_address, existing_program.data, authority)?;
+    let batch = scheduled_batch(
+        LifecycleStep::TransferAuthority {
+            new_authority: *new_authority,
+        },
+        vec![transfer_authority(
+            program_address,
+            authority,
+            new_authority,
+        )],
+        payer,
+        *current_slot,
+    );
+    Ok(LifecyclePlan::new(vec![batch], 0, config))
+}
+
+/// Plans the finalization of a deployed program.
+///
+/// `next_version` is the account of the program that supersedes it, with
+/// its address, or `None` if the program has no next version.
+pub fn plan_finalize(
+    config: &LifecycleConfig,
+    existing_program: ExistingAccount,
+    next_version: Option<(&Pubkey, ExistingAccount)>,
+) -> Result<LifecyclePlan, LifecycleError> {
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    let state = managed_program_state(program_address, existing_program.data, authority)?;
+    check_transition(&state, LoaderV4Status::Finalized, *current_slot)?;
+    let next_version_address = match next_version {
+        Some((address, account)) => {
+            managed_program_state(address, account.data, authority)?;
+            *address
+        }
+        None => *program_address,
+    };
+    let batch = scheduled_batch(
+        LifecycleStep::Finalize {
+            next_version: next_version_address,
+        },
+        vec![finalize(program_address, authority, &next_version_address)],
+        payer,
+        *current_slot,
+    );
+    Ok(LifecyclePlan::new(vec![batch], 0, config))
+}
+
+/// Plans the migration of a loader v3 program to loader v4.
+///
+/// `program_data` is the program data account of the program, whose upgrade
+/// authority must be the authority of the config.
+pub fn plan_migration(
+    config: &LifecycleConfig,
+    program_data: ExistingAccount,
+) -> Result<LifecyclePlan, LifecycleError> {
+    let LifecycleConfig {
+        payer,
+        authority,
+        program_address,
+        current_slot,
+        ..
+    } = config;
+    let Some((
+        UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        },
+        _,
+    )) = UpgradeableLoaderState::from_account_data(program_data.data)
+    else {
+        return Err(LifecycleError::InvalidProgramData);
+    };
+    let upgrade_authority = upgrade_authority_address.ok_or(LifecycleError::Immutable)?;
+    let program_data_address =
+        solana_loader_v3_interface::get_program_data_address(program_address);
+    if upgrade_authority != *authority {
+        return Err(LifecycleError::AuthorityMismatch {
+            address: program_data_address,
+            authority: upgrade_authority,
+        });
+    }
+    let batch = scheduled_batch(
+        LifecycleStep::Migrate,
+        vec![solana_loader_v3_interface::instruction::migrate_program(
+            &program_data_address,
+            program_address,
+            authority,
+        )],
+        payer,
+        *current_slot,
+    );
+    Ok(LifecyclePlan::new(vec![batch], 0, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_packet::PACKET_DATA_SIZE};
+
+    const CURRENT_SLOT: u64 = 100;
+
+    fn config() -> LifecycleConfig {
+        LifecycleConfig {
+            payer: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            program_address: Pubkey::new_unique(),
+            rent: Rent::default(),
+            lamports_per_signature: 5_000,
+            current_slot: CURRENT_SLOT,
+        }
+    }
+
+    fn program(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn account_data(
+        slot: u64,
+        authority: &Pubkey,
+        status: LoaderV4Status,
+        program: &[u8],
+    ) -> Vec<u8> {
+        let mut data = slot.to_le_bytes().to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&(status as u64).to_le_bytes());
+        data.extend_from_slice(program);
+        data
+    }
+
+    fn steps(plan: &LifecyclePlan) -> Vec<(LifecycleStep, u64)> {
+        plan.batches
+            .iter()
+            .map(|batch| (batch.stage.step, batch.stage.min_slot))
+            .collect()
+    }
+
+    #[test]
+    fn test_program_state() {
+        let authority = Pubkey::new_unique();
+        let address = Pubkey::new_unique();
+        let data = account_data(7, &authority, LoaderV4Status::Finalized, &[1, 2]);
+        assert_eq!(
+            program_state(&address, &data),
+            Ok(LoaderV4State {
+                slot: 7,
+                authority_address_or_next_version: authority,
+                status: LoaderV4Status::Finalized,
+            })
+        );
+        let mut invalid = data.clone();
+        invalid[40] = 3;
+        for data in [&data[..47], &invalid[..]] {
+            assert_eq!(
+                program_state(&address, data),
+                Err(LifecycleError::InvalidProgramAccount(address))
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_transition() {
+        let state = |slot, status| LoaderV4State {
+            slot,
+            authority_address_or_next_version: Pubkey::new_unique(),
+            status,
+        };
+        use LoaderV4Status::*;
+        assert_eq!(check_transition(&state(0, Retracted), Deployed, 0), Ok(()));
+        assert_eq!(
+            check_transition(&state(10, Retracted), Deployed, 10),
+            Err(LifecycleError::Cooldown { ready_at_slot: 11 })
+        );
+        assert_eq!(
+            check_transition(&state(10, Deployed), Retracted, 11),
+            Ok(())
+        );
+        assert_eq!(
+            check_transition(&state(10, Deployed), Finalized, 10),
+            Ok(())
+        );
+        assert_eq!(
+            check_transition(&state(10, Retracted), Finalized, 11),
+            Err(LifecycleError::InvalidTransition {
+                from: Retracted,
+                to: Finalized
+            })
+        );
+        assert_eq!(
+            check_transition(&state(10, Deployed), Deployed, 11),
+            Err(LifecycleError::InvalidTransition {
+                from: Deployed,
+                to: Deployed
+            })
+        );
+        for to in [Retracted, Deployed, Finalized] {
+            assert_eq!(
+                check_transition(&state(10, Finalized), to, 11),
+                Err(LifecycleError::Finalized)
+            );
+        }
+    }
+
+    #[test]
+    fn test_plan_deploy_new_program() {
+        let config = config();
+        let program = program(3_000);
+        let plan = plan_deploy(&program, &config, None).unwrap();
+
+        let address = config.program_address;
+        let chunk_size = max_write_chunk_size(&config.payer, &address, &config.authority);
+        let mut expected = vec![(
+            LifecycleStep::Create {
+                address,
+                len: 3_000,
+            },
+            CURRENT_SLOT,
+        )];
+        for offset in (0..program.len()).step_by(chunk_size) {
+            expected.push((
+                LifecycleStep::Write {
+                    address,
+                    offset: offset as u32,
+                    len: chunk_size.min(program.len() - offset),
+                },
+                CURRENT_SLOT,
+            ));
+        }
+        expected.push((LifecycleStep::Deploy, CURRENT_SLOT));
+        assert_eq!(steps(&plan), expected);
+
+        assert_eq!(
+            plan.batches[0].signers,
+            vec![config.payer, address, config.authority]
+        );
+        for batch in &plan.batches {
+            assert!(batch.transaction_size() <= PACKET_DATA_SIZE);
+        }
+        assert_eq!(plan.rent_lamports, config.rent.minimum_balance(48 + 3_000));
+        let writes = expected.len() as u64 - 2;
+        assert_eq!(plan.fee_lamports, (3 + 2 * writes + 2) * 5_000);
+        assert_eq!(
+            plan.total_lamports(),
+            plan.rent_lamports + plan.fee_lamports
+        );
+    }
+
+    #[test]
+    fn test_plan_deploy_upgrade_in_place() {
+        let config = config();
+        let address = config.program_address;
+        let program = program(3_000);
+        let chunk_size = max_write_chunk_size(&config.payer, &address, &config.authority);
+
+        // The deployed program matches the new one but for its first chunk,
+        // and is shorter
+        let mut deployed = program[..2_500].to_vec();
+        deployed[0] ^= 1;
+        let data = account_data(
+            CURRENT_SLOT - 1,
+            &config.authority,
+            LoaderV4Status::Deployed,
+            &deployed,
+        );
+        let lamports = config.rent.minimum_balance(data.len());
+        let account = ExistingAccount {
+            lamports,
+            data: &data,
+        };
+        let plan = plan_deploy(&program, &config, Some(account)).unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![
+                (LifecycleStep::Retract, CURRENT_SLOT),
+                (
+                    LifecycleStep::SetProgramLength {
+                        address,
+                        len: 3_000
+                    },
+                    CURRENT_SLOT
+                ),
+                (
+                    LifecycleStep::Write {
+                        address,
+                        offset: 0,
+                        len: chunk_size
+                    },
+                    CURRENT_SLOT
+                ),
+                (
+                    LifecycleStep::Write {
+                        address,
+                        offset: (2 * chunk_size) as u32,
+                        len: chunk_size
+                    },
+                    CURRENT_SLOT
+                ),
+                (
+                    LifecycleStep::Write {
+                        address,
+                        offset: (3 * chunk_size) as u32,
+                        len: 3_000 - 3 * chunk_size
+                    },
+                    CURRENT_SLOT
+                ),
+                (LifecycleStep::Deploy, CURRENT_SLOT + 1),
+            ]
+        );
+        let top_up = config.rent.minimum_balance(48 + 3_000) - lamports;
+        assert_eq!(plan.rent_lamports, top_up);
+        assert_eq!(
+            plan.batches[1].instructions[0],
+            solana_system_interface::instruction::transfer(&config.payer, &address, top_up)
+        );
+
+        // Deployed in the current slot
+        let data = account_data(
+            CURRENT_SLOT,
+            &config.authority,
+            LoaderV4Status::Deployed,
+            &deployed,
+        );
+        let account = ExistingAccount {
+            lamports,
+            data: &data,
+        };
+        assert_eq!(
+            plan_deploy(&program, &config, Some(account)),
+            Err(LifecycleError::Cooldown {
+                ready_at_slot: CURRENT_SLOT + 1
+            })
+        );
+
+        // Resumed after the writes landed, in the slot of the retraction
+        let data = account_data(
+            CURRENT_SLOT,
+            &config.authority,
+            LoaderV4Status::Retracted,
+            &program,
+        );
+        let account = ExistingAccount {
+            lamports: config.rent.minimum_balance(data.len()),
+            data: &data,
+        };
+        let plan = plan_deploy(&program, &config, Some(account)).unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![(LifecycleStep::Deploy, CURRENT_SLOT + 1)]
+        );
+        assert_eq!(plan.rent_lamports, 0);
+
+        // Managed by another authority
+        let other_authority = Pubkey::new_unique();
+        let data = account_data(0, &other_authority, LoaderV4Status::Retracted, &program);
+        let account = ExistingAccount {
+            lamports,
+            data: &data,
+        };
+        assert_eq!(
+            plan_deploy(&program, &config, Some(account)),
+            Err(LifecycleError::AuthorityMismatch {
+                address,
+                authority: other_authority,
+            })
+        );
+        assert_eq!(
+            plan_deploy(&[], &config, None),
+            Err(LifecycleError::EmptyProgram)
+        );
+    }
+
+    #[test]
+    fn test_plan_deploy_from_buffer() {
+        let config = config();
+        let buffer = Pubkey::new_unique();
+        let program = program(500);
+        let data = account_data(
+            CURRENT_SLOT - 10,
+            &config.authority,
+            LoaderV4Status::Deployed,
+            &[0; 100],
+        );
+        let deployed = ExistingAccount {
+            lamports: config.rent.minimum_balance(data.len()),
+            data: &data,
+        };
+        let plan = plan_deploy_from_buffer(&program, &buffer, &config, deployed, None).unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![
+                (
+                    LifecycleStep::Create {
+                        address: buffer,
+                        len: 500
+                    },
+                    CURRENT_SLOT
+                ),
+                (
+                    LifecycleStep::Write {
+                        address: buffer,
+                        offset: 0,
+                        len: 500
+                    },
+                    CURRENT_SLOT
+                ),
+                (LifecycleStep::Retract, CURRENT_SLOT),
+                (
+                    LifecycleStep::DeployFromSource { source: buffer },
+                    CURRENT_SLOT + 1
+                ),
+            ]
+        );
+        assert_eq!(plan.rent_lamports, config.rent.minimum_balance(48 + 500));
+
+        // The buffer is complete, and the program already retracted
+        let buffer_data = account_data(0, &config.authority, LoaderV4Status::Retracted, &program);
+        let buffer_account = ExistingAccount {
+            lamports: config.rent.minimum_balance(buffer_data.len()),
+            data: &buffer_data,
+        };
+        let data = account_data(
+            CURRENT_SLOT - 10,
+            &config.authority,
+            LoaderV4Status::Retracted,
+            &[0; 100],
+        );
+        let retracted = ExistingAccount {
+            lamports: 0,
+            data: &data,
+        };
+        let plan =
+            plan_deploy_from_buffer(&program, &buffer, &config, retracted, Some(buffer_account))
+                .unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![(
+                LifecycleStep::DeployFromSource { source: buffer },
+                CURRENT_SLOT
+            )]
+        );
+
+        // The buffer is deployed
+        let buffer_data = account_data(0, &config.authority, LoaderV4Status::Deployed, &program);
+        let buffer_account = ExistingAccount {
+            lamports: 0,
+            data: &buffer_data,
+        };
+        assert_eq!(
+            plan_deploy_from_buffer(&program, &buffer, &config, retracted, Some(buffer_account)),
+            Err(LifecycleError::InvalidTransition {
+                from: LoaderV4Status::Deployed,
+                to: LoaderV4Status::Retracted,
+            })
+        );
+    }
+
+    #[test]
+    fn test_plan_management() {
+        let config = config();
+        let deployed_data = account_data(
+            CURRENT_SLOT - 1,
+            &config.authority,
+            LoaderV4Status::Deployed,
+            &[1],
+        );
+        let deployed = ExistingAccount {
+            lamports: 0,
+            data: &deployed_data,
+        };
+        let finalized_data = account_data(
+            CURRENT_SLOT - 1,
+            &config.authority,
+            LoaderV4Status::Finalized,
+            &[1],
+        );
+        let finalized = ExistingAccount {
+            lamports: 0,
+            data: &finalized_data,
+        };
+
+        let plan = plan_retract(&config, deployed).unwrap();
+        assert_eq!(steps(&plan), vec![(LifecycleStep::Retract, CURRENT_SLOT)]);
+        assert_eq!(plan.fee_lamports, 10_000);
+        assert_eq!(
+            plan_retract(&config, finalized),
+            Err(LifecycleError::Finalized)
+        );
+
+        let new_authority = Pubkey::new_unique();
+        let plan = plan_transfer_authority(&config, deployed, &new_authority).unwrap();
+        assert_eq!(
+            plan.batches[0].signers,
+            vec![config.payer, config.authority, new_authority]
+        );
+        assert_eq!(
+            plan_transfer_authority(&config, finalized, &new_authority),
+            Err(LifecycleError::Finalized)
+        );
+
+        let plan = plan_finalize(&config, deployed, None).unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![(
+                LifecycleStep::Finalize {
+                    next_version: config.program_address
+                },
+                CURRENT_SLOT
+            )]
+        );
+        let next_version = Pubkey::new_unique();
+        let plan = plan_finalize(&config, deployed, Some((&next_version, deployed))).unwrap();
+        assert_eq!(
+            plan.batches[0].stage.step,
+            LifecycleStep::Finalize { next_version }
+        );
+        assert_eq!(
+            plan_finalize(&config, deployed, Some((&next_version, finalized))),
+            Err(LifecycleError::Finalized)
+        );
+    }
+
+    #[test]
+    fn test_plan_migration() {
+        let config = config();
+        let program_data = |authority: Option<&Pubkey>| {
+            let mut data = 3u32.to_le_bytes().to_vec();
+            data.extend_from_slice(&42u64.to_le_bytes());
+            match authority {
+                Some(authority) => {
+                    data.push(1);
+                    data.extend_from_slice(authority.as_ref());
+                }
+                None => data.extend_from_slice(&[0; 33]),
+            }
+            data.extend_from_slice(&[1, 2, 3]);
+            data
+        };
+
+        let data = program_data(Some(&config.authority));
+        let account = ExistingAccount {
+            lamports: 0,
+            data: &data,
+        };
+        let plan = plan_migration(&config, account).unwrap();
+        let program_data_address =
+            solana_loader_v3_interface::get_program_data_address(&config.program_address);
+        assert_eq!(
+            plan.batches[0].instructions,
+            vec![solana_loader_v3_interface::instruction::migrate_program(
+                &program_data_address,
+                &config.program_address,
+                &config.authority,
+            )]
+        );
+
+        let other_authority = Pubkey::new_unique();
+        let data = program_data(Some(&other_authority));
+        assert_eq!(
+            plan_migration(
+                &config,
+                ExistingAccount {
+                    lamports: 0,
+                    data: &data
+                }
+            ),
+            Err(LifecycleError::AuthorityMismatch {
+                address: program_data_address,
+                authority: other_authority,
+            })
+        );
+        let data = program_data(None);
+        assert_eq!(
+            plan_migration(
+                &config,
+                ExistingAccount {
+                    lamports: 0,
+                    data: &data
+                }
+            ),
+            Err(LifecycleError::Immutable)
+        );
+        let mut data = program_data(Some(&config.authority));
+        data[0] = 1;
+        assert_eq!(
+            plan_migration(
+                &config,
+                ExistingAccount {
+                    lamports: 0,
+                    data: &data
+                }
+            ),
+            Err(LifecycleError::InvalidProgramData)
+        );
+    }
+}
@@ -33,6 +33,26 @@ impl LoaderV4State {
     pub const fn program_data_offset() -> usize {
         std::mem::size_of::<Self>()
     }
+
+    /// Reads the state at the start of the data of a program account.
+    ///
+    /// Returns the state with the program that follows it, or `None` if the
+    /// data does not hold a state.
+    pub fn from_account_data(data: &[u8]) -> Option<(Self, &[u8])> {
+        let (metadata, program) = data.split_at_checked(Self::program_data_offset())?;
+        let status = match u64::from_le_bytes(metadata[40..48].try_into().unwrap()) {
+            0 => LoaderV4Status::Retracted,
+            1 => LoaderV4Status::Deployed,
+            2 => LoaderV4Status::Finalized,
+            _ => return None,
+        };
+        let state = Self {
+            slot: u64::from_le_bytes(metadata[..8].try_into().unwrap()),
+            authority_address_or_next_version: Pubkey::try_from(&metadata[8..40]).unwrap(),
+            status,
+        };
+        Some((state, program))
+    }
 }
 
 #[cfg(test)]
@@ -49,4 +69,24 @@ mod tests {
         assert_eq!(offset_of!(LoaderV4State, status), 0x28);
         assert_eq!(LoaderV4State::program_data_offset(), 0x30);
     }
+
+    #[test]
+    fn test_from_account_data() {
+        let state = LoaderV4State {
+            slot: 42,
+            authority_address_or_next_version: Pubkey::new_unique(),
+            status: LoaderV4Status::Deployed,
+        };
+        let mut data = state.slot.to_le_bytes().to_vec();
+        data.extend_from_slice(state.authority_address_or_next_version.as_ref());
+        data.extend_from_slice(&(state.status as u64).to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(
+            LoaderV4State::from_account_data(&data),
+            Some((state, &[1, 2, 3][..]))
+        );
+        assert_eq!(LoaderV4State::from_account_data(&data[..47]), None);
+        data[40] = 3;
+        assert_eq!(LoaderV4State::from_account_data(&data), None);
+    }
 }
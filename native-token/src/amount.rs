@@ -0,0 +1,484 @@
+//! Exact decimal amounts of native tokens.
+//!
+//! A [`TokenAmount`] counts the smallest units of a token, lamports for SOL,
+//! and converts from and to the decimal notation of the token without going
+//! through floating point. The ticker and number of decimals of the token
+//! come from a [`Denomination`], so that clusters with their own native
+//! token can reuse the type.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+/// The ticker and precision of a native token.
+pub trait Denomination {
+    /// The symbol of the token, e.g. `SOL`.
+    const TICKER: &'static str;
+    /// The number of decimals of one token, at most 19.
+    const DECIMALS: u8;
+}
+
+/// SOL, divided into 10^9 lamports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Native {}
+
+impl Denomination for Native {
+    const TICKER: &'static str = "SOL";
+    const DECIMALS: u8 = 9;
+}
+
+/// An amount of SOL.
+pub type SolAmount = TokenAmount<Native>;
+
+/// An exact amount of a native token, in its smallest units.
+pub struct TokenAmount<D: Denomination = Native> {
+    base_units: u64,
+    denomination: PhantomData<D>,
+}
+
+impl<D: Denomination> TokenAmount<D> {
+    pub const ZERO: Self = Self::from_base_units(0);
+    pub const MAX: Self = Self::from_base_units(u64::MAX);
+
+    /// Number of base units in one token.
+    pub const BASE_UNITS_PER_TOKEN: u64 = 10u64.pow(D::DECIMALS as u32);
+
+    pub const fn from_base_units(base_units: u64) -> Self {
+        Self {
+            base_units,
+            denomination: PhantomData,
+        }
+    }
+
+    /// An amount of whole tokens, or `None` if it overflows.
+    pub const fn from_tokens(tokens: u64) -> Option<Self> {
+        match tokens.checked_mul(Self::BASE_UNITS_PER_TOKEN) {
+            Some(base_units) => Some(Self::from_base_units(base_units)),
+            None => None,
+        }
+    }
+
+    pub const fn base_units(&self) -> u64 {
+        self.base_units
+    }
+
+    /// The whole tokens of the amount.
+    pub const fn whole_tokens(&self) -> u64 {
+        self.base_units / Self::BASE_UNITS_PER_TOKEN
+    }
+
+    /// The base units of the amount that do not make up a whole token.
+    pub const fn fractional_base_units(&self) -> u64 {
+        self.base_units % Self::BASE_UNITS_PER_TOKEN
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.base_units
+            .checked_add(other.base_units)
+            .map(Self::from_base_units)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.base_units
+            .checked_sub(other.base_units)
+            .map(Self::from_base_units)
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Self> {
+        self.base_units
+            .checked_mul(factor)
+            .map(Self::from_base_units)
+    }
+
+    /// Divides the amount, rounding down, or returns `None` if `divisor` is
+    /// zero.
+    pub fn checked_div(self, divisor: u64) -> Option<Self> {
+        self.base_units
+            .checked_div(divisor)
+            .map(Self::from_base_units)
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self::from_base_units(self.base_units.saturating_add(other.base_units))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::from_base_units(self.base_units.saturating_sub(other.base_units))
+    }
+
+    /// Formats the amount followed by the ticker of the token, e.g.
+    /// `1.5 SOL`.
+    pub fn to_string_with_ticker(&self) -> String {
+        format!("{self} {}", D::TICKER)
+    }
+}
+
+/// Reasons a decimal amount can not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAmountError {
+    Empty,
+    /// A character that is neither a digit, a `_` separator, nor the
+    /// decimal point, at a byte position of the input.
+    InvalidCharacter {
+        position: usize,
+        character: char,
+    },
+    /// A `_` separator not between two digits.
+    MisplacedSeparator {
+        position: usize,
+    },
+    /// No digits before or after the decimal point.
+    MissingDigits {
+        position: usize,
+    },
+    /// More non-zero decimals than the token has.
+    TooManyDecimals {
+        decimals: u8,
+    },
+    /// The amount does not fit in a `u64` of base units.
+    Overflow,
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("amount is empty"),
+            Self::InvalidCharacter {
+                position,
+                character,
+            } => write!(f, "invalid character {character:?} at position {position}"),
+            Self::MisplacedSeparator { position } => {
+                write!(f, "separator at position {position} is not between digits")
+            }
+            Self::MissingDigits { position } => {
+                write!(f, "missing digits at position {position}")
+            }
+            Self::TooManyDecimals { decimals } => {
+                write!(f, "amount has more than {decimals} decimals")
+            }
+            Self::Overflow => f.write_str("amount is too large"),
+        }
+    }
+}
+
+/// Reads the digits of `part`, which starts at byte `offset` of the input,
+/// skipping `_` separators.
+fn digits(part: &str, offset: usize) -> Result<Vec<u8>, ParseAmountError> {
+    let bytes = part.as_bytes();
+    let mut digits = Vec::with_capacity(bytes.len());
+    for (index, character) in part.char_indices() {
+        let position = offset + index;
+        match character {
+            '0'..='9' => digits.push(character as u8 - b'0'),
+            '_' => {
+                let between_digits = index > 0
+                    && bytes[index - 1].is_ascii_digit()
+                    && bytes.get(index + 1).is_some_and(u8::is_ascii_digit);
+                if !between_digits {
+                    return Err(ParseAmountError::MisplacedSeparator { position });
+                }
+            }
+            _ => {
+                return Err(ParseAmountError::InvalidCharacter {
+                    position,
+                    character,
+                })
+            }
+        }
+    }
+    if digits.is_empty() {
+        return Err(ParseAmountError::MissingDigits { position: offset });
+    }
+    Ok(digits)
+}
+
+impl<D: Denomination> FromStr for TokenAmount<D> {
+    type Err = ParseAmountError;
+
+    /// Parses a decimal amount of tokens, such as `1.000000001`, `1_000` or
+    /// `0.5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseAmountError::Empty);
+        }
+        let (whole, fraction) = match s.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (s, None),
+        };
+        let mut base_units = digits(whole, 0)?
+            .into_iter()
+            .try_fold(0u64, |value, digit| {
+                value.checked_mul(10)?.checked_add(u64::from(digit))
+            })
+            .and_then(|tokens| tokens.checked_mul(Self::BASE_UNITS_PER_TOKEN))
+            .ok_or(ParseAmountError::Overflow)?;
+
+        if let Some(fraction) = fraction {
+            let fraction = digits(fraction, whole.len() + 1)?;
+            let decimals = usize::from(D::DECIMALS);
+            if fraction.iter().skip(decimals).any(|digit| *digit != 0) {
+                return Err(ParseAmountError::TooManyDecimals {
+                    decimals: D::DECIMALS,
+                });
+            }
+            let fractional_base_units = (0..decimals).fold(0u64, |value, index| {
+                value * 10 + u64::from(fraction.get(index).copied().unwrap_or(0))
+            });
+            base_units = base_units
+                .checked_add(fractional_base_units)
+                .ok_or(ParseAmountError::Overflow)?;
+        }
+        Ok(Self::from_base_units(base_units))
+    }
+}
+
+impl<D: Denomination> fmt::Display for TokenAmount<D> {
+    /// Formats the amount in tokens, without trailing zeros.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fraction = self.fractional_base_units();
+        if fraction == 0 {
+            return write!(f, "{}", self.whole_tokens());
+        }
+        let fraction = format!("{:0width$}", fraction, width = usize::from(D::DECIMALS));
+        write!(
+            f,
+            "{}.{}",
+            self.whole_tokens(),
+            fraction.trim_end_matches('0')
+        )
+    }
+}
+
+impl<D: Denomination> fmt::Debug for TokenAmount<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_with_ticker())
+    }
+}
+
+// Implemented by hand, as deriving would require the denomination to
+// implement the traits too.
+impl<D: Denomination> Clone for TokenAmount<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D: Denomination> Copy for TokenAmount<D> {}
+
+impl<D: Denomination> Default for TokenAmount<D> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<D: Denomination> PartialEq for TokenAmount<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base_units == other.base_units
+    }
+}
+
+impl<D: Denomination> Eq for TokenAmount<D> {}
+
+impl<D: Denomination> PartialOrd for TokenAmount<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D: Denomination> Ord for TokenAmount<D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.base_units.cmp(&other.base_units)
+    }
+}
+
+impl<D: Denomination> Hash for TokenAmount<D> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.base_units.hash(state)
+    }
+}
+
+impl From<crate::Sol> for SolAmount {
+    fn from(sol: crate::Sol) -> Self {
+        Self::from_base_units(sol.0)
+    }
+}
+
+impl From<SolAmount> for crate::Sol {
+    fn from(amount: SolAmount) -> Self {
+        Self(amount.base_units())
+    }
+}
+
+/// Amounts are serialized as decimal strings, which can not lose precision
+/// in formats whose numbers are floats.
+#[cfg(feature = "serde")]
+impl<D: Denomination> serde::Serialize for TokenAmount<D> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, D: Denomination> serde::Deserialize<'de> for TokenAmount<D> {
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        struct AmountVisitor<D>(PhantomData<D>);
+
+        impl<D: Denomination> serde::de::Visitor<'_> for AmountVisitor<D> {
+            type Value = TokenAmount<D>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a decimal amount of {}", D::TICKER)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(AmountVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::LAMPORTS_PER_SOL};
+
+    enum Coarse {}
+
+    impl Denomination for Coarse {
+        const TICKER: &'static str = "CRS";
+        const DECIMALS: u8 = 2;
+    }
+
+    #[test]
+    fn test_parse() {
+        for (input, lamports) in [
+            ("0", 0),
+            ("0.5", 500_000_000),
+            ("1.000000001", 1_000_000_001),
+            ("1_000", 1_000 * LAMPORTS_PER_SOL),
+            ("1_000.000_000_1", 1_000 * LAMPORTS_PER_SOL + 100),
+            ("007.10", 7_100_000_000),
+            ("2.5000000000000", 2_500_000_000),
+            ("18446744073.709551615", u64::MAX),
+        ] {
+            assert_eq!(
+                input.parse::<SolAmount>(),
+                Ok(SolAmount::from_base_units(lamports)),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            "1.25".parse::<TokenAmount<Coarse>>(),
+            Ok(TokenAmount::from_base_units(125))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, error) in [
+            ("", ParseAmountError::Empty),
+            (
+                "-1",
+                ParseAmountError::InvalidCharacter {
+                    position: 0,
+                    character: '-',
+                },
+            ),
+            (
+                "1.5 SOL",
+                ParseAmountError::InvalidCharacter {
+                    position: 3,
+                    character: ' ',
+                },
+            ),
+            (
+                "1.2.3",
+                ParseAmountError::InvalidCharacter {
+                    position: 3,
+                    character: '.',
+                },
+            ),
+            ("_1", ParseAmountError::MisplacedSeparator { position: 0 }),
+            ("1__0", ParseAmountError::MisplacedSeparator { position: 1 }),
+            ("1_.5", ParseAmountError::MisplacedSeparator { position: 1 }),
+            ("1._5", ParseAmountError::MisplacedSeparator { position: 2 }),
+            (".5", ParseAmountError::MissingDigits { position: 0 }),
+            ("5.", ParseAmountError::MissingDigits { position: 2 }),
+            (
+                "0.0000000001",
+                ParseAmountError::TooManyDecimals { decimals: 9 },
+            ),
+            ("18446744073.709551616", ParseAmountError::Overflow),
+            ("18446744074", ParseAmountError::Overflow),
+            ("99999999999999999999", ParseAmountError::Overflow),
+        ] {
+            assert_eq!(input.parse::<SolAmount>(), Err(error), "{input}");
+        }
+        assert_eq!(
+            "1.255".parse::<TokenAmount<Coarse>>(),
+            Err(ParseAmountError::TooManyDecimals { decimals: 2 })
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        for (lamports, display) in [
+            (0, "0"),
+            (1, "0.000000001"),
+            (500_000_000, "0.5"),
+            (1_000 * LAMPORTS_PER_SOL, "1000"),
+            (u64::MAX, "18446744073.709551615"),
+        ] {
+            let amount = SolAmount::from_base_units(lamports);
+            assert_eq!(amount.to_string(), display);
+            assert_eq!(display.parse::<SolAmount>(), Ok(amount));
+        }
+        let amount = TokenAmount::<Coarse>::from_base_units(1_050);
+        assert_eq!(amount.to_string_with_ticker(), "10.5 CRS");
+        assert_eq!(format!("{amount:?}"), "10.5 CRS");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let one = SolAmount::from_tokens(1).unwrap();
+        let half: SolAmount = "0.5".parse().unwrap();
+        assert_eq!(one.checked_add(half), "1.5".parse().ok());
+        assert_eq!(one.checked_sub(half), Some(half));
+        assert_eq!(half.checked_sub(one), None);
+        assert_eq!(half.checked_mul(3), "1.5".parse().ok());
+        assert_eq!(
+            one.checked_div(3),
+            Some(SolAmount::from_base_units(333_333_333))
+        );
+        assert_eq!(one.checked_div(0), None);
+        assert_eq!(SolAmount::MAX.checked_add(one), None);
+        assert_eq!(SolAmount::MAX.saturating_add(one), SolAmount::MAX);
+        assert_eq!(half.saturating_sub(one), SolAmount::ZERO);
+        assert_eq!(SolAmount::from_tokens(u64::MAX), None);
+        assert_eq!(half.whole_tokens(), 0);
+        assert_eq!(half.fractional_base_units(), 500_000_000);
+        assert!(half < one);
+        assert_eq!(crate::Sol::from(one).0, LAMPORTS_PER_SOL);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let amount: SolAmount = "1.000000001".parse().unwrap();
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "\"1.000000001\"");
+        assert_eq!(serde_json::from_str::<SolAmount>(&json).unwrap(), amount);
+        let error = serde_json::from_str::<SolAmount>("\"1.5.0\"").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("invalid character '.' at position 3"));
+        assert!(serde_json::from_str::<SolAmount>("1").is_err());
+    }
+}
@@ -1,7 +1,11 @@
 //! Definitions for the native SOL token and its fractional lamports.
 
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![allow(clippy::arithmetic_side_effects)]
 
+pub mod amount;
+pub use amount::{Denomination, Native, ParseAmountError, SolAmount, TokenAmount};
+
 /// There are 10^9 lamports in one SOL
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 const LAMPORTS_PER_SOL_F64: f64 = LAMPORTS_PER_SOL as f64;
@@ -0,0 +1,439 @@
+//! A small-footprint heap allocator that reuses freed memory.
+
+use {
+    crate::{HEAP_LENGTH, HEAP_START_ADDRESS},
+    std::{
+        alloc::{GlobalAlloc, Layout},
+        mem::size_of,
+        ptr::{self, null_mut},
+    },
+};
+
+/// Smallest heap frame a program can run with, the default heap size.
+pub const MIN_HEAP_FRAME_BYTES: usize = HEAP_LENGTH;
+/// Largest heap frame a program can request with
+/// `ComputeBudgetInstruction::request_heap_frame`.
+pub const MAX_HEAP_FRAME_BYTES: usize = 256 * 1024;
+/// Requested heap frames must be a multiple of this many bytes.
+pub const HEAP_FRAME_BYTES_GRANULARITY: usize = 1024;
+
+/// Blocks are handed out in multiples of this size, which is also the
+/// minimum alignment of every block, so that a freed block can always hold a
+/// [`FreeBlock`].
+const BLOCK_ALIGN: usize = 2 * size_of::<usize>();
+
+/// Size of the bookkeeping stored at the start of the heap.
+const HEADER_LEN: usize = align_up(size_of::<Header>(), BLOCK_ALIGN);
+
+/// Heap usage statistics of a [`FreeListAllocator`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes currently allocated, after rounding to the block size.
+    pub in_use: usize,
+    /// Largest value `in_use` has reached.
+    pub peak_in_use: usize,
+    /// Bytes of the heap touched so far, including the allocator's own
+    /// bookkeeping.
+    pub high_water_mark: usize,
+    /// Number of successful allocations.
+    pub allocations: usize,
+    /// Number of deallocations.
+    pub deallocations: usize,
+    /// Number of allocations that failed because the heap was exhausted.
+    pub failed_allocations: usize,
+}
+
+impl HeapStats {
+    /// Returns the smallest heap frame size accepted by
+    /// `ComputeBudgetInstruction::request_heap_frame` that covers the heap
+    /// touched so far.
+    ///
+    /// Returns `None` if the heap grew beyond [`MAX_HEAP_FRAME_BYTES`].
+    pub fn required_heap_frame_bytes(&self) -> Option<usize> {
+        let bytes = self
+            .high_water_mark
+            .checked_next_multiple_of(HEAP_FRAME_BYTES_GRANULARITY)?
+            .max(MIN_HEAP_FRAME_BYTES);
+        (bytes <= MAX_HEAP_FRAME_BYTES).then_some(bytes)
+    }
+}
+
+/// A freed block, stored in the freed memory itself.
+#[repr(C)]
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+/// Allocator state, stored at the start of the heap.
+///
+/// The program heap is zero-initialized, so a zero `top` marks a heap that
+/// has not been used yet.
+#[repr(C)]
+struct Header {
+    /// Address of the first byte never handed out.
+    top: usize,
+    /// Free blocks below `top`, sorted by address with no two adjacent.
+    free: *mut FreeBlock,
+    stats: HeapStats,
+}
+
+/// Rounds `addr` up to a multiple of `align`, a power of two.
+///
+/// Integer arithmetic here is safe when operating on the prescribed
+/// `HEAP_START_ADDRESS` and heap frame sizes. Any other use may overflow and
+/// is thus unsupported and at one's own risk.
+#[allow(clippy::arithmetic_side_effects)]
+const fn align_up(addr: usize, align: usize) -> usize {
+    (addr + (align - 1)) & !(align - 1)
+}
+
+/// A first-fit free-list allocator for the program heap.
+///
+/// Unlike [`BumpAllocator`], freed memory is returned to a free list and
+/// reused by later allocations, and adjacent free blocks are merged. This
+/// lets programs that repeatedly build and drop large values, such as when
+/// deserializing accounts, stay within a small heap.
+///
+/// All bookkeeping lives in the heap itself, so the allocator can be used as
+/// a `static` [global allocator]; see [`custom_heap_default`]. It is meant
+/// for the single-threaded program runtime and must not be shared between
+/// threads.
+///
+/// [`BumpAllocator`]: crate::BumpAllocator
+/// [global allocator]: https://doc.rust-lang.org/stable/std/alloc/trait.GlobalAlloc.html
+/// [`custom_heap_default`]: crate::custom_heap_default
+pub struct FreeListAllocator {
+    start: usize,
+    len: usize,
+}
+
+impl FreeListAllocator {
+    /// Creates the allocator for the program heap region, for a program that
+    /// requests a heap frame of `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics, at compile time when used in a `static`, if `len` is not a
+    /// heap frame size accepted by
+    /// `ComputeBudgetInstruction::request_heap_frame`.
+    pub const fn with_heap_frame(len: usize) -> Self {
+        assert!(
+            len >= MIN_HEAP_FRAME_BYTES
+                && len <= MAX_HEAP_FRAME_BYTES
+                && len % HEAP_FRAME_BYTES_GRANULARITY == 0,
+            "invalid heap frame size"
+        );
+        Self {
+            start: HEAP_START_ADDRESS as usize,
+            len,
+        }
+    }
+
+    /// Creates the allocator tied to a provided slice.
+    ///
+    /// # Safety
+    /// As long as the allocator or any of its allocations are alive,
+    /// writing into or deallocating the arena will cause UB.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub unsafe fn new(arena: &mut [u8]) -> Self {
+        let offset = arena.as_ptr().align_offset(BLOCK_ALIGN);
+        debug_assert!(
+            arena.len() >= offset + HEADER_LEN,
+            "Arena should be large enough to hold the allocator header"
+        );
+        let start = arena.as_mut_ptr().add(offset);
+        ptr::write_bytes(start, 0, HEADER_LEN);
+        Self {
+            start: start as usize,
+            len: arena.len() - offset,
+        }
+    }
+
+    /// Returns the heap usage statistics.
+    ///
+    /// # Safety
+    /// The statistics are read from the heap itself, which must be mapped:
+    /// the program heap for an allocator created with
+    /// [`with_heap_frame`](Self::with_heap_frame), which only exists in the
+    /// program runtime, or the arena passed to [`new`](Self::new).
+    pub unsafe fn stats(&self) -> HeapStats {
+        (*(self.start as *const Header)).stats
+    }
+
+    /// Returns the header, initializing it on first use.
+    #[allow(clippy::arithmetic_side_effects, clippy::mut_from_ref)]
+    unsafe fn header(&self) -> &mut Header {
+        let header = &mut *(self.start as *mut Header);
+        if header.top == 0 {
+            header.top = self.start + HEADER_LEN;
+            header.free = null_mut();
+            header.stats = HeapStats {
+                high_water_mark: HEADER_LEN,
+                ..HeapStats::default()
+            };
+        }
+        header
+    }
+
+    fn block_size(layout: Layout) -> Option<usize> {
+        layout.size().max(1).checked_next_multiple_of(BLOCK_ALIGN)
+    }
+
+    /// Returns `[start, start + size)` to the free list, merging it with
+    /// adjacent free blocks and giving it back to the unused heap if it ends
+    /// at `top`.
+    #[allow(clippy::arithmetic_side_effects)]
+    unsafe fn release(header: &mut Header, start: usize, size: usize) {
+        let mut start = start;
+        let mut end = start + size;
+
+        // Find the link to the first block after `start`, and the link to
+        // the block before it.
+        let mut prev_link: *mut *mut FreeBlock = null_mut();
+        let mut link: *mut *mut FreeBlock = &mut header.free;
+        while !(*link).is_null() && ((*link) as usize) < start {
+            prev_link = link;
+            link = &mut (**link).next;
+        }
+
+        let mut next = *link;
+        if !next.is_null() && next as usize == end {
+            end += (*next).size;
+            next = (*next).next;
+        }
+        let mut slot = link;
+        if !prev_link.is_null() {
+            let prev = *prev_link;
+            if prev as usize + (*prev).size == start {
+                start = prev as usize;
+                slot = prev_link;
+            }
+        }
+
+        if next.is_null() && end == header.top {
+            header.top = start;
+            *slot = null_mut();
+        } else {
+            let block = start as *mut FreeBlock;
+            (*block).size = end - start;
+            (*block).next = next;
+            *slot = block;
+        }
+    }
+
+    /// Carves `size` bytes aligned to `align` out of the first free block
+    /// large enough to hold them.
+    #[allow(clippy::arithmetic_side_effects)]
+    unsafe fn alloc_from_free_list(header: &mut Header, size: usize, align: usize) -> *mut u8 {
+        let mut link: *mut *mut FreeBlock = &mut header.free;
+        while !(*link).is_null() {
+            let block = *link;
+            let block_start = block as usize;
+            let block_end = block_start + (*block).size;
+            let start = align_up(block_start, align);
+            if start <= block_end && block_end - start >= size {
+                *link = (*block).next;
+                if start > block_start {
+                    Self::release(header, block_start, start - block_start);
+                }
+                if block_end > start + size {
+                    Self::release(header, start + size, block_end - start - size);
+                }
+                return start as *mut u8;
+            }
+            link = &mut (*block).next;
+        }
+        null_mut()
+    }
+}
+
+/// Integer arithmetic in this global allocator implementation is safe when
+/// operating on the prescribed `HEAP_START_ADDRESS` and heap frame sizes. Any
+/// other use may overflow and is thus unsupported and at one's own risk.
+#[allow(clippy::arithmetic_side_effects)]
+unsafe impl GlobalAlloc for FreeListAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let header = self.header();
+        let align = layout.align().max(BLOCK_ALIGN);
+        let Some(size) = Self::block_size(layout) else {
+            header.stats.failed_allocations += 1;
+            return null_mut();
+        };
+
+        let mut ptr = Self::alloc_from_free_list(header, size, align);
+        if ptr.is_null() {
+            let top = header.top;
+            let start = align_up(top, align);
+            let end = self.start + self.len;
+            if start > end || end - start < size {
+                header.stats.failed_allocations += 1;
+                return null_mut();
+            }
+            header.top = start + size;
+            if start > top {
+                Self::release(header, top, start - top);
+            }
+            header.stats.high_water_mark =
+                header.stats.high_water_mark.max(start + size - self.start);
+            ptr = start as *mut u8;
+        }
+
+        let stats = &mut header.stats;
+        stats.in_use += size;
+        stats.peak_in_use = stats.peak_in_use.max(stats.in_use);
+        stats.allocations += 1;
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let header = self.header();
+        // `layout` was accepted by `alloc`, so its block size fits.
+        let size = Self::block_size(layout).unwrap_or_default();
+        Self::release(header, ptr as usize, size);
+        header.stats.in_use -= size;
+        header.stats.deallocations += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(16))]
+    struct Arena<const N: usize>([u8; N]);
+
+    fn layout(size: usize, align: usize) -> Layout {
+        Layout::from_size_align(size, align).unwrap()
+    }
+
+    #[test]
+    fn test_reuses_freed_blocks() {
+        let mut arena = Arena([0u8; 1024]);
+        let allocator = unsafe { FreeListAllocator::new(&mut arena.0) };
+        let heap_end = arena.0.as_ptr() as usize + arena.0.len();
+
+        unsafe {
+            let a = allocator.alloc(layout(100, 1));
+            let b = allocator.alloc(layout(100, 8));
+            assert!(!a.is_null() && !b.is_null());
+            assert_eq!(b as usize - a as usize, 112);
+            assert!(b as usize + 112 <= heap_end);
+
+            // A freed block is reused by an allocation that fits in it
+            allocator.dealloc(a, layout(100, 1));
+            let c = allocator.alloc(layout(48, 4));
+            assert_eq!(c, a);
+            // and the remainder of the block is reused as well
+            let d = allocator.alloc(layout(64, 16));
+            assert_eq!(d as usize, a as usize + 48);
+
+            allocator.dealloc(b, layout(100, 8));
+            allocator.dealloc(c, layout(48, 4));
+            allocator.dealloc(d, layout(64, 16));
+        }
+
+        let stats = unsafe { allocator.stats() };
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.peak_in_use, 224);
+        assert_eq!(stats.high_water_mark, HEADER_LEN + 224);
+        assert_eq!(stats.allocations, 4);
+        assert_eq!(stats.deallocations, 4);
+        assert_eq!(stats.failed_allocations, 0);
+    }
+
+    #[test]
+    fn test_merges_free_blocks() {
+        let mut arena = Arena([0u8; 512]);
+        let allocator = unsafe { FreeListAllocator::new(&mut arena.0) };
+        let capacity = 512 - HEADER_LEN;
+
+        unsafe {
+            // Fill the heap, then free it out of order
+            let blocks = (0..capacity / 64)
+                .map(|_| allocator.alloc(layout(64, 8)))
+                .collect::<Vec<_>>();
+            assert!(blocks.iter().all(|ptr| !ptr.is_null()));
+            assert!(allocator.alloc(layout(1, 1)).is_null());
+            for ptr in blocks
+                .iter()
+                .step_by(2)
+                .chain(blocks.iter().skip(1).step_by(2))
+            {
+                allocator.dealloc(*ptr, layout(64, 8));
+            }
+
+            // The whole heap is available again as a single block
+            let ptr = allocator.alloc(layout(capacity, 8));
+            assert_eq!(ptr, blocks[0]);
+            allocator.dealloc(ptr, layout(capacity, 8));
+        }
+
+        let stats = unsafe { allocator.stats() };
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.peak_in_use, capacity);
+        assert_eq!(stats.failed_allocations, 1);
+        assert_eq!(
+            stats.required_heap_frame_bytes(),
+            Some(MIN_HEAP_FRAME_BYTES)
+        );
+    }
+
+    #[test]
+    fn test_alignment() {
+        let mut arena = Arena([0u8; 1024]);
+        let allocator = unsafe { FreeListAllocator::new(&mut arena.0) };
+
+        unsafe {
+            let small = allocator.alloc(layout(1, 1));
+            for align in [1, 2, 4, 8, 16, 32, 64, 128] {
+                let ptr = allocator.alloc(layout(1, align));
+                assert!(!ptr.is_null());
+                assert_eq!(0, ptr.align_offset(align));
+                allocator.dealloc(ptr, layout(1, align));
+            }
+            allocator.dealloc(small, layout(1, 1));
+        }
+        assert_eq!(unsafe { allocator.stats() }.in_use, 0);
+
+        // Padding skipped to align a block is handed out again
+        unsafe {
+            let ptr = allocator.alloc(layout(16, 1));
+            assert_eq!(ptr as usize, allocator.start + HEADER_LEN);
+        }
+    }
+
+    #[test]
+    fn test_required_heap_frame_bytes() {
+        let stats = |high_water_mark| HeapStats {
+            high_water_mark,
+            ..HeapStats::default()
+        };
+        assert_eq!(
+            stats(HEADER_LEN).required_heap_frame_bytes(),
+            Some(MIN_HEAP_FRAME_BYTES)
+        );
+        assert_eq!(
+            stats(MIN_HEAP_FRAME_BYTES + 1).required_heap_frame_bytes(),
+            Some(MIN_HEAP_FRAME_BYTES + HEAP_FRAME_BYTES_GRANULARITY)
+        );
+        assert_eq!(
+            stats(MAX_HEAP_FRAME_BYTES).required_heap_frame_bytes(),
+            Some(MAX_HEAP_FRAME_BYTES)
+        );
+        assert_eq!(
+            stats(MAX_HEAP_FRAME_BYTES + 1).required_heap_frame_bytes(),
+            None
+        );
+
+        let allocator = FreeListAllocator::with_heap_frame(64 * 1024);
+        assert_eq!(allocator.len, 64 * 1024);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid heap frame size")]
+    fn test_invalid_heap_frame() {
+        FreeListAllocator::with_heap_frame(32 * 1024 + 1);
+    }
+}
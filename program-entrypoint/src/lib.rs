@@ -5,6 +5,8 @@
 //! [`bpf_loader`]: crate::bpf_loader
 
 extern crate alloc;
+mod free_list_allocator;
+
 use {
     alloc::vec::Vec,
     solana_account_info::AccountInfo,
@@ -21,9 +23,15 @@ use {
 // need to re-export msg for custom_heap_default macro, `AccountInfo` and `Pubkey` for
 // entrypoint_no_alloc macro
 pub use {
+    free_list_allocator::{
+        FreeListAllocator, HeapStats, HEAP_FRAME_BYTES_GRANULARITY, MAX_HEAP_FRAME_BYTES,
+        MIN_HEAP_FRAME_BYTES,
+    },
     solana_account_info::AccountInfo as __AccountInfo,
-    solana_account_info::MAX_PERMITTED_DATA_INCREASE, solana_msg::msg as __msg,
-    solana_program_error::ProgramResult, solana_pubkey::Pubkey as __Pubkey,
+    solana_account_info::MAX_PERMITTED_DATA_INCREASE,
+    solana_msg::msg as __msg,
+    solana_program_error::ProgramResult,
+    solana_pubkey::Pubkey as __Pubkey,
 };
 
 /// User implemented function to process an instruction
@@ -218,6 +226,28 @@ macro_rules! entrypoint_no_alloc {
 ///
 /// [global allocator]: https://doc.rust-lang.org/stable/std/alloc/trait.GlobalAlloc.html
 ///
+/// # Free-list allocator
+///
+/// The default [`BumpAllocator`] never frees memory. Programs that allocate
+/// and drop large values can instead use the [`FreeListAllocator`], which
+/// reuses freed blocks:
+///
+/// ```ignore
+/// solana_program_entrypoint::custom_heap_default!(free_list);
+/// ```
+///
+/// A program that requests a larger heap frame with
+/// `ComputeBudgetInstruction::request_heap_frame` passes the same size, which
+/// must be a multiple of [`HEAP_FRAME_BYTES_GRANULARITY`] between
+/// [`MIN_HEAP_FRAME_BYTES`] and [`MAX_HEAP_FRAME_BYTES`]:
+///
+/// ```ignore
+/// solana_program_entrypoint::custom_heap_default!(free_list, 64 * 1024);
+/// ```
+///
+/// Every transaction invoking the program must then request at least that
+/// heap frame. These variants ignore the `custom-heap` feature, so that they
+/// can be combined with [`entrypoint!`] in a crate that enables it.
 #[macro_export]
 macro_rules! custom_heap_default {
     () => {
@@ -228,6 +258,15 @@ macro_rules! custom_heap_default {
             len: $crate::HEAP_LENGTH,
         };
     };
+    (free_list) => {
+        $crate::custom_heap_default!(free_list, $crate::HEAP_LENGTH);
+    };
+    (free_list, $heap_frame_bytes:expr) => {
+        #[cfg(target_os = "solana")]
+        #[global_allocator]
+        static A: $crate::FreeListAllocator =
+            $crate::FreeListAllocator::with_heap_frame($heap_frame_bytes);
+    };
 }
 
 /// Define the default global panic handler.
@@ -0,0 +1,287 @@
+//! Derive macro for `solana_program_error::CustomProgramError`.
+//!
+//! Assigns every variant of a fieldless error enum a stable
+//! `ProgramError::Custom` code and implements the conversions, logging and
+//! decoding traits around it.
+
+extern crate proc_macro;
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::{Span, TokenStream as TokenStream2},
+    quote::quote,
+    std::collections::HashMap,
+    syn::{
+        parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, ExprLit,
+        Fields, Lit, LitStr, Path, Result,
+    },
+};
+
+/// Derives `CustomProgramError`, `From<Self> for ProgramError`,
+/// `TryFrom<u32>`, `ToStr`, `PrintProgramError`, `DecodeError<Self>` and
+/// `FromPrimitive` for a fieldless error enum.
+///
+/// Codes follow the enum's discriminants, so an explicit discriminant resets
+/// the numbering for the variants that follow it, and are shifted by an
+/// optional namespace offset. `FromPrimitive` decodes those codes, offset
+/// included, so that `DecodeError::decode_custom_error_to_enum` and
+/// `ProgramError::print` resolve `ProgramError::Custom` codes; do not also
+/// derive it with `num_derive`.
+///
+/// # Attributes
+///
+/// On the enum:
+/// * `#[program_error(offset = 6000)]` - added to every variant's code.
+/// * `#[program_error(crate = "solana_program::program_error")]` - path of
+///   the `solana-program-error` crate, if it is not a direct dependency.
+///
+/// On variants:
+/// * `#[program_error(msg = "...")]` - message used by `ToStr` and
+///   `PrintProgramError`, defaults to the variant name.
+///
+/// # Example
+///
+/// ```ignore
+/// use solana_program_error::{CustomProgramError, ProgramError};
+///
+/// #[derive(Debug, CustomProgramError)]
+/// #[program_error(offset = 100)]
+/// enum VaultError {
+///     #[program_error(msg = "Vault is frozen")]
+///     Frozen,
+///     InsufficientShares = 10,
+///     Overflow,
+/// }
+///
+/// assert_eq!(ProgramError::from(VaultError::Frozen), ProgramError::Custom(100));
+/// assert_eq!(ProgramError::from(VaultError::Overflow), ProgramError::Custom(111));
+/// ```
+#[proc_macro_derive(CustomProgramError, attributes(program_error))]
+pub fn derive_custom_program_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct EnumArgs {
+    krate: Path,
+    offset: u32,
+}
+
+struct Variant {
+    ident: syn::Ident,
+    code: u32,
+    msg: LitStr,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "CustomProgramError cannot be derived for generic types",
+        ));
+    }
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "CustomProgramError can only be derived for enums",
+        ));
+    };
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(
+            name,
+            "CustomProgramError cannot be derived for empty enums",
+        ));
+    }
+
+    let EnumArgs { krate, offset } = parse_enum_args(&input.attrs)?;
+
+    let mut variants = Vec::with_capacity(data.variants.len());
+    let mut seen = HashMap::new();
+    let mut next_discriminant = Some(0u32);
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                &variant.fields,
+                "CustomProgramError variants cannot have fields",
+            ));
+        }
+        let discriminant = match &variant.discriminant {
+            Some((_, expr)) => parse_discriminant(expr)?,
+            None => next_discriminant
+                .ok_or_else(|| Error::new_spanned(variant, "discriminant overflows u32"))?,
+        };
+        next_discriminant = discriminant.checked_add(1);
+        let code = offset.checked_add(discriminant).ok_or_else(|| {
+            Error::new_spanned(variant, "error code overflows u32 after applying offset")
+        })?;
+        if let Some(previous) = seen.insert(code, variant.ident.clone()) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("error code {code} is already used by `{previous}`"),
+            ));
+        }
+        let msg = parse_variant_msg(&variant.attrs)?
+            .unwrap_or_else(|| LitStr::new(&variant.ident.to_string(), variant.ident.span()));
+        variants.push(Variant {
+            ident: variant.ident.clone(),
+            code,
+            msg,
+        });
+    }
+
+    let name_str = name.to_string();
+    let entries = variants.iter().map(|Variant { ident, code, msg }| {
+        let ident_str = ident.to_string();
+        quote! {
+            #krate::CustomErrorEntry {
+                code: #code,
+                name: #ident_str,
+                msg: #msg,
+            }
+        }
+    });
+    let code_arms = variants
+        .iter()
+        .map(|Variant { ident, code, .. }| quote! { Self::#ident => #code });
+    let from_code_arms = variants
+        .iter()
+        .map(|Variant { ident, code, .. }| quote! { #code => Ok(Self::#ident) });
+    let to_str_arms = variants
+        .iter()
+        .map(|Variant { ident, msg, .. }| quote! { Self::#ident => concat!("Error: ", #msg) });
+
+    Ok(quote! {
+        impl #krate::CustomProgramError for #name {
+            const NAME: &'static str = #name_str;
+            const ERRORS: &'static [#krate::CustomErrorEntry] = &[#(#entries),*];
+
+            fn code(&self) -> u32 {
+                match self {
+                    #(#code_arms,)*
+                }
+            }
+        }
+
+        impl ::core::convert::From<#name> for #krate::ProgramError {
+            fn from(error: #name) -> Self {
+                #krate::ProgramError::Custom(#krate::CustomProgramError::code(&error))
+            }
+        }
+
+        impl ::core::convert::TryFrom<u32> for #name {
+            type Error = u32;
+
+            fn try_from(code: u32) -> ::core::result::Result<Self, u32> {
+                match code {
+                    #(#from_code_arms,)*
+                    _ => Err(code),
+                }
+            }
+        }
+
+        impl #krate::ToStr for #name {
+            fn to_str<E>(&self) -> &'static str
+            where
+                E: 'static + #krate::ToStr + ::core::convert::TryFrom<u32>,
+            {
+                match self {
+                    #(#to_str_arms,)*
+                }
+            }
+        }
+
+        #[allow(deprecated)]
+        impl #krate::PrintProgramError for #name {
+            fn print<E>(&self)
+            where
+                E: 'static
+                    + ::std::error::Error
+                    + #krate::__private::DecodeError<E>
+                    + #krate::PrintProgramError
+                    + #krate::__private::FromPrimitive,
+            {
+                #krate::__private::msg!(#krate::ToStr::to_str::<Self>(self));
+            }
+        }
+
+        #[allow(deprecated)]
+        impl #krate::__private::DecodeError<#name> for #name {
+            fn type_of() -> &'static str {
+                #name_str
+            }
+        }
+
+        impl #krate::__private::FromPrimitive for #name {
+            fn from_i64(n: i64) -> ::core::option::Option<Self> {
+                <u32 as ::core::convert::TryFrom<i64>>::try_from(n)
+                    .ok()
+                    .and_then(|code| <Self as ::core::convert::TryFrom<u32>>::try_from(code).ok())
+            }
+
+            fn from_u64(n: u64) -> ::core::option::Option<Self> {
+                <u32 as ::core::convert::TryFrom<u64>>::try_from(n)
+                    .ok()
+                    .and_then(|code| <Self as ::core::convert::TryFrom<u32>>::try_from(code).ok())
+            }
+        }
+    })
+}
+
+fn parse_enum_args(attrs: &[Attribute]) -> Result<EnumArgs> {
+    let mut args = EnumArgs {
+        krate: syn::parse_quote!(::solana_program_error),
+        offset: 0,
+    };
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("program_error"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                let value: Expr = meta.value()?.parse()?;
+                args.offset = parse_discriminant(&value)?;
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                let value: LitStr = meta.value()?.parse()?;
+                args.krate = value.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `offset` or `crate`"))
+            }
+        })?;
+    }
+    Ok(args)
+}
+
+fn parse_variant_msg(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+    let mut msg = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("program_error"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("msg") {
+                msg = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `msg`"))
+            }
+        })?;
+    }
+    Ok(msg)
+}
+
+fn parse_discriminant(expr: &Expr) -> Result<u32> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse(),
+        _ => Err(Error::new(
+            expr.span(),
+            "expected an integer literal that fits in a u32",
+        )),
+    }
+}
@@ -240,6 +240,104 @@ impl ToStr for ProgramError {
     }
 }
 
+/// One entry of a [`CustomProgramError`] error table.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CustomErrorEntry {
+    /// Code carried by [`ProgramError::Custom`] and [`InstructionError::Custom`].
+    pub code: u32,
+    /// Name of the enum variant.
+    pub name: &'static str,
+    /// Human-readable message.
+    pub msg: &'static str,
+}
+
+/// A program-specific error enum with stable [`ProgramError::Custom`] codes.
+///
+/// This is usually implemented with `#[derive(CustomProgramError)]`, available
+/// with the `derive` feature, which also implements `From<Self>` for
+/// [`ProgramError`], `TryFrom<u32>`, [`ToStr`], and the deprecated
+/// [`PrintProgramError`] and `DecodeError`. Codes can be offset to give
+/// each program its own namespace:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use solana_program_error::{CustomProgramError, ProgramError};
+///
+/// #[derive(Debug, PartialEq, CustomProgramError)]
+/// #[program_error(offset = 6000)]
+/// enum VaultError {
+///     #[program_error(msg = "Vault is frozen")]
+///     Frozen,
+///     InsufficientShares = 10,
+/// }
+///
+/// let error = ProgramError::from(VaultError::InsufficientShares);
+/// assert_eq!(error, ProgramError::Custom(6010));
+/// assert_eq!(
+///     VaultError::from_program_error(&error),
+///     Some(VaultError::InsufficientShares)
+/// );
+/// assert_eq!(VaultError::ERRORS[0].msg, "Vault is frozen");
+/// # }
+/// ```
+pub trait CustomProgramError: ToStr + TryFrom<u32> + 'static {
+    /// Name of the error type.
+    const NAME: &'static str;
+
+    /// Every error code of this type, in declaration order.
+    ///
+    /// Clients can use this table to describe `Custom` codes returned by the
+    /// program without depending on the program crate at runtime.
+    const ERRORS: &'static [CustomErrorEntry];
+
+    /// Returns the `Custom` code of this error.
+    fn code(&self) -> u32;
+
+    /// Decodes an error from its `Custom` code.
+    fn from_code(code: u32) -> Option<Self> {
+        Self::try_from(code).ok()
+    }
+
+    /// Decodes an error from [`ProgramError::Custom`].
+    fn from_program_error(error: &ProgramError) -> Option<Self> {
+        match error {
+            ProgramError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+
+    /// Decodes an error from [`InstructionError::Custom`].
+    fn from_instruction_error(error: &InstructionError) -> Option<Self> {
+        match error {
+            InstructionError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+
+    /// Returns the table entry of this error.
+    fn entry(&self) -> Option<&'static CustomErrorEntry> {
+        let code = self.code();
+        Self::ERRORS.iter().find(|entry| entry.code == code)
+    }
+
+    /// Logs this error to the program log.
+    fn log(&self) {
+        msg!(self.to_str::<Self>());
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use solana_program_error_macro::CustomProgramError;
+
+// Not public API. Referenced by macro-generated code.
+#[doc(hidden)]
+pub mod __private {
+    #[allow(deprecated)]
+    pub use {num_traits::FromPrimitive, solana_decode_error::DecodeError, solana_msg::msg};
+}
+
 impl From<ProgramError> for u64 {
     fn from(error: ProgramError) -> Self {
         match error {
@@ -379,3 +477,132 @@ impl From<BorshIoError> for ProgramError {
         Self::BorshIoError(format!("{error}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, CustomProgramError)]
+    #[program_error(crate = "crate", offset = 100)]
+    enum TestError {
+        #[program_error(msg = "first error")]
+        First,
+        Second = 10,
+        Third,
+    }
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.to_str::<Self>())
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    #[test]
+    fn test_derive_custom_program_error() {
+        assert_eq!(TestError::NAME, "TestError");
+        assert_eq!(
+            TestError::ERRORS,
+            &[
+                CustomErrorEntry {
+                    code: 100,
+                    name: "First",
+                    msg: "first error",
+                },
+                CustomErrorEntry {
+                    code: 110,
+                    name: "Second",
+                    msg: "Second",
+                },
+                CustomErrorEntry {
+                    code: 111,
+                    name: "Third",
+                    msg: "Third",
+                },
+            ]
+        );
+
+        assert_eq!(
+            ProgramError::from(TestError::First),
+            ProgramError::Custom(100)
+        );
+        assert_eq!(
+            ProgramError::from(TestError::Third),
+            ProgramError::Custom(111)
+        );
+        assert_eq!(TestError::try_from(110), Ok(TestError::Second));
+        assert_eq!(TestError::try_from(101), Err(101));
+
+        assert_eq!(
+            TestError::from_instruction_error(&InstructionError::Custom(111)),
+            Some(TestError::Third)
+        );
+        assert_eq!(
+            TestError::from_instruction_error(&InstructionError::InvalidArgument),
+            None
+        );
+        assert_eq!(
+            TestError::from_program_error(&ProgramError::Custom(100)),
+            Some(TestError::First)
+        );
+        assert_eq!(TestError::Second.entry(), Some(&TestError::ERRORS[1]));
+
+        assert_eq!(TestError::First.to_str::<TestError>(), "Error: first error");
+        assert_eq!(
+            ProgramError::Custom(110).to_str::<TestError>(),
+            "Error: Second"
+        );
+        assert_eq!(
+            ProgramError::Custom(7).to_str::<TestError>(),
+            "Error: Unknown"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_derive_decode_error() {
+        use solana_decode_error::DecodeError;
+
+        assert_eq!(
+            <TestError as DecodeError<TestError>>::type_of(),
+            "TestError"
+        );
+        for entry in TestError::ERRORS {
+            let error = TestError::decode_custom_error_to_enum(entry.code).unwrap();
+            assert_eq!(error.code(), entry.code);
+        }
+        assert_eq!(TestError::decode_custom_error_to_enum(101), None);
+        assert_eq!(TestError::decode_custom_error_to_enum(0), None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_derive_print_program_error() {
+        // `msg!` prints to stdout off-chain, so the test runs itself in a
+        // child process to capture the log.
+        const PRINT_ENV: &str = "SOLANA_PROGRAM_ERROR_TEST_PRINT";
+        if std::env::var_os(PRINT_ENV).is_some() {
+            TestError::Third.print::<TestError>();
+            ProgramError::Custom(100).print::<TestError>();
+            ProgramError::Custom(101).print::<TestError>();
+            return;
+        }
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "tests::test_derive_print_program_error",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(PRINT_ENV, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains("Error: Third\nError: first error\nError: Unknown\n"),
+            "{stdout}"
+        );
+    }
+}
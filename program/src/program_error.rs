@@ -1,3 +1,7 @@
+// Not public API. Referenced by `#[derive(CustomProgramError)]` code when the
+// crate path is `solana_program::program_error`.
+#[doc(hidden)]
+pub use solana_program_error::__private;
 #[allow(deprecated)]
 pub use solana_program_error::PrintProgramError;
 pub use {
@@ -12,5 +16,5 @@ pub use {
         MISSING_REQUIRED_SIGNATURES, NOT_ENOUGH_ACCOUNT_KEYS, UNINITIALIZED_ACCOUNT,
         UNSUPPORTED_SYSVAR,
     },
-    solana_program_error::ProgramError,
+    solana_program_error::{CustomErrorEntry, CustomProgramError, ProgramError, ToStr},
 };
@@ -0,0 +1,294 @@
+//! Checking the rent state transitions of the accounts of a transaction.
+//!
+//! The runtime fails a transaction with
+//! [`TransactionError::InsufficientFundsForRent`] when it leaves a writable
+//! account rent-paying, unless the account was already rent-paying with the
+//! same data size and did not gain lamports. These checks reproduce that
+//! rule, so that clients can tell ahead of time which accounts need a top-up.
+
+use {
+    solana_account::{AccountSharedData, ReadableAccount},
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    solana_sdk_ids::incinerator,
+    solana_transaction_error::TransactionError,
+    std::fmt,
+};
+
+/// The rent state of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RentState {
+    /// The account holds no lamports.
+    Uninitialized,
+    /// The account holds lamports, but fewer than rent exemption requires.
+    RentPaying {
+        lamports: u64,
+        data_size: usize,
+    },
+    RentExempt,
+}
+
+impl RentState {
+    pub fn from_account(account: &impl ReadableAccount, rent: &Rent) -> Self {
+        if account.lamports() == 0 {
+            Self::Uninitialized
+        } else if rent.is_exempt(account.lamports(), account.data().len()) {
+            Self::RentExempt
+        } else {
+            Self::RentPaying {
+                lamports: account.lamports(),
+                data_size: account.data().len(),
+            }
+        }
+    }
+
+    /// Whether the runtime allows an account to go from `pre` to `self`.
+    pub fn transition_allowed_from(&self, pre: &RentState) -> bool {
+        match self {
+            Self::Uninitialized | Self::RentExempt => true,
+            Self::RentPaying {
+                lamports: post_lamports,
+                data_size: post_data_size,
+            } => match pre {
+                Self::Uninitialized | Self::RentExempt => false,
+                Self::RentPaying {
+                    lamports: pre_lamports,
+                    data_size: pre_data_size,
+                } => post_data_size == pre_data_size && post_lamports <= pre_lamports,
+            },
+        }
+    }
+}
+
+/// Checks the transition of the account at `account_index`, with the error
+/// the runtime would fail the transaction with.
+pub fn check_rent_state(
+    pre: &RentState,
+    post: &RentState,
+    address: &Pubkey,
+    account_index: u8,
+) -> Result<(), TransactionError> {
+    if incinerator::check_id(address) || post.transition_allowed_from(pre) {
+        Ok(())
+    } else {
+        Err(TransactionError::InsufficientFundsForRent { account_index })
+    }
+}
+
+/// An account left in a rent state the runtime does not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RentStateViolation {
+    pub account_index: usize,
+    pub address: Pubkey,
+    pub pre: RentState,
+    pub post: RentState,
+    /// Lamports to add to the account after the transaction to make it rent
+    /// exempt.
+    pub top_up: u64,
+}
+
+impl RentStateViolation {
+    /// The error the runtime fails the transaction with, or `None` if the
+    /// account index does not fit the `u8` index of the error, which no
+    /// valid transaction has.
+    pub fn transaction_error(&self) -> Option<TransactionError> {
+        let account_index = u8::try_from(self.account_index).ok()?;
+        Some(TransactionError::InsufficientFundsForRent { account_index })
+    }
+}
+
+/// Accounts passed to [`check_rent_state_transitions`] that do not describe
+/// the same transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RentStateCheckError {
+    /// The accounts at `account_index` before and after the transaction have
+    /// different addresses.
+    AddressMismatch {
+        account_index: usize,
+        pre: Pubkey,
+        post: Pubkey,
+    },
+}
+
+impl fmt::Display for RentStateCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AddressMismatch {
+                account_index,
+                pre,
+                post,
+            } => write!(
+                f,
+                "account {account_index} is {pre} before the transaction but {post} after it"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RentStateCheckError {}
+
+/// Checks the writable accounts of a transaction, given as the accounts
+/// before and after it, at the same indexes. Accounts missing before the
+/// transaction are uninitialized.
+///
+/// Fails if an account has a different address before and after the
+/// transaction.
+pub fn check_rent_state_transitions(
+    rent: &Rent,
+    pre_accounts: &[(Pubkey, AccountSharedData)],
+    post_accounts: &[(Pubkey, AccountSharedData)],
+) -> Result<Vec<RentStateViolation>, RentStateCheckError> {
+    let mut violations = vec![];
+    for (account_index, (address, post_account)) in post_accounts.iter().enumerate() {
+        let pre = match pre_accounts.get(account_index) {
+            Some((pre_address, _)) if pre_address != address => {
+                return Err(RentStateCheckError::AddressMismatch {
+                    account_index,
+                    pre: *pre_address,
+                    post: *address,
+                });
+            }
+            Some((_, pre_account)) => RentState::from_account(pre_account, rent),
+            None => RentState::Uninitialized,
+        };
+        let post = RentState::from_account(post_account, rent);
+        if !incinerator::check_id(address) && !post.transition_allowed_from(&pre) {
+            violations.push(RentStateViolation {
+                account_index,
+                address: *address,
+                pre,
+                post,
+                top_up: rent
+                    .minimum_balance(post_account.data().len())
+                    .saturating_sub(post_account.lamports()),
+            });
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rent_state() {
+        let rent = Rent::default();
+        let exempt = rent.minimum_balance(10);
+        let owner = Pubkey::new_unique();
+        assert_eq!(
+            RentState::from_account(&AccountSharedData::new(0, 10, &owner), &rent),
+            RentState::Uninitialized
+        );
+        assert_eq!(
+            RentState::from_account(&AccountSharedData::new(exempt, 10, &owner), &rent),
+            RentState::RentExempt
+        );
+        assert_eq!(
+            RentState::from_account(&AccountSharedData::new(exempt - 1, 10, &owner), &rent),
+            RentState::RentPaying {
+                lamports: exempt - 1,
+                data_size: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn test_transition_allowed_from() {
+        let paying = |lamports, data_size| RentState::RentPaying {
+            lamports,
+            data_size,
+        };
+        for pre in [
+            RentState::Uninitialized,
+            RentState::RentExempt,
+            paying(10, 1),
+        ] {
+            assert!(RentState::Uninitialized.transition_allowed_from(&pre));
+            assert!(RentState::RentExempt.transition_allowed_from(&pre));
+        }
+        assert!(!paying(10, 1).transition_allowed_from(&RentState::Uninitialized));
+        assert!(!paying(10, 1).transition_allowed_from(&RentState::RentExempt));
+        assert!(paying(10, 1).transition_allowed_from(&paying(10, 1)));
+        assert!(paying(9, 1).transition_allowed_from(&paying(10, 1)));
+        assert!(!paying(11, 1).transition_allowed_from(&paying(10, 1)));
+        assert!(!paying(10, 2).transition_allowed_from(&paying(10, 1)));
+
+        let address = Pubkey::new_unique();
+        assert_eq!(
+            check_rent_state(&RentState::RentExempt, &paying(1, 0), &address, 3),
+            Err(TransactionError::InsufficientFundsForRent { account_index: 3 })
+        );
+        assert_eq!(
+            check_rent_state(&RentState::RentExempt, &paying(1, 0), &incinerator::id(), 3),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_rent_state_transitions() {
+        let rent = Rent::default();
+        let exempt = rent.minimum_balance(0);
+        let owner = Pubkey::new_unique();
+        let account = |lamports| AccountSharedData::new(lamports, 0, &owner);
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let created = Pubkey::new_unique();
+
+        let pre = vec![
+            (payer, account(exempt + 1_000)),
+            (recipient, account(0)),
+            (incinerator::id(), account(0)),
+        ];
+        let post = vec![
+            (payer, account(exempt - 500)),
+            (recipient, account(1_000)),
+            (incinerator::id(), account(500)),
+            (created, account(exempt)),
+        ];
+        assert_eq!(
+            check_rent_state_transitions(&rent, &pre, &post),
+            Ok(vec![
+                RentStateViolation {
+                    account_index: 0,
+                    address: payer,
+                    pre: RentState::RentExempt,
+                    post: RentState::RentPaying {
+                        lamports: exempt - 500,
+                        data_size: 0,
+                    },
+                    top_up: 500,
+                },
+                RentStateViolation {
+                    account_index: 1,
+                    address: recipient,
+                    pre: RentState::Uninitialized,
+                    post: RentState::RentPaying {
+                        lamports: 1_000,
+                        data_size: 0,
+                    },
+                    top_up: exempt - 1_000,
+                },
+            ])
+        );
+        let mut violations = check_rent_state_transitions(&rent, &pre, &post).unwrap();
+        assert_eq!(
+            violations[1].transaction_error(),
+            Some(TransactionError::InsufficientFundsForRent { account_index: 1 })
+        );
+        violations[1].account_index = 256;
+        assert_eq!(violations[1].transaction_error(), None);
+
+        // The accounts must be given in the same order
+        let mut swapped = pre.clone();
+        swapped.swap(0, 1);
+        assert_eq!(
+            check_rent_state_transitions(&rent, &swapped, &post),
+            Err(RentStateCheckError::AddressMismatch {
+                account_index: 0,
+                pre: recipient,
+                post: payer,
+            })
+        );
+    }
+}
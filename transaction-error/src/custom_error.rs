@@ -0,0 +1,333 @@
+//! Client-side decoding of [`InstructionError::Custom`] codes.
+//!
+//! A `Custom` code only has meaning in the context of the program that
+//! returned it. [`CustomErrorRegistry`] maps program ids to decoders so that a
+//! failed transaction can be rendered with the name and message of the
+//! program-specific error.
+//!
+//! Decoders can be built from any enum implementing [`FromPrimitive`] and
+//! [`Display`], which covers enums used with the `DecodeError` trait, or from
+//! the error table of a [`CustomProgramError`].
+//!
+//! The registry returned by [`CustomErrorRegistry::with_builtins`] knows the
+//! System program's errors, including its durable nonce errors, the vote
+//! program's [`VoteError`] and the precompiles' [`PrecompileError`]. The
+//! address lookup table program and the BPF loaders report failures with the
+//! standard [`InstructionError`] variants rather than `Custom` codes, so they
+//! need no entry; their errors are rendered by [`InstructionError`]'s
+//! `Display` implementation.
+//!
+//! [`Display`]: fmt::Display
+//! [`VoteError`]: solana_vote_interface::error::VoteError
+//! [`PrecompileError`]: solana_precompile_error::PrecompileError
+
+use {
+    crate::TransactionError, core::fmt, num_traits::FromPrimitive,
+    solana_instruction::error::InstructionError, solana_program_error::CustomProgramError,
+    solana_pubkey::Pubkey, std::collections::HashMap,
+};
+
+/// A `Custom` error code decoded by a [`CustomErrorRegistry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedCustomError {
+    /// Human-readable name of the program that returned the error.
+    pub program_name: String,
+    /// Name of the program's error type.
+    pub type_name: String,
+    /// Name of the error variant.
+    pub name: String,
+    /// Description of the error.
+    pub msg: String,
+    /// The raw `Custom` code.
+    pub code: u32,
+}
+
+impl fmt::Display for DecodedCustomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}::{} (custom program error: {:#x}): {}",
+            self.program_name, self.type_name, self.name, self.code, self.msg
+        )
+    }
+}
+
+type Decoder = Box<dyn Fn(u32) -> Option<(String, String)> + Send + Sync>;
+
+struct ProgramErrors {
+    program_name: String,
+    type_name: String,
+    decoder: Decoder,
+}
+
+/// Maps program ids to decoders for their `Custom` error codes.
+#[derive(Default)]
+pub struct CustomErrorRegistry {
+    programs: HashMap<Pubkey, ProgramErrors>,
+}
+
+impl fmt::Debug for CustomErrorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.programs.iter().map(|(program_id, errors)| {
+                (program_id, (&errors.program_name, &errors.type_name))
+            }))
+            .finish()
+    }
+}
+
+impl CustomErrorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with decoders for the builtin programs and
+    /// precompiles that return `Custom` codes.
+    pub fn with_builtins() -> Self {
+        use {
+            solana_precompile_error::PrecompileError,
+            solana_sdk_ids::{
+                ed25519_program, secp256k1_program, secp256r1_program, system_program, vote,
+            },
+            solana_system_interface::error::SystemError,
+            solana_vote_interface::error::VoteError,
+        };
+
+        let mut registry = Self::new();
+        registry.register_from_primitive::<SystemError>(
+            system_program::id(),
+            "System Program",
+            "SystemError",
+        );
+        registry.register_from_primitive::<VoteError>(vote::id(), "Vote Program", "VoteError");
+        for (program_id, program_name) in [
+            (ed25519_program::id(), "Ed25519 Precompile"),
+            (secp256k1_program::id(), "Secp256k1 Precompile"),
+            (secp256r1_program::id(), "Secp256r1 Precompile"),
+        ] {
+            registry.register_from_primitive::<PrecompileError>(
+                program_id,
+                program_name,
+                "PrecompileError",
+            );
+        }
+        registry
+    }
+
+    /// Registers a decoder for the `Custom` codes returned by `program_id`.
+    ///
+    /// The decoder returns the variant name and message of a code, or `None`
+    /// if the code is unknown. Registering a program again replaces its
+    /// previous decoder.
+    pub fn register<F>(
+        &mut self,
+        program_id: Pubkey,
+        program_name: impl Into<String>,
+        type_name: impl Into<String>,
+        decoder: F,
+    ) where
+        F: Fn(u32) -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        self.programs.insert(
+            program_id,
+            ProgramErrors {
+                program_name: program_name.into(),
+                type_name: type_name.into(),
+                decoder: Box::new(decoder),
+            },
+        );
+    }
+
+    /// Registers an error enum whose `Custom` codes are its discriminants.
+    ///
+    /// The variant name is taken from `Debug` and the message from
+    /// `Display`.
+    pub fn register_from_primitive<E>(
+        &mut self,
+        program_id: Pubkey,
+        program_name: impl Into<String>,
+        type_name: impl Into<String>,
+    ) where
+        E: FromPrimitive + fmt::Debug + fmt::Display,
+    {
+        self.register(program_id, program_name, type_name, |code| {
+            E::from_u32(code).map(|error| (format!("{error:?}"), error.to_string()))
+        });
+    }
+
+    /// Registers the error table of a [`CustomProgramError`].
+    pub fn register_custom_program_error<E: CustomProgramError>(
+        &mut self,
+        program_id: Pubkey,
+        program_name: impl Into<String>,
+    ) {
+        self.register(program_id, program_name, E::NAME, |code| {
+            E::ERRORS
+                .iter()
+                .find(|entry| entry.code == code)
+                .map(|entry| (entry.name.to_string(), entry.msg.to_string()))
+        });
+    }
+
+    /// Returns whether a decoder is registered for `program_id`.
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
+    /// Decodes a `Custom` code returned by `program_id`.
+    pub fn decode(&self, program_id: &Pubkey, code: u32) -> Option<DecodedCustomError> {
+        let errors = self.programs.get(program_id)?;
+        let (name, msg) = (errors.decoder)(code)?;
+        Some(DecodedCustomError {
+            program_name: errors.program_name.clone(),
+            type_name: errors.type_name.clone(),
+            name,
+            msg,
+            code,
+        })
+    }
+
+    /// Decodes an [`InstructionError::Custom`] returned by `program_id`.
+    ///
+    /// Returns `None` for other variants and for unknown codes.
+    pub fn decode_instruction_error(
+        &self,
+        program_id: &Pubkey,
+        error: &InstructionError,
+    ) -> Option<DecodedCustomError> {
+        match error {
+            InstructionError::Custom(code) => self.decode(program_id, *code),
+            _ => None,
+        }
+    }
+
+    /// Renders an instruction error returned by `program_id`, decoding
+    /// `Custom` codes when possible.
+    pub fn describe_instruction_error(
+        &self,
+        program_id: &Pubkey,
+        error: &InstructionError,
+    ) -> String {
+        match self.decode_instruction_error(program_id, error) {
+            Some(decoded) => decoded.to_string(),
+            None => error.to_string(),
+        }
+    }
+
+    /// Renders a transaction error, decoding the `Custom` code of a failed
+    /// instruction when possible.
+    ///
+    /// `instruction_program_ids` holds the program id of each of the
+    /// transaction's top-level instructions, in order.
+    pub fn describe_transaction_error(
+        &self,
+        error: &TransactionError,
+        instruction_program_ids: &[Pubkey],
+    ) -> String {
+        match error {
+            TransactionError::InstructionError(index, instruction_error) => {
+                match instruction_program_ids
+                    .get(usize::from(*index))
+                    .and_then(|program_id| {
+                        self.decode_instruction_error(program_id, instruction_error)
+                    }) {
+                    Some(decoded) => format!("Error processing Instruction {index}: {decoded}"),
+                    None => error.to_string(),
+                }
+            }
+            _ => error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_program_error::CustomErrorEntry,
+        solana_sdk_ids::{system_program, vote},
+    };
+
+    #[test]
+    fn test_builtins() {
+        let registry = CustomErrorRegistry::with_builtins();
+
+        assert_eq!(
+            registry.decode(&system_program::id(), 7),
+            Some(DecodedCustomError {
+                program_name: "System Program".to_string(),
+                type_name: "SystemError".to_string(),
+                name: "NonceBlockhashNotExpired".to_string(),
+                msg: "stored nonce is still in recent_blockhashes".to_string(),
+                code: 7,
+            })
+        );
+        assert_eq!(registry.decode(&system_program::id(), 1000), None);
+        assert_eq!(registry.decode(&Pubkey::new_unique(), 0), None);
+
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(0));
+        assert_eq!(
+            registry.describe_transaction_error(&error, &[system_program::id(), vote::id()]),
+            "Error processing Instruction 1: Vote Program: VoteError::VoteTooOld \
+             (custom program error: 0x0): vote already recorded or not in slot hashes history"
+        );
+        // Without the failed instruction's program id the error is rendered as is
+        assert_eq!(
+            registry.describe_transaction_error(&error, &[system_program::id()]),
+            error.to_string()
+        );
+        assert_eq!(
+            registry.describe_transaction_error(
+                &TransactionError::AccountInUse,
+                &[system_program::id()]
+            ),
+            "Account in use"
+        );
+    }
+
+    #[test]
+    fn test_register_custom_program_error() {
+        #[derive(Debug, PartialEq)]
+        enum TestError {
+            Frozen,
+        }
+        impl solana_program_error::ToStr for TestError {
+            fn to_str<E>(&self) -> &'static str {
+                "Error: frozen"
+            }
+        }
+        impl TryFrom<u32> for TestError {
+            type Error = u32;
+            fn try_from(code: u32) -> Result<Self, u32> {
+                (code == 6000).then_some(Self::Frozen).ok_or(code)
+            }
+        }
+        impl CustomProgramError for TestError {
+            const NAME: &'static str = "TestError";
+            const ERRORS: &'static [CustomErrorEntry] = &[CustomErrorEntry {
+                code: 6000,
+                name: "Frozen",
+                msg: "frozen",
+            }];
+            fn code(&self) -> u32 {
+                6000
+            }
+        }
+
+        let program_id = Pubkey::new_unique();
+        let mut registry = CustomErrorRegistry::new();
+        assert!(!registry.contains(&program_id));
+        registry.register_custom_program_error::<TestError>(program_id, "Test Program");
+        assert!(registry.contains(&program_id));
+
+        assert_eq!(
+            registry.describe_instruction_error(&program_id, &InstructionError::Custom(6000)),
+            "Test Program: TestError::Frozen (custom program error: 0x1770): frozen"
+        );
+        assert_eq!(
+            registry.describe_instruction_error(&program_id, &InstructionError::Custom(6001)),
+            InstructionError::Custom(6001).to_string()
+        );
+    }
+}
@@ -6,6 +6,9 @@ use serde_derive::{Deserialize, Serialize};
 use solana_frozen_abi_macro::{AbiEnumVisitor, AbiExample};
 use {core::fmt, solana_instruction::error::InstructionError, solana_sanitize::SanitizeError};
 
+#[cfg(feature = "custom-error-registry")]
+pub mod custom_error;
+
 pub type TransactionResult<T> = Result<T, TransactionError>;
 
 /// Reasons a transaction might be rejected.
@@ -0,0 +1,79 @@
+use {
+    criterion::{black_box, criterion_group, criterion_main, Criterion},
+    solana_pubkey::Pubkey,
+    solana_vote_interface::{
+        authorized_voters::AuthorizedVoters,
+        state::{
+            LandedVote, Lockout, VoteState, VoteStateV4, VoteStateVersions, VoteStateView,
+            MAX_EPOCH_CREDITS_HISTORY, MAX_LOCKOUT_HISTORY,
+        },
+    },
+    std::mem::MaybeUninit,
+};
+
+fn full_vote_state() -> VoteState {
+    let mut vote_state = VoteState {
+        node_pubkey: Pubkey::new_unique(),
+        authorized_withdrawer: Pubkey::new_unique(),
+        commission: 5,
+        authorized_voters: AuthorizedVoters::new(0, Pubkey::new_unique()),
+        root_slot: Some(1_000),
+        ..VoteState::default()
+    };
+    for offset in 0..MAX_LOCKOUT_HISTORY as u64 {
+        vote_state.votes.push_back(LandedVote {
+            latency: 1,
+            lockout: Lockout::new_with_confirmation_count(
+                offset.saturating_add(1_001),
+                (MAX_LOCKOUT_HISTORY as u64).saturating_sub(offset) as u32,
+            ),
+        });
+    }
+    for epoch in 0..MAX_EPOCH_CREDITS_HISTORY as u64 {
+        vote_state.epoch_credits.push((
+            epoch,
+            epoch.saturating_add(1).saturating_mul(1_000),
+            epoch.saturating_mul(1_000),
+        ));
+    }
+    vote_state
+}
+
+fn account_data(versioned: &VoteStateVersions) -> Vec<u8> {
+    let mut data = vec![0; VoteState::size_of()];
+    VoteState::serialize(versioned, &mut data).unwrap();
+    data
+}
+
+fn bench_vote_state_parsing(c: &mut Criterion) {
+    let vote_state = full_vote_state();
+    let v3_data = account_data(&VoteStateVersions::new_current(vote_state.clone()));
+    let v4_data = account_data(&VoteStateVersions::new_v4(
+        VoteStateV4::new_from_vote_state(&Pubkey::new_unique(), vote_state),
+    ));
+
+    let mut group = c.benchmark_group("vote_state");
+    for (version, data) in [("v3", &v3_data), ("v4", &v4_data)] {
+        group.bench_function(format!("{version} bincode deserialize"), |b| {
+            b.iter(|| bincode::deserialize::<VoteStateVersions>(black_box(data)).unwrap())
+        });
+        group.bench_function(format!("{version} view credits and last vote"), |b| {
+            b.iter(|| {
+                let view = VoteStateView::try_new(black_box(data)).unwrap();
+                (view.credits(), view.last_voted_slot())
+            })
+        });
+    }
+    // `VoteState` cannot hold a V4 layout
+    group.bench_function("v3 deserialize_into_uninit", |b| {
+        b.iter(|| {
+            let mut vote_state = MaybeUninit::uninit();
+            VoteState::deserialize_into_uninit(black_box(&v3_data), &mut vote_state).unwrap();
+            unsafe { vote_state.assume_init() }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_vote_state_parsing);
+criterion_main!(benches);
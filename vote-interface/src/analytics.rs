@@ -0,0 +1,387 @@
+//! Estimates of the staking rewards earned through a vote account.
+//!
+//! Rewards are minted at the start of each epoch for the previous one. The
+//! inflation for the epoch is split between stake accounts by _points_, the
+//! product of their stake and the vote credits their validator earned, and
+//! each share is split between the validator and the delegators by the
+//! commission.
+//!
+//! This module reproduces that calculation for a single stake amount from
+//! the `epoch_credits` history of a vote account, so that dashboards and
+//! tools can project rewards without reimplementing the runtime math.
+//! Network-wide values that a vote account does not record, such as the
+//! capitalization and the total active stake, are given by a
+//! [`RewardsEnvironment`].
+
+use {
+    crate::state::{vote_state_v3, VoteState, VOTE_CREDITS_MAXIMUM_PER_SLOT},
+    solana_clock::{Epoch, Slot},
+    solana_epoch_schedule::EpochSchedule,
+    solana_inflation::Inflation,
+};
+
+/// Network-wide parameters of a rewards estimate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardsEnvironment {
+    pub inflation: Inflation,
+    pub epoch_schedule: EpochSchedule,
+    /// Slots per year, as in the genesis config.
+    pub slots_per_year: f64,
+    /// First slot of the epoch from which inflation is enabled.
+    pub inflation_start_slot: Slot,
+    /// Total lamports in circulation.
+    pub capitalization: u64,
+    /// Total active stake of all validators.
+    pub total_active_stake: u64,
+    /// Credits earned per epoch by the average stake of the network, or
+    /// `None` to assume the network earns as many credits as the validator
+    /// being estimated.
+    pub network_credits_per_epoch: Option<u64>,
+}
+
+/// Estimated rewards for one epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochRewardsEstimate {
+    pub epoch: Epoch,
+    /// Credits earned by the vote account in the epoch.
+    pub credits: u64,
+    /// Inflation minted for validator rewards across the network.
+    pub inflation_rewards: u64,
+    /// Rewards of the estimated stake, before the commission.
+    pub rewards: u64,
+    /// The validator's share of `rewards`.
+    pub validator_rewards: u64,
+    /// The delegators' share of `rewards`.
+    pub delegator_rewards: u64,
+    /// Delegator rewards relative to the stake.
+    pub delegator_yield: f64,
+}
+
+/// Estimated rewards over the epochs of an `epoch_credits` history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardsEstimate {
+    pub epochs: Vec<EpochRewardsEstimate>,
+    /// Simple annual rate of the delegator yield.
+    pub annual_percentage_rate: f64,
+    /// Annual yield of the delegator rewards, compounded every epoch.
+    pub annual_percentage_yield: f64,
+}
+
+impl RewardsEstimate {
+    pub fn total_rewards(&self) -> u64 {
+        self.epochs.iter().map(|epoch| epoch.rewards).sum()
+    }
+
+    pub fn total_validator_rewards(&self) -> u64 {
+        self.epochs
+            .iter()
+            .map(|epoch| epoch.validator_rewards)
+            .sum()
+    }
+
+    pub fn total_delegator_rewards(&self) -> u64 {
+        self.epochs
+            .iter()
+            .map(|epoch| epoch.delegator_rewards)
+            .sum()
+    }
+}
+
+impl RewardsEnvironment {
+    /// Length of `epoch` in years.
+    pub fn epoch_duration_in_years(&self, epoch: Epoch) -> f64 {
+        self.epoch_schedule.get_slots_in_epoch(epoch) as f64 / self.slots_per_year
+    }
+
+    /// Years of inflation elapsed at the start of `epoch`, which determine
+    /// the inflation rate of the rewards paid in that epoch.
+    pub fn slot_in_year_for_inflation(&self, epoch: Epoch) -> f64 {
+        let inflation_start_epoch = self
+            .epoch_schedule
+            .get_epoch(self.inflation_start_slot)
+            .saturating_sub(1);
+        let num_slots = self
+            .epoch_schedule
+            .get_first_slot_in_epoch(epoch)
+            .saturating_sub(
+                self.epoch_schedule
+                    .get_first_slot_in_epoch(inflation_start_epoch),
+            );
+        num_slots as f64 / self.slots_per_year
+    }
+
+    /// Inflation minted for validator rewards earned during `epoch`, paid at
+    /// the start of the next one.
+    pub fn epoch_inflation_rewards(&self, epoch: Epoch) -> u64 {
+        let validator_rate = self
+            .inflation
+            .validator(self.slot_in_year_for_inflation(epoch.saturating_add(1)));
+        (validator_rate * self.capitalization as f64 * self.epoch_duration_in_years(epoch)) as u64
+    }
+
+    /// Estimates the rewards of `stake` delegated to a vote account with
+    /// `epoch_credits` and `commission`, for every epoch of the history.
+    pub fn estimate_rewards(
+        &self,
+        epoch_credits: &[(Epoch, u64, u64)],
+        commission: u8,
+        stake: u64,
+    ) -> RewardsEstimate {
+        let epochs = epoch_credits
+            .iter()
+            .map(|(epoch, credits, prev_credits)| {
+                self.estimate_epoch_rewards(
+                    *epoch,
+                    credits.saturating_sub(*prev_credits),
+                    commission,
+                    stake,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let (annual_percentage_rate, annual_percentage_yield) = if epochs.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let years = epochs
+                .iter()
+                .map(|epoch| self.epoch_duration_in_years(epoch.epoch))
+                .sum::<f64>();
+            let growth = epochs
+                .iter()
+                .map(|epoch| 1.0 + epoch.delegator_yield)
+                .product::<f64>();
+            let total_yield = epochs
+                .iter()
+                .map(|epoch| epoch.delegator_yield)
+                .sum::<f64>();
+            (total_yield / years, growth.powf(1.0 / years) - 1.0)
+        };
+
+        RewardsEstimate {
+            epochs,
+            annual_percentage_rate,
+            annual_percentage_yield,
+        }
+    }
+
+    /// Estimates the rewards of `stake` delegated to a vote account that
+    /// earned `credits` during `epoch`.
+    pub fn estimate_epoch_rewards(
+        &self,
+        epoch: Epoch,
+        credits: u64,
+        commission: u8,
+        stake: u64,
+    ) -> EpochRewardsEstimate {
+        let inflation_rewards = self.epoch_inflation_rewards(epoch);
+        let network_credits = self.network_credits_per_epoch.unwrap_or(credits);
+        let points = u128::from(stake).checked_mul(u128::from(credits));
+        let total_points =
+            u128::from(self.total_active_stake).checked_mul(u128::from(network_credits));
+        let rewards = match (points, total_points) {
+            (Some(points), Some(total_points)) if total_points > 0 => {
+                // A stake cannot earn more than the rewards of the epoch, even
+                // if it is not accounted for in the total active stake.
+                let rewards = points
+                    .checked_mul(u128::from(inflation_rewards))
+                    .and_then(|product| product.checked_div(total_points))
+                    .unwrap_or_else(|| {
+                        (points as f64 / total_points as f64 * inflation_rewards as f64) as u128
+                    });
+                rewards.min(u128::from(inflation_rewards)) as u64
+            }
+            _ => 0,
+        };
+        let (validator_rewards, delegator_rewards) = commission_split(commission, rewards);
+
+        EpochRewardsEstimate {
+            epoch,
+            credits,
+            inflation_rewards,
+            rewards,
+            validator_rewards,
+            delegator_rewards,
+            delegator_yield: if stake == 0 {
+                0.0
+            } else {
+                delegator_rewards as f64 / stake as f64
+            },
+        }
+    }
+}
+
+/// Splits `rewards` into the validator's and the delegators' portions with
+/// [`state::vote_state_v3::commission_split`](crate::state::vote_state_v3::commission_split),
+/// as the runtime does.
+///
+/// Each portion is rounded down independently, so fractional lamports are
+/// not paid to either party.
+pub fn commission_split(commission: u8, rewards: u64) -> (u64, u64) {
+    let (validator, delegators, _) = vote_state_v3::commission_split(commission, rewards);
+    (validator, delegators)
+}
+
+/// Average credits earned per vote for votes landing with `latencies`.
+///
+/// Votes earn [`VOTE_CREDITS_MAXIMUM_PER_SLOT`] credits when they land within
+/// the grace period of timely vote credits, and one less for every slot after
+/// it, down to a single credit.
+pub fn average_credits_per_vote(latencies: impl IntoIterator<Item = u8>) -> f64 {
+    let (votes, credits) = latencies
+        .into_iter()
+        .fold((0u64, 0u64), |(votes, credits), latency| {
+            (
+                votes.saturating_add(1),
+                credits.saturating_add(VoteState::credits_for_latency(latency)),
+            )
+        });
+    if votes == 0 {
+        0.0
+    } else {
+        credits as f64 / votes as f64
+    }
+}
+
+/// Credits a validator would earn in `epoch` by voting on `vote_fraction` of
+/// its slots, with votes landing with `latencies`.
+pub fn projected_epoch_credits(
+    epoch_schedule: &EpochSchedule,
+    epoch: Epoch,
+    vote_fraction: f64,
+    latencies: impl IntoIterator<Item = u8>,
+) -> u64 {
+    let votes = epoch_schedule.get_slots_in_epoch(epoch) as f64 * vote_fraction.clamp(0.0, 1.0);
+    (votes * average_credits_per_vote(latencies)) as u64
+}
+
+/// Fraction of the maximum credits of `epoch` that were earned, where the
+/// maximum is a timely vote on every slot.
+pub fn vote_credit_efficiency(epoch_schedule: &EpochSchedule, epoch: Epoch, credits: u64) -> f64 {
+    let maximum_credits = epoch_schedule
+        .get_slots_in_epoch(epoch)
+        .saturating_mul(u64::from(VOTE_CREDITS_MAXIMUM_PER_SLOT));
+    if maximum_credits == 0 {
+        0.0
+    } else {
+        credits as f64 / maximum_credits as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS_PER_EPOCH: u64 = 432_000;
+
+    fn environment() -> RewardsEnvironment {
+        RewardsEnvironment {
+            inflation: Inflation::new_fixed(0.05),
+            epoch_schedule: EpochSchedule::without_warmup(),
+            slots_per_year: (SLOTS_PER_EPOCH * 100) as f64,
+            inflation_start_slot: 0,
+            capitalization: 1_000_000_000,
+            total_active_stake: 500_000_000,
+            network_credits_per_epoch: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_epoch_rewards() {
+        let environment = environment();
+        assert_eq!(environment.epoch_duration_in_years(0), 0.01);
+        // 5% of the capitalization over a hundredth of a year
+        assert_eq!(environment.epoch_inflation_rewards(0), 500_000);
+
+        // A tenth of the stake, earning as many credits as the network
+        let estimate = environment.estimate_epoch_rewards(0, 1_000, 10, 50_000_000);
+        assert_eq!(
+            estimate,
+            EpochRewardsEstimate {
+                epoch: 0,
+                credits: 1_000,
+                inflation_rewards: 500_000,
+                rewards: 50_000,
+                validator_rewards: 5_000,
+                delegator_rewards: 45_000,
+                delegator_yield: 0.0009,
+            }
+        );
+
+        // Earning half the credits of the network halves the rewards
+        let environment = RewardsEnvironment {
+            network_credits_per_epoch: Some(2_000),
+            ..environment
+        };
+        let estimate = environment.estimate_epoch_rewards(0, 1_000, 10, 50_000_000);
+        assert_eq!(estimate.rewards, 25_000);
+
+        // No credits, no rewards
+        let estimate = environment.estimate_epoch_rewards(0, 0, 10, 50_000_000);
+        assert_eq!(estimate.rewards, 0);
+    }
+
+    #[test]
+    fn test_estimate_rewards() {
+        let environment = environment();
+        let epoch_credits = [(0, 1_000, 0), (1, 2_500, 1_000), (2, 4_000, 2_500)];
+        let estimate = environment.estimate_rewards(&epoch_credits, 0, 50_000_000);
+        assert_eq!(estimate.epochs.len(), 3);
+        assert_eq!(
+            estimate
+                .epochs
+                .iter()
+                .map(|epoch| epoch.credits)
+                .collect::<Vec<_>>(),
+            vec![1_000, 1_500, 1_500]
+        );
+        assert_eq!(estimate.total_rewards(), 150_000);
+        assert_eq!(estimate.total_validator_rewards(), 0);
+        assert_eq!(estimate.total_delegator_rewards(), 150_000);
+
+        // 0.1% per hundredth of a year
+        assert!((estimate.annual_percentage_rate - 0.1).abs() < 1e-9);
+        assert!((estimate.annual_percentage_yield - (1.001f64.powi(100) - 1.0)).abs() < 1e-9);
+
+        let estimate = environment.estimate_rewards(&[], 0, 50_000_000);
+        assert_eq!(estimate.annual_percentage_yield, 0.0);
+    }
+
+    #[test]
+    fn test_inflation_taper() {
+        let environment = RewardsEnvironment {
+            inflation: Inflation::default(),
+            ..environment()
+        };
+        // Rewards of epoch 99 are paid at the start of year 1
+        assert!(environment.epoch_inflation_rewards(0) > environment.epoch_inflation_rewards(99));
+        assert_eq!(environment.slot_in_year_for_inflation(100), 1.0);
+    }
+
+    #[test]
+    fn test_commission_split() {
+        assert_eq!(commission_split(0, 100), (0, 100));
+        assert_eq!(commission_split(100, 100), (100, 0));
+        assert_eq!(commission_split(150, 100), (100, 0));
+        assert_eq!(commission_split(10, 99), (9, 89));
+        assert_eq!(commission_split(50, u64::MAX), (u64::MAX / 2, u64::MAX / 2));
+    }
+
+    #[test]
+    fn test_timely_vote_credits() {
+        assert_eq!(average_credits_per_vote([]), 0.0);
+        assert_eq!(average_credits_per_vote([1, 2]), 16.0);
+        assert_eq!(
+            average_credits_per_vote([2, 4, 100]),
+            (16 + 14 + 1) as f64 / 3.0
+        );
+        // Latency 0 is recorded by software that did not track latency
+        assert_eq!(average_credits_per_vote([0]), 1.0);
+
+        let epoch_schedule = EpochSchedule::custom(1_000, 1_000, false);
+        assert_eq!(
+            projected_epoch_credits(&epoch_schedule, 0, 0.5, [1, 3]),
+            7_750
+        );
+        assert_eq!(vote_credit_efficiency(&epoch_schedule, 0, 8_000), 0.5);
+    }
+}
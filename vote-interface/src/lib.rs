@@ -4,10 +4,14 @@
 //!
 //! [np]: https://docs.solanalabs.com/runtime/programs#vote-program
 
+#[cfg(feature = "analytics")]
+pub mod analytics;
 pub mod authorized_voters;
 pub mod error;
 pub mod instruction;
 pub mod state;
+#[cfg(feature = "tower-simulator")]
+pub mod tower_simulator;
 
 pub mod program {
     pub use solana_sdk_ids::vote::{check_id, id, ID};
@@ -23,6 +23,10 @@ pub mod vote_state_versions;
 pub use vote_state_versions::*;
 pub mod vote_state_v3;
 pub use vote_state_v3::VoteState;
+pub mod vote_state_v4;
+pub use vote_state_v4::VoteStateV4;
+pub mod vote_state_view;
+pub use vote_state_view::{VoteStateLayout, VoteStateView};
 mod vote_instruction_data;
 pub use vote_instruction_data::*;
 
@@ -407,7 +411,7 @@ mod tests {
         VoteState::serialize(&versioned, &mut buffer).unwrap();
         assert_eq!(
             VoteState::deserialize(&buffer).unwrap(),
-            versioned.convert_to_current()
+            versioned.try_convert_to_current().unwrap()
         );
     }
 
@@ -433,12 +437,12 @@ mod tests {
             let target_vote_state_versions =
                 VoteStateVersions::arbitrary(&mut unstructured).unwrap();
             let vote_state_buf = bincode::serialize(&target_vote_state_versions).unwrap();
-            let target_vote_state = target_vote_state_versions.convert_to_current();
+            let target_vote_state = target_vote_state_versions.try_convert_to_current();
 
             let mut test_vote_state = VoteState::default();
-            VoteState::deserialize_into(&vote_state_buf, &mut test_vote_state).unwrap();
+            let test_res = VoteState::deserialize_into(&vote_state_buf, &mut test_vote_state);
 
-            assert_eq!(target_vote_state, test_vote_state);
+            assert_eq!(target_vote_state, test_res.map(|()| test_vote_state));
         }
     }
 
@@ -478,11 +482,12 @@ mod tests {
             let target_vote_state_versions =
                 VoteStateVersions::arbitrary(&mut unstructured).unwrap();
             let vote_state_buf = bincode::serialize(&target_vote_state_versions).unwrap();
-            let target_vote_state = target_vote_state_versions.convert_to_current();
+            let target_vote_state = target_vote_state_versions.try_convert_to_current();
 
             let mut test_vote_state = MaybeUninit::uninit();
-            VoteState::deserialize_into_uninit(&vote_state_buf, &mut test_vote_state).unwrap();
-            let test_vote_state = unsafe { test_vote_state.assume_init() };
+            let test_res =
+                VoteState::deserialize_into_uninit(&vote_state_buf, &mut test_vote_state);
+            let test_vote_state = test_res.map(|()| unsafe { test_vote_state.assume_init() });
 
             assert_eq!(target_vote_state, test_vote_state);
         }
@@ -516,7 +521,8 @@ mod tests {
             let mut test_vote_state = MaybeUninit::uninit();
             let test_res = VoteState::deserialize_into_uninit(&raw_data, &mut test_vote_state);
             let bincode_res = bincode::deserialize::<VoteStateVersions>(&raw_data)
-                .map(|versioned| versioned.convert_to_current());
+                .map_err(|_| InstructionError::InvalidAccountData)
+                .and_then(VoteStateVersions::try_convert_to_current);
 
             if test_res.is_err() {
                 assert!(bincode_res.is_err());
@@ -549,19 +555,25 @@ mod tests {
             let mut test_vote_state = MaybeUninit::uninit();
             let test_res = VoteState::deserialize_into_uninit(&truncated_buf, &mut test_vote_state);
             let bincode_res = bincode::deserialize::<VoteStateVersions>(&truncated_buf)
-                .map(|versioned| versioned.convert_to_current());
+                .map_err(|_| InstructionError::InvalidAccountData)
+                .and_then(VoteStateVersions::try_convert_to_current);
 
             assert!(test_res.is_err());
             assert!(bincode_res.is_err());
 
-            // expanded succeeds
+            // expanded succeeds, unless the layout is V4
             let mut test_vote_state = MaybeUninit::uninit();
-            VoteState::deserialize_into_uninit(&expanded_buf, &mut test_vote_state).unwrap();
+            let test_res = VoteState::deserialize_into_uninit(&expanded_buf, &mut test_vote_state);
             let bincode_res = bincode::deserialize::<VoteStateVersions>(&expanded_buf)
-                .map(|versioned| versioned.convert_to_current());
+                .map_err(|_| InstructionError::InvalidAccountData)
+                .and_then(VoteStateVersions::try_convert_to_current);
 
-            let test_vote_state = unsafe { test_vote_state.assume_init() };
-            assert_eq!(test_vote_state, bincode_res.unwrap());
+            let test_vote_state = test_res.map(|()| unsafe { test_vote_state.assume_init() });
+            assert_eq!(
+                test_vote_state.is_ok(),
+                !matches!(original_vote_state_versions, VoteStateVersions::V4(_))
+            );
+            assert_eq!(test_vote_state, bincode_res);
         }
     }
 
@@ -929,7 +941,7 @@ mod tests {
 
             let versioned = VoteStateVersions::new_current(vote_state.take().unwrap());
             VoteState::serialize(&versioned, &mut max_sized_data).unwrap();
-            vote_state = Some(versioned.convert_to_current());
+            vote_state = Some(versioned.try_convert_to_current().unwrap());
         }
     }
 
@@ -122,8 +122,8 @@ impl VoteState {
         #[cfg(not(target_os = "solana"))]
         {
             bincode::deserialize::<VoteStateVersions>(input)
-                .map(|versioned| versioned.convert_to_current())
-                .map_err(|_| InstructionError::InvalidAccountData)
+                .map_err(|_| InstructionError::InvalidAccountData)?
+                .try_convert_to_current()
         }
         #[cfg(target_os = "solana")]
         {
@@ -231,8 +231,8 @@ impl VoteState {
                     unsafe {
                         vote_state.write(
                             bincode::deserialize::<VoteStateVersions>(input)
-                                .map(|versioned| versioned.convert_to_current())
-                                .map_err(|_| InstructionError::InvalidAccountData)?,
+                                .map_err(|_| InstructionError::InvalidAccountData)?
+                                .try_convert_to_current()?,
                         );
                     }
                     Ok(())
@@ -244,6 +244,7 @@ impl VoteState {
             1 => deserialize_vote_state_into(&mut cursor, vote_state, false),
             // Current. the only difference from V1_14_11 is the addition of a slot-latency to each vote
             2 => deserialize_vote_state_into(&mut cursor, vote_state, true),
+            // V4. separate commissions and collectors, which `VoteState` cannot hold
             _ => Err(InstructionError::InvalidAccountData),
         }?;
 
@@ -267,32 +268,7 @@ impl VoteState {
     ///   indicate with false for was_split
     #[deprecated(since = "2.2.0", note = "logic was moved into the agave runtime crate")]
     pub fn commission_split(&self, on: u64) -> (u64, u64, bool) {
-        match self.commission.min(100) {
-            0 => (0, on, false),
-            100 => (on, 0, false),
-            split => {
-                let on = u128::from(on);
-                // Calculate mine and theirs independently and symmetrically instead of
-                // using the remainder of the other to treat them strictly equally.
-                // This is also to cancel the rewarding if either of the parties
-                // should receive only fractional lamports, resulting in not being rewarded at all.
-                // Thus, note that we intentionally discard any residual fractional lamports.
-                let mine = on
-                    .checked_mul(u128::from(split))
-                    .expect("multiplication of a u64 and u8 should not overflow")
-                    / 100u128;
-                let theirs = on
-                    .checked_mul(u128::from(
-                        100u8
-                            .checked_sub(split)
-                            .expect("commission cannot be greater than 100"),
-                    ))
-                    .expect("multiplication of a u64 and u8 should not overflow")
-                    / 100u128;
-
-                (mine as u64, theirs as u64, true)
-            }
-        }
+        commission_split(self.commission, on)
     }
 
     /// Returns if the vote state contains a slot `candidate_slot`
@@ -393,6 +369,11 @@ impl VoteState {
             .get(index)
             .map_or(0, |landed_vote| landed_vote.latency);
 
+        Self::credits_for_latency(latency)
+    }
+
+    /// Returns the credits to award for a vote that landed `latency` slots after the voted slot
+    pub fn credits_for_latency(latency: u8) -> u64 {
         // If latency is 0, this means that the Lockout was created and stored from a software version that did not
         // store vote latencies; in this case, 1 credit is awarded
         if latency == 0 {
@@ -597,6 +578,41 @@ impl VoteState {
     }
 }
 
+/// Splits `on` lamports between the voter and the stakers of a vote account
+/// with `commission`, as a `(voter_portion, staker_portion, was_split)`
+/// tuple.
+///
+/// Commissions above 100 are treated as 100. If the commission is 100% one
+/// way or the other, `was_split` is false.
+pub fn commission_split(commission: u8, on: u64) -> (u64, u64, bool) {
+    match commission.min(100) {
+        0 => (0, on, false),
+        100 => (on, 0, false),
+        split => {
+            let on = u128::from(on);
+            // Calculate mine and theirs independently and symmetrically instead of
+            // using the remainder of the other to treat them strictly equally.
+            // This is also to cancel the rewarding if either of the parties
+            // should receive only fractional lamports, resulting in not being rewarded at all.
+            // Thus, note that we intentionally discard any residual fractional lamports.
+            let mine = on
+                .checked_mul(u128::from(split))
+                .expect("multiplication of a u64 and u8 should not overflow")
+                / 100u128;
+            let theirs = on
+                .checked_mul(u128::from(
+                    100u8
+                        .checked_sub(split)
+                        .expect("commission cannot be greater than 100"),
+                ))
+                .expect("multiplication of a u64 and u8 should not overflow")
+                / 100u128;
+
+            (mine as u64, theirs as u64, true)
+        }
+    }
+}
+
 #[cfg(any(target_os = "solana", feature = "bincode"))]
 mod vote_state_deserialize {
     use {
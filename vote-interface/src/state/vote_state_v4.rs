@@ -0,0 +1,286 @@
+#[cfg(feature = "dev-context-only-utils")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "frozen-abi")]
+use solana_frozen_abi_macro::AbiExample;
+use {
+    super::{BlockTimestamp, CircBuf, LandedVote, VoteState},
+    crate::authorized_voters::AuthorizedVoters,
+    solana_clock::{Epoch, Slot},
+    solana_pubkey::Pubkey,
+    solana_rent::Rent,
+    std::collections::VecDeque,
+};
+
+/// Size of a compressed BLS public key.
+pub const BLS_PUBLIC_KEY_COMPRESSED_SIZE: usize = 48;
+
+/// Commissions in basis points are out of this many.
+pub const MAX_COMMISSION_BPS: u16 = 10_000;
+
+/// Vote state layout with separate commissions and collectors for inflation
+/// rewards and block revenue, as proposed by SIMD-0185.
+///
+/// Unlike [`VoteState`], it does not keep the history of prior voters.
+#[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "dev-context-only-utils", derive(Arbitrary))]
+pub struct VoteStateV4 {
+    /// the node that votes in this account
+    pub node_pubkey: Pubkey,
+
+    /// the signer for withdrawals
+    pub authorized_withdrawer: Pubkey,
+
+    /// the account that receives the validator's share of inflation rewards
+    pub inflation_rewards_collector: Pubkey,
+
+    /// the account that receives the validator's share of block revenue
+    pub block_revenue_collector: Pubkey,
+
+    /// basis points (0-10,000) of inflation rewards kept by the validator
+    pub inflation_rewards_commission_bps: u16,
+
+    /// basis points (0-10,000) of block revenue kept by the validator
+    pub block_revenue_commission_bps: u16,
+
+    /// rewards not yet distributed to stake delegators
+    pub pending_delegator_rewards: u64,
+
+    /// compressed BLS public key of the validator
+    #[cfg_attr(feature = "serde", serde(with = "serde_bls_pubkey"))]
+    pub bls_pubkey_compressed: Option<[u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]>,
+
+    pub votes: VecDeque<LandedVote>,
+
+    // This usually the last Lockout which was popped from self.votes.
+    // However, it can be arbitrary slot, when being used inside Tower
+    pub root_slot: Option<Slot>,
+
+    /// the signer for vote transactions
+    pub authorized_voters: AuthorizedVoters,
+
+    /// history of how many credits earned by the end of each epoch
+    ///  each tuple is (Epoch, credits, prev_credits)
+    pub epoch_credits: Vec<(Epoch, u64, u64)>,
+
+    /// most recent timestamp submitted with a vote
+    pub last_timestamp: BlockTimestamp,
+}
+
+impl VoteStateV4 {
+    /// Converts a [`VoteState`] of the vote account `vote_pubkey`.
+    ///
+    /// Inflation rewards are collected by the vote account and block revenue
+    /// by the node, with the validator keeping all block revenue, which is
+    /// how both are distributed for a [`VoteState`].
+    pub fn new_from_vote_state(vote_pubkey: &Pubkey, vote_state: VoteState) -> Self {
+        Self {
+            node_pubkey: vote_state.node_pubkey,
+            authorized_withdrawer: vote_state.authorized_withdrawer,
+            inflation_rewards_collector: *vote_pubkey,
+            block_revenue_collector: vote_state.node_pubkey,
+            inflation_rewards_commission_bps: u16::from(vote_state.commission).saturating_mul(100),
+            block_revenue_commission_bps: MAX_COMMISSION_BPS,
+            pending_delegator_rewards: 0,
+            bls_pubkey_compressed: None,
+            votes: vote_state.votes,
+            root_slot: vote_state.root_slot,
+            authorized_voters: vote_state.authorized_voters,
+            epoch_credits: vote_state.epoch_credits,
+            last_timestamp: vote_state.last_timestamp,
+        }
+    }
+
+    /// The inflation rewards commission as a percentage, rounded down.
+    pub fn commission(&self) -> u8 {
+        u8::try_from(self.inflation_rewards_commission_bps / 100).unwrap_or(u8::MAX)
+    }
+
+    pub fn get_rent_exempt_reserve(rent: &Rent) -> u64 {
+        rent.minimum_balance(Self::size_of())
+    }
+
+    /// Size of a vote account holding this layout.
+    ///
+    /// This is the size of a [`VoteState`] account, so that existing accounts
+    /// can be converted in place; a maximum sized `VoteStateV4` fits in it.
+    pub const fn size_of() -> usize {
+        VoteState::size_of()
+    }
+
+    pub fn is_uninitialized(&self) -> bool {
+        self.authorized_voters.is_empty()
+    }
+
+    /// Converts to the [`VoteState`] layout, dropping what it cannot hold.
+    ///
+    /// The inflation rewards commission is rounded down to a percentage, and
+    /// the collectors, the block revenue commission, the pending delegator
+    /// rewards and the BLS public key are lost.
+    pub fn into_vote_state_lossy(self) -> VoteState {
+        VoteState {
+            commission: self.commission(),
+            node_pubkey: self.node_pubkey,
+            authorized_withdrawer: self.authorized_withdrawer,
+            votes: self.votes,
+            root_slot: self.root_slot,
+            authorized_voters: self.authorized_voters,
+            prior_voters: CircBuf::default(),
+            epoch_credits: self.epoch_credits,
+            last_timestamp: self.last_timestamp,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_bls_pubkey {
+    use {
+        super::BLS_PUBLIC_KEY_COMPRESSED_SIZE,
+        serde::{Deserialize, Deserializer, Serialize, Serializer},
+        serde_big_array::BigArray,
+    };
+
+    #[derive(serde_derive::Deserialize, serde_derive::Serialize)]
+    struct BlsPubkey(#[serde(with = "BigArray")] [u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]);
+
+    pub fn serialize<S: Serializer>(
+        pubkey: &Option<[u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        pubkey.map(BlsPubkey).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<[u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]>, D::Error> {
+        Ok(Option::<BlsPubkey>::deserialize(deserializer)?.map(|pubkey| pubkey.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::state::{Lockout, VoteStateVersions},
+        bincode::serialized_size,
+        core::mem::MaybeUninit,
+        solana_instruction::error::InstructionError,
+    };
+
+    fn get_max_sized_vote_state_v4() -> VoteStateV4 {
+        VoteStateV4 {
+            bls_pubkey_compressed: Some([7; BLS_PUBLIC_KEY_COMPRESSED_SIZE]),
+            ..VoteStateV4::new_from_vote_state(
+                &Pubkey::new_unique(),
+                VoteState::get_max_sized_vote_state(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_vote_state_v4_size_of() {
+        let vote_state = VoteStateVersions::new_v4(get_max_sized_vote_state_v4());
+        assert!(serialized_size(&vote_state).unwrap() <= VoteStateV4::size_of() as u64);
+    }
+
+    #[test]
+    fn test_vote_state_v4_layout() {
+        let vote_state = VoteStateV4 {
+            node_pubkey: Pubkey::new_from_array([1; 32]),
+            authorized_withdrawer: Pubkey::new_from_array([2; 32]),
+            inflation_rewards_collector: Pubkey::new_from_array([3; 32]),
+            block_revenue_collector: Pubkey::new_from_array([4; 32]),
+            inflation_rewards_commission_bps: 0x0506,
+            block_revenue_commission_bps: 0x0708,
+            pending_delegator_rewards: 9,
+            bls_pubkey_compressed: Some([10; BLS_PUBLIC_KEY_COMPRESSED_SIZE]),
+            ..VoteStateV4::default()
+        };
+        let data = bincode::serialize(&VoteStateVersions::new_v4(vote_state.clone())).unwrap();
+
+        let mut expected = vec![3, 0, 0, 0];
+        for byte in 1..=4 {
+            expected.extend([byte; 32]);
+        }
+        expected.extend([6, 5, 8, 7]);
+        expected.extend(9u64.to_le_bytes());
+        expected.push(1);
+        expected.extend([10; BLS_PUBLIC_KEY_COMPRESSED_SIZE]);
+        assert_eq!(data[..expected.len()], expected);
+
+        let deserialized: VoteStateVersions = bincode::deserialize(&data).unwrap();
+        assert_eq!(deserialized, VoteStateVersions::new_v4(vote_state));
+    }
+
+    #[test]
+    fn test_vote_state_v4_conversion() {
+        let vote_pubkey = Pubkey::new_unique();
+        let mut vote_state = VoteState::new_rand_for_tests(Pubkey::new_unique(), 42);
+        vote_state.commission = 7;
+        vote_state.authorized_voters = AuthorizedVoters::new(1, Pubkey::new_unique());
+
+        let vote_state_v4 = VoteStateV4::new_from_vote_state(&vote_pubkey, vote_state.clone());
+        assert_eq!(vote_state_v4.inflation_rewards_collector, vote_pubkey);
+        assert_eq!(
+            vote_state_v4.block_revenue_collector,
+            vote_state.node_pubkey
+        );
+        assert_eq!(vote_state_v4.inflation_rewards_commission_bps, 700);
+        assert_eq!(vote_state_v4.block_revenue_commission_bps, 10_000);
+        assert_eq!(vote_state_v4.commission(), 7);
+        assert_eq!(vote_state_v4.into_vote_state_lossy(), vote_state);
+
+        let vote_state_v4 = VoteStateV4 {
+            inflation_rewards_commission_bps: 1_299,
+            ..VoteStateV4::default()
+        };
+        assert_eq!(vote_state_v4.commission(), 12);
+        let vote_state_v4 = VoteStateV4 {
+            inflation_rewards_commission_bps: u16::MAX,
+            ..VoteStateV4::default()
+        };
+        assert_eq!(vote_state_v4.commission(), u8::MAX);
+    }
+
+    #[test]
+    fn test_vote_deserialize_v4() {
+        let vote_state = VoteStateV4 {
+            votes: [LandedVote::from(Lockout::new(1))].into_iter().collect(),
+            authorized_voters: AuthorizedVoters::new(1, Pubkey::new_unique()),
+            ..VoteStateV4::default()
+        };
+        let versioned = VoteStateVersions::new_v4(vote_state.clone());
+        let vote_state_buf = bincode::serialize(&versioned).unwrap();
+
+        // A V4 account is not silently converted to the older layout
+        assert_eq!(
+            versioned.try_convert_to_current(),
+            Err(InstructionError::InvalidAccountData)
+        );
+        assert_eq!(
+            VoteState::deserialize(&vote_state_buf),
+            Err(InstructionError::InvalidAccountData)
+        );
+        let mut test_vote_state = MaybeUninit::uninit();
+        assert_eq!(
+            VoteState::deserialize_into_uninit(&vote_state_buf, &mut test_vote_state),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        // Unless asked to
+        let VoteStateVersions::V4(deserialized) = bincode::deserialize(&vote_state_buf).unwrap()
+        else {
+            panic!("not a V4 vote state");
+        };
+        assert_eq!(
+            deserialized.clone().into_vote_state_lossy(),
+            vote_state.clone().into_vote_state_lossy()
+        );
+        #[allow(deprecated)]
+        let converted = VoteStateVersions::V4(deserialized).convert_to_current();
+        assert_eq!(converted, vote_state.into_vote_state_lossy());
+    }
+}
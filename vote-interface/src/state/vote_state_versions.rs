@@ -5,9 +5,10 @@ use {
         authorized_voters::AuthorizedVoters,
         state::{
             vote_state_0_23_5::VoteState0_23_5, vote_state_1_14_11::VoteState1_14_11, CircBuf,
-            LandedVote, Lockout, VoteState,
+            LandedVote, Lockout, VoteState, VoteStateV4,
         },
     },
+    solana_instruction::error::InstructionError,
     solana_pubkey::Pubkey,
     std::collections::VecDeque,
 };
@@ -21,6 +22,7 @@ pub enum VoteStateVersions {
     V0_23_5(Box<VoteState0_23_5>),
     V1_14_11(Box<VoteState1_14_11>),
     Current(Box<VoteState>),
+    V4(Box<VoteStateV4>),
 }
 
 impl VoteStateVersions {
@@ -28,8 +30,35 @@ impl VoteStateVersions {
         Self::Current(Box::new(vote_state))
     }
 
+    pub fn new_v4(vote_state: VoteStateV4) -> Self {
+        Self::V4(Box::new(vote_state))
+    }
+
+    /// Converts to the current [`VoteState`] layout.
+    ///
+    /// Lossy for [`VoteStateV4`], which is converted with
+    /// [`VoteStateV4::into_vote_state_lossy`]; use
+    /// [`VoteStateVersions::try_convert_to_current`] to reject it instead.
+    #[deprecated(
+        since = "2.3.0",
+        note = "Use `try_convert_to_current`, which rejects V4 vote state instead of dropping its fields"
+    )]
     pub fn convert_to_current(self) -> VoteState {
         match self {
+            VoteStateVersions::V4(state) => state.into_vote_state_lossy(),
+            versions => versions
+                .try_convert_to_current()
+                .expect("only V4 vote state fails to convert"),
+        }
+    }
+
+    /// Converts to the current [`VoteState`] layout.
+    ///
+    /// Fails with [`InstructionError::InvalidAccountData`] for
+    /// [`VoteStateV4`], which a [`VoteState`] cannot represent without
+    /// losing data; see [`VoteStateV4::into_vote_state_lossy`].
+    pub fn try_convert_to_current(self) -> Result<VoteState, InstructionError> {
+        Ok(match self {
             VoteStateVersions::V0_23_5(state) => {
                 let authorized_voters =
                     AuthorizedVoters::new(state.authorized_voter_epoch, state.authorized_voter);
@@ -74,7 +103,9 @@ impl VoteStateVersions {
             },
 
             VoteStateVersions::Current(state) => *state,
-        }
+
+            VoteStateVersions::V4(_) => return Err(InstructionError::InvalidAccountData),
+        })
     }
 
     fn landed_votes_from_lockouts(lockouts: VecDeque<Lockout>) -> VecDeque<LandedVote> {
@@ -90,6 +121,8 @@ impl VoteStateVersions {
             VoteStateVersions::V1_14_11(vote_state) => vote_state.authorized_voters.is_empty(),
 
             VoteStateVersions::Current(vote_state) => vote_state.authorized_voters.is_empty(),
+
+            VoteStateVersions::V4(vote_state) => vote_state.is_uninitialized(),
         }
     }
 
@@ -110,10 +143,11 @@ impl VoteStateVersions {
 #[cfg(test)]
 impl Arbitrary<'_> for VoteStateVersions {
     fn arbitrary(u: &mut Unstructured<'_>) -> arbitrary::Result<Self> {
-        let variant = u.choose_index(2)?;
+        let variant = u.choose_index(3)?;
         match variant {
             0 => Ok(Self::Current(Box::new(VoteState::arbitrary(u)?))),
             1 => Ok(Self::V1_14_11(Box::new(VoteState1_14_11::arbitrary(u)?))),
+            2 => Ok(Self::V4(Box::new(VoteStateV4::arbitrary(u)?))),
             _ => unreachable!(),
         }
     }
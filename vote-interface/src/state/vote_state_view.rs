@@ -0,0 +1,668 @@
+//! Read-only access to serialized vote state without deserializing it.
+//!
+//! [`VoteState::deserialize`](super::VoteState::deserialize) allocates the
+//! votes, authorized voters and epoch credits of an account. Scans over every
+//! vote account of a bank, such as summing the stake of the validators that
+//! voted recently, only need a few fields, which [`VoteStateView`] reads in
+//! place.
+
+use {
+    super::{vote_state_v4::BLS_PUBLIC_KEY_COMPRESSED_SIZE, BlockTimestamp, LandedVote, Lockout},
+    solana_clock::{Epoch, Slot},
+    solana_instruction::error::InstructionError,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+const VERSION_SIZE: usize = 4;
+const U16_SIZE: usize = 2;
+const U64_SIZE: usize = 8;
+const LOCKOUT_SIZE: usize = U64_SIZE + 4;
+const LATENCY_SIZE: usize = 1;
+const LANDED_VOTE_SIZE: usize = LATENCY_SIZE + LOCKOUT_SIZE;
+const AUTHORIZED_VOTER_SIZE: usize = U64_SIZE + PUBKEY_BYTES;
+const EPOCH_CREDITS_SIZE: usize = 3 * U64_SIZE;
+const BLOCK_TIMESTAMP_SIZE: usize = 2 * U64_SIZE;
+const PRIOR_VOTERS_ITEMS: usize = 32;
+// Items, `idx` and `is_empty`
+const PRIOR_VOTERS_SIZE: usize = PRIOR_VOTERS_ITEMS * (PUBKEY_BYTES + 2 * U64_SIZE) + U64_SIZE + 1;
+// Items with an additional slot, and `idx`
+const PRIOR_VOTERS_0_23_5_SIZE: usize =
+    PRIOR_VOTERS_ITEMS * (PUBKEY_BYTES + 3 * U64_SIZE) + U64_SIZE;
+
+const NODE_PUBKEY_OFFSET: usize = VERSION_SIZE;
+
+const V0_23_5_AUTHORIZED_VOTER_OFFSET: usize = NODE_PUBKEY_OFFSET + PUBKEY_BYTES;
+const V0_23_5_PRIOR_VOTERS_OFFSET: usize = V0_23_5_AUTHORIZED_VOTER_OFFSET + AUTHORIZED_VOTER_SIZE;
+const V0_23_5_AUTHORIZED_WITHDRAWER_OFFSET: usize =
+    V0_23_5_PRIOR_VOTERS_OFFSET + PRIOR_VOTERS_0_23_5_SIZE;
+const V0_23_5_COMMISSION_OFFSET: usize = V0_23_5_AUTHORIZED_WITHDRAWER_OFFSET + PUBKEY_BYTES;
+
+const AUTHORIZED_WITHDRAWER_OFFSET: usize = NODE_PUBKEY_OFFSET + PUBKEY_BYTES;
+const COMMISSION_OFFSET: usize = AUTHORIZED_WITHDRAWER_OFFSET + PUBKEY_BYTES;
+
+const V4_INFLATION_REWARDS_COLLECTOR_OFFSET: usize = AUTHORIZED_WITHDRAWER_OFFSET + PUBKEY_BYTES;
+const V4_BLOCK_REVENUE_COLLECTOR_OFFSET: usize =
+    V4_INFLATION_REWARDS_COLLECTOR_OFFSET + PUBKEY_BYTES;
+const V4_INFLATION_REWARDS_COMMISSION_BPS_OFFSET: usize =
+    V4_BLOCK_REVENUE_COLLECTOR_OFFSET + PUBKEY_BYTES;
+const V4_BLOCK_REVENUE_COMMISSION_BPS_OFFSET: usize =
+    V4_INFLATION_REWARDS_COMMISSION_BPS_OFFSET + U16_SIZE;
+const V4_PENDING_DELEGATOR_REWARDS_OFFSET: usize =
+    V4_BLOCK_REVENUE_COMMISSION_BPS_OFFSET + U16_SIZE;
+const V4_BLS_PUBKEY_OFFSET: usize = V4_PENDING_DELEGATOR_REWARDS_OFFSET + U64_SIZE;
+
+/// The serialized layout of vote state, one per
+/// [`VoteStateVersions`](super::VoteStateVersions) variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteStateLayout {
+    V0_23_5,
+    V1_14_11,
+    /// [`VoteState`](super::VoteState), serialized as
+    /// [`VoteStateVersions::Current`](super::VoteStateVersions::Current)
+    V3,
+    V4,
+}
+
+impl VoteStateLayout {
+    fn from_version(version: u32) -> Result<Self, InstructionError> {
+        match version {
+            0 => Ok(Self::V0_23_5),
+            1 => Ok(Self::V1_14_11),
+            2 => Ok(Self::V3),
+            3 => Ok(Self::V4),
+            _ => Err(InstructionError::InvalidAccountData),
+        }
+    }
+
+    /// Votes are serialized as `Lockout`s before V3, and as `LandedVote`s
+    /// since.
+    fn vote_size(self) -> usize {
+        match self {
+            Self::V0_23_5 | Self::V1_14_11 => LOCKOUT_SIZE,
+            Self::V3 | Self::V4 => LANDED_VOTE_SIZE,
+        }
+    }
+
+    /// Size of the latency that precedes the lockout of each vote.
+    fn latency_size(self) -> usize {
+        match self {
+            Self::V0_23_5 | Self::V1_14_11 => 0,
+            Self::V3 | Self::V4 => LATENCY_SIZE,
+        }
+    }
+}
+
+/// A zero-copy view of serialized vote state, in any of the
+/// [`VoteStateVersions`](super::VoteStateVersions) layouts.
+///
+/// The layout is validated and the offsets of the variable-length fields are
+/// computed once by [`VoteStateView::try_new`]; accessors then read the
+/// fields from the account data on demand. Fields that a layout lacks are
+/// reported as they would be after
+/// [`VoteStateVersions::try_convert_to_current`](super::VoteStateVersions::try_convert_to_current),
+/// or [`VoteStateV4::into_vote_state_lossy`](super::VoteStateV4::into_vote_state_lossy)
+/// for the V4 layout, except for those only in
+/// [`VoteStateV4`](super::VoteStateV4), which are `None` for older layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoteStateView<'a> {
+    data: &'a [u8],
+    layout: VoteStateLayout,
+    has_bls_pubkey: bool,
+    votes: Items,
+    root_slot_offset: usize,
+    authorized_voters: Items,
+    epoch_credits: Items,
+    last_timestamp_offset: usize,
+}
+
+/// The number of items of a collection and the range of bytes they span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Items {
+    len: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Reads the fields of a layout in order, checking that they are in bounds.
+struct Frame<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Frame<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], InstructionError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(InstructionError::InvalidAccountData)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn skip_to(&mut self, offset: usize) -> Result<(), InstructionError> {
+        let len = offset
+            .checked_sub(self.offset)
+            .ok_or(InstructionError::InvalidAccountData)?;
+        self.take(len).map(|_| ())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, InstructionError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, InstructionError> {
+        Ok(read_u64(self.take(U64_SIZE)?))
+    }
+
+    fn read_bool(&mut self) -> Result<bool, InstructionError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(InstructionError::InvalidAccountData),
+        }
+    }
+
+    /// Skips an optional value, returning whether it is present.
+    fn skip_option(&mut self, size: usize) -> Result<bool, InstructionError> {
+        let is_some = self.read_bool()?;
+        if is_some {
+            self.take(size)?;
+        }
+        Ok(is_some)
+    }
+
+    /// Skips a length-prefixed collection, returning where its items are.
+    fn skip_collection(&mut self, item_size: usize) -> Result<Items, InstructionError> {
+        let len =
+            usize::try_from(self.read_u64()?).map_err(|_| InstructionError::InvalidAccountData)?;
+        let start = self.offset;
+        self.take(
+            len.checked_mul(item_size)
+                .ok_or(InstructionError::InvalidAccountData)?,
+        )?;
+        Ok(Items {
+            len,
+            start,
+            end: self.offset,
+        })
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0; U64_SIZE];
+    buf.copy_from_slice(&bytes[..U64_SIZE]);
+    u64::from_le_bytes(buf)
+}
+
+fn read_i64(bytes: &[u8]) -> i64 {
+    let mut buf = [0; U64_SIZE];
+    buf.copy_from_slice(&bytes[..U64_SIZE]);
+    i64::from_le_bytes(buf)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(buf)
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_pubkey(bytes: &[u8]) -> Pubkey {
+    let mut buf = [0; PUBKEY_BYTES];
+    buf.copy_from_slice(&bytes[..PUBKEY_BYTES]);
+    Pubkey::new_from_array(buf)
+}
+
+impl<'a> VoteStateView<'a> {
+    /// Validates the layout of serialized vote state.
+    ///
+    /// Fails with [`InstructionError::InvalidAccountData`] if `data` is not a
+    /// serialized [`VoteStateVersions`](super::VoteStateVersions). Trailing
+    /// bytes, such as the unused space of a vote account, are ignored.
+    pub fn try_new(data: &'a [u8]) -> Result<Self, InstructionError> {
+        let mut frame = Frame { data, offset: 0 };
+        let layout = VoteStateLayout::from_version(read_u32(frame.take(VERSION_SIZE)?))?;
+        let mut has_bls_pubkey = false;
+        match layout {
+            VoteStateLayout::V0_23_5 => {
+                frame.skip_to(V0_23_5_COMMISSION_OFFSET + 1)?;
+            }
+            VoteStateLayout::V1_14_11 | VoteStateLayout::V3 => {
+                frame.skip_to(COMMISSION_OFFSET + 1)?;
+            }
+            VoteStateLayout::V4 => {
+                frame.skip_to(V4_BLS_PUBKEY_OFFSET)?;
+                has_bls_pubkey = frame.skip_option(BLS_PUBLIC_KEY_COMPRESSED_SIZE)?;
+            }
+        }
+
+        let votes = frame.skip_collection(layout.vote_size())?;
+        let root_slot_offset = frame.offset;
+        frame.skip_option(U64_SIZE)?;
+        let authorized_voters = match layout {
+            // A single authorized voter, stored as pubkey and epoch
+            VoteStateLayout::V0_23_5 => Items {
+                len: 1,
+                start: V0_23_5_AUTHORIZED_VOTER_OFFSET,
+                end: V0_23_5_PRIOR_VOTERS_OFFSET,
+            },
+            _ => frame.skip_collection(AUTHORIZED_VOTER_SIZE)?,
+        };
+        if matches!(layout, VoteStateLayout::V1_14_11 | VoteStateLayout::V3) {
+            frame.take(PRIOR_VOTERS_SIZE - 1)?;
+            // `is_empty`
+            frame.read_bool()?;
+        }
+        let epoch_credits = frame.skip_collection(EPOCH_CREDITS_SIZE)?;
+        let last_timestamp_offset = frame.offset;
+        frame.take(BLOCK_TIMESTAMP_SIZE)?;
+
+        Ok(Self {
+            data,
+            layout,
+            has_bls_pubkey,
+            votes,
+            root_slot_offset,
+            authorized_voters,
+            epoch_credits,
+            last_timestamp_offset,
+        })
+    }
+
+    pub fn layout(&self) -> VoteStateLayout {
+        self.layout
+    }
+
+    pub fn node_pubkey(&self) -> Pubkey {
+        read_pubkey(&self.data[NODE_PUBKEY_OFFSET..])
+    }
+
+    pub fn authorized_withdrawer(&self) -> Pubkey {
+        match self.layout {
+            VoteStateLayout::V0_23_5 => {
+                read_pubkey(&self.data[V0_23_5_AUTHORIZED_WITHDRAWER_OFFSET..])
+            }
+            _ => read_pubkey(&self.data[AUTHORIZED_WITHDRAWER_OFFSET..]),
+        }
+    }
+
+    /// The commission as a percentage; for V4, the inflation rewards
+    /// commission rounded down, as
+    /// [`VoteStateV4::commission`](super::VoteStateV4::commission).
+    pub fn commission(&self) -> u8 {
+        match self.layout {
+            VoteStateLayout::V0_23_5 => self.data[V0_23_5_COMMISSION_OFFSET],
+            VoteStateLayout::V1_14_11 | VoteStateLayout::V3 => self.data[COMMISSION_OFFSET],
+            VoteStateLayout::V4 => {
+                let bps = read_u16(&self.data[V4_INFLATION_REWARDS_COMMISSION_BPS_OFFSET..]);
+                u8::try_from(bps / 100).unwrap_or(u8::MAX)
+            }
+        }
+    }
+
+    /// The inflation rewards commission in basis points; for older layouts,
+    /// the commission converted to basis points.
+    pub fn inflation_rewards_commission_bps(&self) -> u16 {
+        match self.layout {
+            VoteStateLayout::V4 => {
+                read_u16(&self.data[V4_INFLATION_REWARDS_COMMISSION_BPS_OFFSET..])
+            }
+            _ => u16::from(self.commission()).saturating_mul(100),
+        }
+    }
+
+    fn v4_field<T>(&self, read: impl FnOnce(&'a [u8]) -> T) -> Option<T> {
+        (self.layout == VoteStateLayout::V4).then(|| read(self.data))
+    }
+
+    pub fn inflation_rewards_collector(&self) -> Option<Pubkey> {
+        self.v4_field(|data| read_pubkey(&data[V4_INFLATION_REWARDS_COLLECTOR_OFFSET..]))
+    }
+
+    pub fn block_revenue_collector(&self) -> Option<Pubkey> {
+        self.v4_field(|data| read_pubkey(&data[V4_BLOCK_REVENUE_COLLECTOR_OFFSET..]))
+    }
+
+    pub fn block_revenue_commission_bps(&self) -> Option<u16> {
+        self.v4_field(|data| read_u16(&data[V4_BLOCK_REVENUE_COMMISSION_BPS_OFFSET..]))
+    }
+
+    pub fn pending_delegator_rewards(&self) -> Option<u64> {
+        self.v4_field(|data| read_u64(&data[V4_PENDING_DELEGATOR_REWARDS_OFFSET..]))
+    }
+
+    pub fn bls_pubkey_compressed(&self) -> Option<&'a [u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]> {
+        self.has_bls_pubkey.then(|| {
+            // Skip the `Option` tag
+            self.data[V4_BLS_PUBKEY_OFFSET..][1..][..BLS_PUBLIC_KEY_COMPRESSED_SIZE]
+                .try_into()
+                .expect("slice has the size of a BLS pubkey")
+        })
+    }
+
+    pub fn votes_len(&self) -> usize {
+        self.votes.len
+    }
+
+    /// Votes, oldest first. Layouts before V3 do not record latency, which
+    /// is reported as 0.
+    pub fn votes(&self) -> impl DoubleEndedIterator<Item = LandedVote> + ExactSizeIterator + 'a {
+        let latency_size = self.layout.latency_size();
+        self.data[self.votes.start..self.votes.end]
+            .chunks_exact(self.layout.vote_size())
+            .map(move |vote| {
+                let lockout = &vote[latency_size..];
+                LandedVote {
+                    latency: if latency_size == 0 { 0 } else { vote[0] },
+                    lockout: Lockout::new_with_confirmation_count(
+                        read_u64(lockout),
+                        read_u32(&lockout[U64_SIZE..]),
+                    ),
+                }
+            })
+    }
+
+    pub fn last_voted_slot(&self) -> Option<Slot> {
+        self.votes().next_back().map(|vote| vote.slot())
+    }
+
+    pub fn root_slot(&self) -> Option<Slot> {
+        (self.data[self.root_slot_offset] == 1)
+            .then(|| read_u64(&self.data[self.root_slot_offset..][1..]))
+    }
+
+    /// Authorized voters with the epoch from which they may vote, in
+    /// ascending epoch order.
+    pub fn authorized_voters(&self) -> impl ExactSizeIterator<Item = (Epoch, Pubkey)> + 'a {
+        let (epoch_offset, pubkey_offset) = match self.layout {
+            VoteStateLayout::V0_23_5 => (PUBKEY_BYTES, 0),
+            _ => (0, U64_SIZE),
+        };
+        self.data[self.authorized_voters.start..self.authorized_voters.end]
+            .chunks_exact(AUTHORIZED_VOTER_SIZE)
+            .map(move |voter| {
+                (
+                    read_u64(&voter[epoch_offset..]),
+                    read_pubkey(&voter[pubkey_offset..]),
+                )
+            })
+    }
+
+    /// History of `(epoch, credits, prev_credits)`, oldest first.
+    pub fn epoch_credits(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (Epoch, u64, u64)> + ExactSizeIterator + 'a {
+        self.data[self.epoch_credits.start..self.epoch_credits.end]
+            .chunks_exact(EPOCH_CREDITS_SIZE)
+            .map(|credits| {
+                (
+                    read_u64(credits),
+                    read_u64(&credits[U64_SIZE..]),
+                    read_u64(&credits[2 * U64_SIZE..]),
+                )
+            })
+    }
+
+    /// Number of credits owed to this account, as
+    /// [`VoteState::credits`](super::VoteState::credits).
+    pub fn credits(&self) -> u64 {
+        self.epoch_credits()
+            .next_back()
+            .map_or(0, |(_, credits, _)| credits)
+    }
+
+    pub fn last_timestamp(&self) -> BlockTimestamp {
+        let timestamp = &self.data[self.last_timestamp_offset..];
+        BlockTimestamp {
+            slot: read_u64(timestamp),
+            timestamp: read_i64(&timestamp[U64_SIZE..]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            authorized_voters::AuthorizedVoters,
+            state::{
+                vote_state_0_23_5::{CircBuf as CircBuf0_23_5, VoteState0_23_5},
+                VoteState, VoteState1_14_11, VoteStateV4, VoteStateVersions, MAX_LOCKOUT_HISTORY,
+            },
+        },
+        arbitrary::{Arbitrary, Unstructured},
+    };
+
+    fn assert_view_eq_current(view: &VoteStateView, vote_state: &VoteState) {
+        assert_eq!(view.node_pubkey(), vote_state.node_pubkey);
+        assert_eq!(
+            view.authorized_withdrawer(),
+            vote_state.authorized_withdrawer
+        );
+        assert_eq!(view.commission(), vote_state.commission);
+        assert_eq!(view.votes_len(), vote_state.votes.len());
+        assert!(view.votes().eq(vote_state.votes.iter().cloned()));
+        assert_eq!(view.last_voted_slot(), vote_state.last_voted_slot());
+        assert_eq!(view.root_slot(), vote_state.root_slot);
+        assert!(view.authorized_voters().eq(vote_state
+            .authorized_voters
+            .iter()
+            .map(|(epoch, voter)| (*epoch, *voter))));
+        assert!(view
+            .epoch_credits()
+            .eq(vote_state.epoch_credits.iter().copied()));
+        assert_eq!(view.credits(), vote_state.credits());
+        assert_eq!(view.last_timestamp(), vote_state.last_timestamp);
+    }
+
+    fn assert_view_eq(view: &VoteStateView, versioned: &VoteStateVersions) {
+        let vote_state = match versioned.clone() {
+            VoteStateVersions::V4(vote_state) => vote_state.into_vote_state_lossy(),
+            versioned => versioned.try_convert_to_current().unwrap(),
+        };
+        assert_view_eq_current(view, &vote_state);
+        match versioned {
+            VoteStateVersions::V4(vote_state) => {
+                assert_eq!(view.layout(), VoteStateLayout::V4);
+                assert_eq!(
+                    view.inflation_rewards_collector(),
+                    Some(vote_state.inflation_rewards_collector)
+                );
+                assert_eq!(
+                    view.block_revenue_collector(),
+                    Some(vote_state.block_revenue_collector)
+                );
+                assert_eq!(
+                    view.inflation_rewards_commission_bps(),
+                    vote_state.inflation_rewards_commission_bps
+                );
+                assert_eq!(
+                    view.block_revenue_commission_bps(),
+                    Some(vote_state.block_revenue_commission_bps)
+                );
+                assert_eq!(
+                    view.pending_delegator_rewards(),
+                    Some(vote_state.pending_delegator_rewards)
+                );
+                assert_eq!(
+                    view.bls_pubkey_compressed(),
+                    vote_state.bls_pubkey_compressed.as_ref()
+                );
+            }
+            _ => {
+                assert_eq!(
+                    view.inflation_rewards_commission_bps(),
+                    u16::from(view.commission()).saturating_mul(100)
+                );
+                assert_eq!(view.inflation_rewards_collector(), None);
+                assert_eq!(view.block_revenue_collector(), None);
+                assert_eq!(view.block_revenue_commission_bps(), None);
+                assert_eq!(view.pending_delegator_rewards(), None);
+                assert_eq!(view.bls_pubkey_compressed(), None);
+            }
+        }
+    }
+
+    fn vote_state_0_23_5() -> VoteState0_23_5 {
+        let mut prior_voters = CircBuf0_23_5::default();
+        prior_voters.append((Pubkey::new_unique(), 1, 2, 3));
+        VoteState0_23_5 {
+            node_pubkey: Pubkey::new_unique(),
+            authorized_voter: Pubkey::new_unique(),
+            authorized_voter_epoch: 4,
+            prior_voters,
+            authorized_withdrawer: Pubkey::new_unique(),
+            commission: 10,
+            votes: (5..10).map(Lockout::new).collect(),
+            root_slot: Some(4),
+            epoch_credits: vec![(3, 20, 10), (4, 30, 20)],
+            last_timestamp: BlockTimestamp {
+                slot: 9,
+                timestamp: 1_000,
+            },
+        }
+    }
+
+    #[test]
+    fn test_vote_state_view() {
+        let mut vote_state = VoteState::new_rand_for_tests(Pubkey::new_unique(), 12);
+        vote_state.commission = 5;
+        vote_state.authorized_voters = AuthorizedVoters::new(7, Pubkey::new_unique());
+        vote_state.authorized_voters.insert(9, Pubkey::new_unique());
+        vote_state.epoch_credits = vec![(1, 10, 0), (2, 25, 10)];
+        vote_state.last_timestamp = BlockTimestamp {
+            slot: 19,
+            timestamp: -5,
+        };
+        let vote_state_v4 = VoteStateV4 {
+            bls_pubkey_compressed: Some([3; BLS_PUBLIC_KEY_COMPRESSED_SIZE]),
+            inflation_rewards_commission_bps: 550,
+            ..VoteStateV4::new_from_vote_state(&Pubkey::new_unique(), vote_state.clone())
+        };
+
+        for (versioned, layout) in [
+            (
+                VoteStateVersions::V0_23_5(Box::new(vote_state_0_23_5())),
+                VoteStateLayout::V0_23_5,
+            ),
+            (
+                VoteStateVersions::V1_14_11(Box::new(VoteState1_14_11::from(vote_state.clone()))),
+                VoteStateLayout::V1_14_11,
+            ),
+            (
+                VoteStateVersions::new_current(vote_state.clone()),
+                VoteStateLayout::V3,
+            ),
+            (
+                VoteStateVersions::new_v4(vote_state_v4),
+                VoteStateLayout::V4,
+            ),
+            (
+                VoteStateVersions::new_v4(VoteStateV4::default()),
+                VoteStateLayout::V4,
+            ),
+        ] {
+            let mut data = bincode::serialize(&versioned).unwrap();
+            data.resize(VoteState::size_of(), 0);
+            let view = VoteStateView::try_new(&data).unwrap();
+            assert_eq!(view.layout(), layout);
+            assert_view_eq(&view, &versioned);
+        }
+
+        let data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+        let view = VoteStateView::try_new(&data).unwrap();
+        assert_eq!(view.commission(), 5);
+        assert_eq!(
+            view.last_voted_slot(),
+            Some(12 + MAX_LOCKOUT_HISTORY as u64)
+        );
+        assert_eq!(view.credits(), 25);
+    }
+
+    #[test]
+    fn test_vote_state_view_arbitrary() {
+        let struct_bytes_x4 = std::mem::size_of::<VoteState>() * 4;
+        for _ in 0..1000 {
+            let raw_data: Vec<u8> = (0..struct_bytes_x4).map(|_| rand::random::<u8>()).collect();
+            let mut unstructured = Unstructured::new(&raw_data);
+            let versioned = VoteStateVersions::arbitrary(&mut unstructured).unwrap();
+
+            let data = bincode::serialize(&versioned).unwrap();
+            let view = VoteStateView::try_new(&data).unwrap();
+            assert_view_eq(&view, &versioned);
+        }
+    }
+
+    #[test]
+    fn test_vote_state_view_invalid() {
+        let vote_state = VoteState {
+            votes: [LandedVote::from(Lockout::new(1))].into_iter().collect(),
+            root_slot: Some(0),
+            epoch_credits: vec![(0, 1, 0)],
+            ..VoteState::default()
+        };
+        for versioned in [
+            VoteStateVersions::V0_23_5(Box::new(vote_state_0_23_5())),
+            VoteStateVersions::V1_14_11(Box::new(VoteState1_14_11::from(vote_state.clone()))),
+            VoteStateVersions::new_current(vote_state.clone()),
+            VoteStateVersions::new_v4(VoteStateV4::new_from_vote_state(
+                &Pubkey::new_unique(),
+                vote_state.clone(),
+            )),
+        ] {
+            let data = bincode::serialize(&versioned).unwrap();
+            for len in 0..data.len() {
+                assert_eq!(
+                    VoteStateView::try_new(&data[..len]),
+                    Err(InstructionError::InvalidAccountData)
+                );
+            }
+        }
+
+        let data = bincode::serialize(&VoteStateVersions::new_v4(
+            VoteStateV4::new_from_vote_state(&Pubkey::new_unique(), vote_state.clone()),
+        ))
+        .unwrap();
+
+        // unknown version
+        let mut unknown_version = data.clone();
+        unknown_version[0] = 4;
+        assert_eq!(
+            VoteStateView::try_new(&unknown_version),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        // invalid option tag
+        let mut invalid_option = data.clone();
+        invalid_option[V4_BLS_PUBKEY_OFFSET] = 2;
+        assert_eq!(
+            VoteStateView::try_new(&invalid_option),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        // vote count larger than the data
+        let mut invalid_count = data;
+        invalid_count[V4_BLS_PUBKEY_OFFSET + 1..V4_BLS_PUBKEY_OFFSET + 1 + U64_SIZE]
+            .copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            VoteStateView::try_new(&invalid_count),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        // invalid `is_empty` of prior voters
+        let mut data = bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap();
+        let is_empty_offset = data.len() - BLOCK_TIMESTAMP_SIZE - EPOCH_CREDITS_SIZE - U64_SIZE - 1;
+        assert_eq!(data[is_empty_offset], 1);
+        data[is_empty_offset] = 2;
+        assert_eq!(
+            VoteStateView::try_new(&data),
+            Err(InstructionError::InvalidAccountData)
+        );
+    }
+}
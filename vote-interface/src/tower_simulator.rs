@@ -0,0 +1,630 @@
+//! Replaying vote histories against a fork structure.
+//!
+//! A [`TowerSimulator`] applies votes to a [`VoteState`] the way the vote
+//! program does, over a [`SlotTree`] of the forks a validator observed. For
+//! every vote it reports the resulting lockout stack, whether the root
+//! advanced, whether the vote switched forks, which requires a switching
+//! proof, and whether it violated the lockouts of earlier votes.
+//!
+//! Votes can be cast one by one, decided by a [`VotingPolicy`] for each slot
+//! that a validator sees, or replayed from the [`TowerSync`] instructions a
+//! validator sent.
+
+use {
+    crate::state::{Lockout, TowerSync, VoteState},
+    solana_clock::Slot,
+    std::{collections::BTreeMap, fmt},
+};
+
+/// Errors building a [`SlotTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlotTreeError {
+    /// The slot is already in the tree.
+    DuplicateSlot(Slot),
+    /// The parent of the slot is not in the tree.
+    UnknownParent { slot: Slot, parent: Slot },
+    /// The slot is not greater than its parent.
+    SlotNotAfterParent { slot: Slot, parent: Slot },
+}
+
+impl std::error::Error for SlotTreeError {}
+
+impl fmt::Display for SlotTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateSlot(slot) => write!(f, "slot {slot} is already in the tree"),
+            Self::UnknownParent { slot, parent } => {
+                write!(f, "parent {parent} of slot {slot} is not in the tree")
+            }
+            Self::SlotNotAfterParent { slot, parent } => {
+                write!(f, "slot {slot} is not after its parent {parent}")
+            }
+        }
+    }
+}
+
+/// The forks descending from a root slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotTree {
+    root: Slot,
+    parents: BTreeMap<Slot, Slot>,
+}
+
+impl SlotTree {
+    pub fn new(root: Slot) -> Self {
+        Self {
+            root,
+            parents: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a tree from `(slot, parent)` pairs, with parents before their
+    /// children.
+    pub fn from_edges(
+        root: Slot,
+        edges: impl IntoIterator<Item = (Slot, Slot)>,
+    ) -> Result<Self, SlotTreeError> {
+        let mut tree = Self::new(root);
+        for (slot, parent) in edges {
+            tree.insert(slot, parent)?;
+        }
+        Ok(tree)
+    }
+
+    pub fn insert(&mut self, slot: Slot, parent: Slot) -> Result<(), SlotTreeError> {
+        if self.contains(slot) {
+            return Err(SlotTreeError::DuplicateSlot(slot));
+        }
+        if !self.contains(parent) {
+            return Err(SlotTreeError::UnknownParent { slot, parent });
+        }
+        if slot <= parent {
+            return Err(SlotTreeError::SlotNotAfterParent { slot, parent });
+        }
+        self.parents.insert(slot, parent);
+        Ok(())
+    }
+
+    pub fn root(&self) -> Slot {
+        self.root
+    }
+
+    pub fn contains(&self, slot: Slot) -> bool {
+        slot == self.root || self.parents.contains_key(&slot)
+    }
+
+    pub fn parent(&self, slot: Slot) -> Option<Slot> {
+        self.parents.get(&slot).copied()
+    }
+
+    /// Ancestors of `slot`, from its parent to the root.
+    pub fn ancestors(&self, slot: Slot) -> impl Iterator<Item = Slot> + '_ {
+        std::iter::successors(self.parent(slot), |slot| self.parent(*slot))
+    }
+
+    /// Whether `slot` is `ancestor` or one of its descendants.
+    pub fn is_same_fork(&self, ancestor: Slot, slot: Slot) -> bool {
+        self.contains(slot)
+            && (ancestor == slot || self.ancestors(slot).any(|parent| parent == ancestor))
+    }
+
+    /// The greatest slot that is `a` or `b` or an ancestor of both.
+    pub fn common_ancestor(&self, a: Slot, b: Slot) -> Option<Slot> {
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+        std::iter::once(a)
+            .chain(self.ancestors(a))
+            .find(|ancestor| self.is_same_fork(*ancestor, b))
+    }
+
+    /// Slots without children, in ascending order.
+    pub fn leaves(&self) -> Vec<Slot> {
+        let mut leaves = std::iter::once(self.root)
+            .chain(self.parents.keys().copied())
+            .collect::<Vec<_>>();
+        leaves.retain(|slot| !self.parents.values().any(|parent| parent == slot));
+        leaves
+    }
+}
+
+/// A vote that switches from the fork of the last vote to another one.
+///
+/// Validators must observe enough stake voting on other forks, a switching
+/// proof, before casting such a vote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwitchRequirement {
+    pub last_voted_slot: Slot,
+    pub slot: Slot,
+    /// Where the two forks diverge.
+    pub common_ancestor: Option<Slot>,
+}
+
+/// A vote that breaks the rules of the tower.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TowerViolation {
+    /// The slot is not in the slot tree.
+    UnknownSlot(Slot),
+    /// The slot does not descend from the root of the tower.
+    NotDescendantOfRoot { root: Slot, slot: Slot },
+    /// The slot is on another fork than a vote that is still locked out.
+    LockedOut { vote: Lockout, slot: Slot },
+    /// A [`TowerSync`] proposed a root older than the root of the tower, or
+    /// no root at all.
+    RootRollback { root: Slot, proposed: Option<Slot> },
+    /// Applying a [`TowerSync`] did not produce the proposed tower.
+    ProposedTowerMismatch {
+        proposed: Vec<Lockout>,
+        actual: Vec<Lockout>,
+    },
+}
+
+/// The outcome of a vote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteReport {
+    pub slot: Slot,
+    /// Whether the vote was applied. Votes for slots not after the last vote
+    /// are ignored, as by the vote program, and a [`VotingPolicy`] may
+    /// decline to vote.
+    pub voted: bool,
+    /// The lockout stack after the vote.
+    pub lockouts: Vec<Lockout>,
+    pub root: Option<Slot>,
+    /// Whether the vote advanced the root.
+    pub root_advanced: bool,
+    pub switch: Option<SwitchRequirement>,
+    pub violations: Vec<TowerViolation>,
+}
+
+/// Decides whether to vote on slots as they are observed.
+pub trait VotingPolicy {
+    fn should_vote(&mut self, simulator: &TowerSimulator, slot: Slot) -> bool;
+}
+
+impl<F: FnMut(&TowerSimulator, Slot) -> bool> VotingPolicy for F {
+    fn should_vote(&mut self, simulator: &TowerSimulator, slot: Slot) -> bool {
+        self(simulator, slot)
+    }
+}
+
+/// Votes on every slot, regardless of lockouts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AlwaysVote;
+
+impl VotingPolicy for AlwaysVote {
+    fn should_vote(&mut self, _simulator: &TowerSimulator, _slot: Slot) -> bool {
+        true
+    }
+}
+
+/// Votes on slots that neither violate lockouts nor switch forks, and, if
+/// `allow_switching`, on slots that switch forks without violating lockouts,
+/// assuming a switching proof is available.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RespectLockouts {
+    pub allow_switching: bool,
+}
+
+impl VotingPolicy for RespectLockouts {
+    fn should_vote(&mut self, simulator: &TowerSimulator, slot: Slot) -> bool {
+        let (violations, switch) = simulator.check_vote(slot);
+        violations.is_empty() && (self.allow_switching || switch.is_none())
+    }
+}
+
+/// Applies votes to a tower over a [`SlotTree`].
+#[derive(Debug, Clone)]
+pub struct TowerSimulator {
+    slot_tree: SlotTree,
+    vote_state: VoteState,
+}
+
+impl TowerSimulator {
+    pub fn new(slot_tree: SlotTree) -> Self {
+        Self {
+            slot_tree,
+            vote_state: VoteState::default(),
+        }
+    }
+
+    pub fn slot_tree(&self) -> &SlotTree {
+        &self.slot_tree
+    }
+
+    pub fn slot_tree_mut(&mut self) -> &mut SlotTree {
+        &mut self.slot_tree
+    }
+
+    pub fn vote_state(&self) -> &VoteState {
+        &self.vote_state
+    }
+
+    pub fn lockouts(&self) -> Vec<Lockout> {
+        self.vote_state
+            .votes
+            .iter()
+            .map(|vote| vote.lockout)
+            .collect()
+    }
+
+    pub fn root(&self) -> Option<Slot> {
+        self.vote_state.root_slot
+    }
+
+    /// The violations and switching requirement of a vote on `slot`, without
+    /// applying it.
+    pub fn check_vote(&self, slot: Slot) -> (Vec<TowerViolation>, Option<SwitchRequirement>) {
+        if !self.slot_tree.contains(slot) {
+            return (vec![TowerViolation::UnknownSlot(slot)], None);
+        }
+
+        let mut violations = vec![];
+        if let Some(root) = self.root() {
+            if !self.slot_tree.is_same_fork(root, slot) {
+                violations.push(TowerViolation::NotDescendantOfRoot { root, slot });
+            }
+        }
+        // Votes that the vote program would not pop must be on the same fork
+        let mut locked_out = self.lockouts();
+        while locked_out
+            .last()
+            .is_some_and(|vote| !vote.is_locked_out_at_slot(slot))
+        {
+            locked_out.pop();
+        }
+        violations.extend(
+            locked_out
+                .into_iter()
+                .filter(|vote| !self.slot_tree.is_same_fork(vote.slot(), slot))
+                .map(|vote| TowerViolation::LockedOut { vote, slot }),
+        );
+
+        let switch = self
+            .vote_state
+            .last_voted_slot()
+            .filter(|last_voted_slot| {
+                *last_voted_slot < slot && !self.slot_tree.is_same_fork(*last_voted_slot, slot)
+            })
+            .map(|last_voted_slot| SwitchRequirement {
+                last_voted_slot,
+                slot,
+                common_ancestor: self.slot_tree.common_ancestor(last_voted_slot, slot),
+            });
+
+        (violations, switch)
+    }
+
+    /// Votes on `slot`, landing in `current_slot`.
+    ///
+    /// The vote is applied even if it violates lockouts, as a validator
+    /// with a faulty tower would.
+    pub fn vote(&mut self, slot: Slot, current_slot: Slot) -> VoteReport {
+        let voted = match self.vote_state.last_voted_slot() {
+            Some(last_voted_slot) => slot > last_voted_slot,
+            None => true,
+        };
+        let (violations, switch) = if voted {
+            self.check_vote(slot)
+        } else {
+            (vec![], None)
+        };
+        let root = self.root();
+        if voted {
+            self.vote_state
+                .process_next_vote_slot(slot, 0, current_slot);
+        }
+        self.report(slot, voted, root, switch, violations)
+    }
+
+    /// Offers every slot of `slots` to `policy`, voting on those it accepts
+    /// with votes landing in the next slot.
+    pub fn run_policy(
+        &mut self,
+        policy: &mut impl VotingPolicy,
+        slots: impl IntoIterator<Item = Slot>,
+    ) -> Vec<VoteReport> {
+        slots
+            .into_iter()
+            .map(|slot| {
+                if policy.should_vote(self, slot) {
+                    self.vote(slot, slot.saturating_add(1))
+                } else {
+                    let root = self.root();
+                    self.report(slot, false, root, None, vec![])
+                }
+            })
+            .collect()
+    }
+
+    /// Applies the votes of a [`TowerSync`] newer than the last vote, landing
+    /// in `current_slot`, and checks that the result is the proposed tower.
+    ///
+    /// As in the vote program, the root of the tower becomes the proposed
+    /// root, and the votes it roots are dropped, unless the proposed root
+    /// rolls the current one back.
+    pub fn apply_tower_sync(&mut self, tower_sync: &TowerSync, current_slot: Slot) -> VoteReport {
+        let root = self.root();
+        let last_voted_slot = self.vote_state.last_voted_slot();
+        let mut voted = false;
+        let mut switch = None;
+        let mut violations = vec![];
+        for lockout in &tower_sync.lockouts {
+            if last_voted_slot.is_some_and(|last_voted_slot| lockout.slot() <= last_voted_slot) {
+                continue;
+            }
+            let report = self.vote(lockout.slot(), current_slot);
+            voted |= report.voted;
+            switch = switch.or(report.switch);
+            violations.extend(report.violations);
+        }
+
+        match root {
+            Some(root) if !matches!(tower_sync.root, Some(proposed) if proposed >= root) => {
+                violations.push(TowerViolation::RootRollback {
+                    root,
+                    proposed: tower_sync.root,
+                });
+            }
+            _ => {
+                self.vote_state.root_slot = tower_sync.root;
+                if let Some(proposed) = tower_sync.root {
+                    self.vote_state.votes.retain(|vote| vote.slot() > proposed);
+                }
+            }
+        }
+
+        let actual = self.lockouts();
+        if !actual.iter().eq(&tower_sync.lockouts) {
+            violations.push(TowerViolation::ProposedTowerMismatch {
+                proposed: tower_sync.lockouts.iter().copied().collect(),
+                actual,
+            });
+        }
+        let slot = tower_sync
+            .last_voted_slot()
+            .or(last_voted_slot)
+            .unwrap_or_default();
+        self.report(slot, voted, root, switch, violations)
+    }
+
+    /// Applies a stream of [`TowerSync`]s, each landing in the slot after its
+    /// last vote.
+    pub fn replay_tower_syncs<'a>(
+        &mut self,
+        tower_syncs: impl IntoIterator<Item = &'a TowerSync>,
+    ) -> Vec<VoteReport> {
+        tower_syncs
+            .into_iter()
+            .map(|tower_sync| {
+                let current_slot = tower_sync
+                    .last_voted_slot()
+                    .unwrap_or_default()
+                    .saturating_add(1);
+                self.apply_tower_sync(tower_sync, current_slot)
+            })
+            .collect()
+    }
+
+    fn report(
+        &self,
+        slot: Slot,
+        voted: bool,
+        previous_root: Option<Slot>,
+        switch: Option<SwitchRequirement>,
+        violations: Vec<TowerViolation>,
+    ) -> VoteReport {
+        VoteReport {
+            slot,
+            voted,
+            lockouts: self.lockouts(),
+            root: self.root(),
+            root_advanced: self.root() != previous_root,
+            switch,
+            violations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::state::MAX_LOCKOUT_HISTORY};
+
+    //  0 - 1 - 2 - 3 - 4 - 8 - 9 - ... - 40
+    //       \
+    //        5 - 6 - 7
+    fn slot_tree() -> SlotTree {
+        let edges = [
+            (1, 0),
+            (2, 1),
+            (3, 2),
+            (4, 3),
+            (5, 1),
+            (6, 5),
+            (7, 6),
+            (8, 4),
+        ];
+        SlotTree::from_edges(
+            0,
+            edges
+                .into_iter()
+                .chain((9..=40u64).map(|slot| (slot, slot.saturating_sub(1)))),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_slot_tree() {
+        let tree = slot_tree();
+        assert_eq!(tree.parent(8), Some(4));
+        assert!(tree.is_same_fork(1, 7));
+        assert!(!tree.is_same_fork(2, 7));
+        assert!(tree.is_same_fork(7, 7));
+        assert_eq!(tree.common_ancestor(4, 7), Some(1));
+        assert_eq!(tree.common_ancestor(4, 2), Some(2));
+        assert_eq!(tree.common_ancestor(4, 41), None);
+        assert_eq!(tree.leaves(), vec![7, 40]);
+
+        let mut tree = SlotTree::new(0);
+        assert_eq!(
+            tree.insert(2, 1),
+            Err(SlotTreeError::UnknownParent { slot: 2, parent: 1 })
+        );
+        tree.insert(1, 0).unwrap();
+        assert_eq!(tree.insert(1, 0), Err(SlotTreeError::DuplicateSlot(1)));
+        assert_eq!(tree.insert(0, 1), Err(SlotTreeError::DuplicateSlot(0)));
+    }
+
+    #[test]
+    fn test_lockout_violation_and_switch() {
+        let mut simulator = TowerSimulator::new(slot_tree());
+        for slot in 1..=4 {
+            let report = simulator.vote(slot, slot + 1);
+            assert!(report.voted);
+            assert!(report.violations.is_empty());
+            assert_eq!(report.switch, None);
+        }
+        assert_eq!(
+            simulator.lockouts(),
+            vec![
+                Lockout::new_with_confirmation_count(1, 4),
+                Lockout::new_with_confirmation_count(2, 3),
+                Lockout::new_with_confirmation_count(3, 2),
+                Lockout::new_with_confirmation_count(4, 1),
+            ]
+        );
+
+        // Votes for 2, 3 and 4 are still locked out at slot 5
+        let report = simulator.clone().vote(5, 6);
+        assert_eq!(
+            report.switch,
+            Some(SwitchRequirement {
+                last_voted_slot: 4,
+                slot: 5,
+                common_ancestor: Some(1),
+            })
+        );
+        assert_eq!(
+            report.violations,
+            vec![
+                TowerViolation::LockedOut {
+                    vote: Lockout::new_with_confirmation_count(2, 3),
+                    slot: 5,
+                },
+                TowerViolation::LockedOut {
+                    vote: Lockout::new_with_confirmation_count(3, 2),
+                    slot: 5,
+                },
+                TowerViolation::LockedOut {
+                    vote: Lockout::new_with_confirmation_count(4, 1),
+                    slot: 5,
+                },
+            ]
+        );
+
+        // A policy respecting lockouts does not vote on the other fork
+        let reports = simulator.run_policy(
+            &mut RespectLockouts {
+                allow_switching: true,
+            },
+            [5, 6, 7, 8],
+        );
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| report.voted)
+                .collect::<Vec<_>>(),
+            vec![false, false, false, true]
+        );
+        assert!(reports.iter().all(|report| report.violations.is_empty()));
+    }
+
+    #[test]
+    fn test_root_advancement() {
+        let mut simulator = TowerSimulator::new(slot_tree());
+        let reports = simulator.run_policy(&mut AlwaysVote, (1..=4).chain(8..=40));
+        let rooted = reports
+            .iter()
+            .filter(|report| report.root_advanced)
+            .map(|report| (report.slot, report.root))
+            .collect::<Vec<_>>();
+        // The votes for 3 and 4 expire when voting on 8
+        assert_eq!(rooted[0], (37, Some(1)));
+        assert_eq!(rooted.last(), Some(&(40, Some(9))));
+        assert_eq!(simulator.lockouts().len(), MAX_LOCKOUT_HISTORY);
+
+        // Votes for slots descending from another fork than the root
+        let report = simulator.vote(7, 41);
+        assert!(!report.voted);
+        let mut simulator = TowerSimulator::new(slot_tree());
+        simulator.vote_state.root_slot = Some(2);
+        assert_eq!(
+            simulator.vote(7, 8).violations,
+            vec![TowerViolation::NotDescendantOfRoot { root: 2, slot: 7 }]
+        );
+    }
+
+    #[test]
+    fn test_replay_tower_syncs() {
+        let mut simulator = TowerSimulator::new(slot_tree());
+        let tower_syncs = [
+            TowerSync::from(vec![(1, 2), (2, 1)]),
+            TowerSync::from(vec![(1, 3), (2, 2), (3, 1)]),
+            // Drops the vote for 3 instead of keeping it locked out
+            TowerSync::from(vec![(1, 3), (2, 2), (4, 1)]),
+            TowerSync::from(vec![(1, 2), (5, 1)]),
+        ];
+        let reports = simulator.replay_tower_syncs(&tower_syncs);
+        assert!(reports[0].violations.is_empty());
+        assert!(reports[1].violations.is_empty());
+        assert_eq!(
+            reports[2].violations,
+            vec![TowerViolation::ProposedTowerMismatch {
+                proposed: tower_syncs[2].lockouts.iter().copied().collect(),
+                actual: vec![
+                    Lockout::new_with_confirmation_count(1, 4),
+                    Lockout::new_with_confirmation_count(2, 3),
+                    Lockout::new_with_confirmation_count(3, 2),
+                    Lockout::new_with_confirmation_count(4, 1),
+                ],
+            }]
+        );
+        assert_eq!(
+            reports[3].switch.map(|switch| switch.common_ancestor),
+            Some(Some(1))
+        );
+        assert!(reports[3].violations.contains(&TowerViolation::LockedOut {
+            vote: Lockout::new_with_confirmation_count(2, 3),
+            slot: 5,
+        }));
+    }
+
+    #[test]
+    fn test_replay_tower_sync_roots() {
+        let mut simulator = TowerSimulator::new(slot_tree());
+        let tower_syncs = [
+            TowerSync::from(vec![(1, 2), (2, 1)]),
+            TowerSync {
+                root: Some(1),
+                ..TowerSync::from(vec![(2, 2), (3, 1)])
+            },
+            TowerSync::from(vec![(2, 3), (3, 2), (4, 1)]),
+        ];
+        let reports = simulator.replay_tower_syncs(&tower_syncs);
+        assert!(reports[1].violations.is_empty());
+        assert_eq!(reports[1].root, Some(1));
+        assert!(reports[1].root_advanced);
+        assert_eq!(
+            reports[1].lockouts,
+            Vec::from(tower_syncs[1].lockouts.clone())
+        );
+        assert_eq!(
+            reports[2].violations,
+            vec![TowerViolation::RootRollback {
+                root: 1,
+                proposed: None,
+            }]
+        );
+        // The rolled back root is kept
+        assert_eq!(reports[2].root, Some(1));
+    }
+}